@@ -0,0 +1,731 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::types::Value;
+use rusqlite::{params, params_from_iter, Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::tcp::TcpError;
+use crate::transcript::{Direction, TranscriptEntry, TranscriptSource};
+
+/// 1ページで返すフレーム数の既定値と上限
+const DEFAULT_PAGE_SIZE: usize = 100;
+const MAX_PAGE_SIZE: usize = 1000;
+/// 1回のトランザクションでまとめて書き込む件数
+const WRITE_BATCH_SIZE: usize = 500;
+/// 書き込みスレッドに渡す記録の上限（超えた分は破棄する）
+const WRITE_QUEUE_CAPACITY: usize = 10_000;
+/// 保持期間を過ぎたフレームを削除する間隔
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+/// 保持期間の既定値（日）
+const DEFAULT_RETENTION_DAYS: u64 = 30;
+/// 全文検索（trigram）で検索できる最短の文字数
+const MIN_FULL_TEXT_CHARS: usize = 3;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS frames (
+    id INTEGER PRIMARY KEY,
+    source_kind TEXT NOT NULL,
+    source_id TEXT NOT NULL,
+    client_id TEXT,
+    peer TEXT,
+    direction TEXT NOT NULL,
+    seq INTEGER NOT NULL,
+    timestamp TEXT NOT NULL,
+    timestamp_ms INTEGER NOT NULL,
+    bytes INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    raw BLOB NOT NULL
+);
+CREATE INDEX IF NOT EXISTS frames_source ON frames (source_kind, source_id, timestamp_ms);
+CREATE INDEX IF NOT EXISTS frames_timestamp ON frames (timestamp_ms);
+CREATE INDEX IF NOT EXISTS frames_peer ON frames (peer);
+CREATE VIRTUAL TABLE IF NOT EXISTS frames_fts USING fts5 (
+    text, content = 'frames', content_rowid = 'id', tokenize = 'trigram'
+);
+CREATE TRIGGER IF NOT EXISTS frames_insert AFTER INSERT ON frames BEGIN
+    INSERT INTO frames_fts (rowid, text) VALUES (new.id, new.text);
+END;
+CREATE TRIGGER IF NOT EXISTS frames_delete AFTER DELETE ON frames BEGIN
+    INSERT INTO frames_fts (frames_fts, rowid, text) VALUES ('delete', old.id, old.text);
+END;
+";
+
+/// アーカイブの検索条件（省略した条件では絞り込まない）
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ArchiveQuery {
+    /// 含まれる文字列（大文字・小文字は区別しない）
+    #[serde(default)]
+    pub text: Option<String>,
+    /// 正規表現
+    #[serde(default)]
+    pub regex: Option<String>,
+    #[serde(default)]
+    pub connection_id: Option<String>,
+    #[serde(default)]
+    pub server_id: Option<String>,
+    #[serde(default)]
+    pub direction: Option<Direction>,
+    /// 相手のアドレス（完全一致）
+    #[serde(default)]
+    pub peer: Option<String>,
+    /// 検索期間（RFC3339、fromは含みtoは含まない）
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    /// 前ページのnext_cursor
+    #[serde(default)]
+    pub cursor: Option<i64>,
+    /// 1ページの件数（既定100、最大1000）
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// アーカイブに保存したフレーム
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedFrame {
+    pub id: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub connection_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer: Option<String>,
+    pub direction: Direction,
+    pub seq: u64,
+    pub timestamp: String,
+    pub bytes: usize,
+    pub text: String,
+    pub base64: String,
+}
+
+/// アーカイブの状態
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveStatus {
+    /// 送受信を記録しているかどうか
+    pub enabled: bool,
+    pub retention_days: Option<u64>,
+    /// 書き込みが追いつかず破棄したフレームの数
+    pub dropped: u64,
+}
+
+/// 検索結果（古い順）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ArchiveSearchResult {
+    pub frames: Vec<ArchivedFrame>,
+    /// 続きがある場合に次のcursorに指定する値
+    pub next_cursor: Option<i64>,
+}
+
+struct ArchiveRecord {
+    source: TranscriptSource,
+    entry: TranscriptEntry,
+}
+
+/// 書き込みスレッドへの指示
+enum ArchiveCommand {
+    Insert(ArchiveRecord),
+    /// 保持期間を過ぎたフレームを削除し、削除した件数を返す
+    Cleanup(Option<u64>, mpsc::Sender<rusqlite::Result<usize>>),
+}
+
+/// 書き込みスレッドへの上限付きのキュー（送受信を止めないよう、溢れた記録は破棄して数える）
+struct ArchiveQueue {
+    sender: mpsc::SyncSender<ArchiveCommand>,
+    dropped: AtomicU64,
+}
+
+impl ArchiveQueue {
+    fn new(capacity: usize) -> (Self, mpsc::Receiver<ArchiveCommand>) {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let queue = Self {
+            sender,
+            dropped: AtomicU64::new(0),
+        };
+        (queue, receiver)
+    }
+
+    fn push(&self, record: ArchiveRecord) {
+        if self.sender.try_send(ArchiveCommand::Insert(record)).is_err() {
+            let dropped = self.dropped.fetch_add(1, Ordering::Relaxed) + 1;
+            // ログが溢れないよう、破棄した数が2の累乗になったときだけ警告する
+            if dropped.is_power_of_two() {
+                log::warn!("Archive writer is falling behind ({} frames dropped)", dropped);
+            }
+        }
+    }
+
+    fn dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+}
+
+/// 正規表現で検索できるようREGEXP演算子を定義する（パターンは文ごとに1回だけコンパイルする）
+fn register_regexp(connection: &Connection) -> rusqlite::Result<()> {
+    connection.create_scalar_function(
+        "regexp",
+        2,
+        FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC,
+        |ctx| {
+            type BoxError = Box<dyn std::error::Error + Send + Sync>;
+            let regex: Arc<Regex> =
+                ctx.get_or_create_aux(0, |pattern| -> Result<_, BoxError> { Ok(Regex::new(pattern.as_str()?)?) })?;
+            let text = ctx
+                .get_raw(1)
+                .as_str()
+                .map_err(|e| rusqlite::Error::UserFunctionError(e.into()))?;
+            Ok(regex.is_match(text))
+        },
+    )
+}
+
+fn direction_name(direction: Direction) -> &'static str {
+    match direction {
+        Direction::Tx => "tx",
+        Direction::Rx => "rx",
+        Direction::System => "system",
+    }
+}
+
+fn parse_direction(name: &str) -> Direction {
+    match name {
+        "tx" => Direction::Tx,
+        "rx" => Direction::Rx,
+        _ => Direction::System,
+    }
+}
+
+fn parse_time(value: &str) -> Result<i64, TcpError> {
+    DateTime::parse_from_rfc3339(value)
+        .map(|time| time.timestamp_millis())
+        .map_err(|e| TcpError::InvalidQuery(format!("Invalid time {}: {}", value, e)))
+}
+
+fn storage_error(e: rusqlite::Error) -> TcpError {
+    TcpError::StorageFailed(format!("Archive error: {}", e))
+}
+
+/// LIKEの特殊文字をエスケープする
+fn like_pattern(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+    format!("%{}%", escaped)
+}
+
+/// 送受信の記録を保存するSQLiteデータベース
+pub(crate) struct Archive {
+    connection: Connection,
+}
+
+impl Archive {
+    fn open(path: &Path) -> rusqlite::Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// 検索用に読み取り専用の接続を開く（書き込み用の接続でスキーマを作成した後に開く）
+    fn open_read_only(path: &Path) -> rusqlite::Result<Self> {
+        let connection = Connection::open_with_flags(
+            path,
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_URI | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+        )?;
+        register_regexp(&connection)?;
+        Ok(Self { connection })
+    }
+
+    fn init(connection: Connection) -> rusqlite::Result<Self> {
+        // 検索用の接続が読み取っている間も書き込めるようWALを使う（メモリ上のDBでは"memory"のまま）
+        connection.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        connection.pragma_update(None, "synchronous", "NORMAL")?;
+        connection.execute_batch(SCHEMA)?;
+        register_regexp(&connection)?;
+        Ok(Self { connection })
+    }
+
+    fn insert(&mut self, records: &[ArchiveRecord]) -> rusqlite::Result<()> {
+        let transaction = self.connection.transaction()?;
+        {
+            let mut statement = transaction.prepare_cached(
+                "INSERT INTO frames (source_kind, source_id, client_id, peer, direction, seq, timestamp, timestamp_ms, bytes, text, raw)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            )?;
+            for record in records {
+                let (kind, id) = match &record.source {
+                    TranscriptSource::Connection(id) => ("connection", id),
+                    TranscriptSource::Server(id) => ("server", id),
+                };
+                let entry = &record.entry;
+                let timestamp_ms = DateTime::parse_from_rfc3339(&entry.timestamp)
+                    .map(|time| time.timestamp_millis())
+                    .unwrap_or_else(|_| Utc::now().timestamp_millis());
+                statement.execute(params![
+                    kind,
+                    id,
+                    entry.client_id,
+                    entry.peer,
+                    direction_name(entry.direction),
+                    entry.seq as i64,
+                    entry.timestamp,
+                    timestamp_ms,
+                    entry.bytes as i64,
+                    entry.text,
+                    entry.raw,
+                ])?;
+            }
+        }
+        transaction.commit()
+    }
+
+    fn search(&self, query: &ArchiveQuery) -> Result<ArchiveSearchResult, TcpError> {
+        let mut sql = String::from(
+            "SELECT id, source_kind, source_id, client_id, peer, direction, seq, timestamp, bytes, text, raw
+             FROM frames WHERE 1 = 1",
+        );
+        let mut values: Vec<Value> = Vec::new();
+
+        if let Some(text) = query.text.as_deref().filter(|text| !text.is_empty()) {
+            if text.chars().count() >= MIN_FULL_TEXT_CHARS {
+                // フレーズとして検索するため二重引用符で囲む
+                sql.push_str(" AND id IN (SELECT rowid FROM frames_fts WHERE frames_fts MATCH ?)");
+                values.push(Value::Text(format!("\"{}\"", text.replace('"', "\"\""))));
+            } else {
+                sql.push_str(" AND text LIKE ? ESCAPE '\\'");
+                values.push(Value::Text(like_pattern(text)));
+            }
+        }
+        if let Some(pattern) = query.regex.as_deref().filter(|pattern| !pattern.is_empty()) {
+            Regex::new(pattern).map_err(|e| TcpError::InvalidQuery(format!("Invalid regex: {}", e)))?;
+            sql.push_str(" AND text REGEXP ?");
+            values.push(Value::Text(pattern.to_string()));
+        }
+        if let Some(connection_id) = &query.connection_id {
+            sql.push_str(" AND source_kind = 'connection' AND source_id = ?");
+            values.push(Value::Text(connection_id.clone()));
+        }
+        if let Some(server_id) = &query.server_id {
+            sql.push_str(" AND source_kind = 'server' AND source_id = ?");
+            values.push(Value::Text(server_id.clone()));
+        }
+        if let Some(direction) = query.direction {
+            sql.push_str(" AND direction = ?");
+            values.push(Value::Text(direction_name(direction).to_string()));
+        }
+        if let Some(peer) = &query.peer {
+            sql.push_str(" AND peer = ?");
+            values.push(Value::Text(peer.clone()));
+        }
+        if let Some(from) = &query.from {
+            sql.push_str(" AND timestamp_ms >= ?");
+            values.push(Value::Integer(parse_time(from)?));
+        }
+        if let Some(to) = &query.to {
+            sql.push_str(" AND timestamp_ms < ?");
+            values.push(Value::Integer(parse_time(to)?));
+        }
+        if let Some(cursor) = query.cursor {
+            sql.push_str(" AND id > ?");
+            values.push(Value::Integer(cursor));
+        }
+        // 続きがあるか判定するため1件多く取得する
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        sql.push_str(" ORDER BY id LIMIT ?");
+        values.push(Value::Integer(limit as i64 + 1));
+
+        let mut statement = self.connection.prepare(&sql).map_err(storage_error)?;
+        let rows = statement
+            .query_map(params_from_iter(values), |row| {
+                let kind: String = row.get(1)?;
+                let source_id: String = row.get(2)?;
+                let direction: String = row.get(5)?;
+                let raw: Vec<u8> = row.get(10)?;
+                Ok(ArchivedFrame {
+                    id: row.get(0)?,
+                    connection_id: (kind == "connection").then(|| source_id.clone()),
+                    server_id: (kind == "server").then_some(source_id),
+                    client_id: row.get(3)?,
+                    peer: row.get(4)?,
+                    direction: parse_direction(&direction),
+                    seq: row.get::<_, i64>(6)? as u64,
+                    timestamp: row.get(7)?,
+                    bytes: row.get::<_, i64>(8)? as usize,
+                    text: row.get(9)?,
+                    base64: BASE64_STANDARD.encode(raw),
+                })
+            })
+            .map_err(storage_error)?;
+        let mut frames = rows.collect::<rusqlite::Result<Vec<_>>>().map_err(storage_error)?;
+
+        let next_cursor = if frames.len() > limit {
+            frames.truncate(limit);
+            frames.last().map(|frame| frame.id)
+        } else {
+            None
+        };
+        Ok(ArchiveSearchResult { frames, next_cursor })
+    }
+
+    /// 指定した時刻より古いフレームを削除し、削除した件数を返す
+    fn remove_before(&self, cutoff_ms: i64) -> rusqlite::Result<usize> {
+        self.connection
+            .execute("DELETE FROM frames WHERE timestamp_ms < ?1", params![cutoff_ms])
+    }
+
+    /// 保持期間を過ぎたフレームを削除する
+    fn cleanup(&self, retention_days: Option<u64>) -> rusqlite::Result<usize> {
+        let Some(days) = retention_days else {
+            return Ok(0);
+        };
+        let cutoff = Utc::now().timestamp_millis() - (days as i64) * 24 * 60 * 60 * 1000;
+        let removed = self.remove_before(cutoff)?;
+        if removed > 0 {
+            log::info!("Removed {} archived frames older than {} days", removed, days);
+        }
+        Ok(removed)
+    }
+}
+
+struct ArchiveState {
+    /// 検索用の読み取り専用の接続（書き込み用の接続は書き込みスレッドが持つ）
+    reader: Arc<Mutex<Archive>>,
+    queue: ArchiveQueue,
+    retention_days: Arc<Mutex<Option<u64>>>,
+    enabled: AtomicBool,
+}
+
+// アプリのデータディレクトリに置くアーカイブ（起動時に開く）
+static ARCHIVE: std::sync::OnceLock<ArchiveState> = std::sync::OnceLock::new();
+
+/// アーカイブを開き、書き込みと保持期間の削除を行うスレッドを開始する
+pub fn init_archive(path: PathBuf) {
+    if let Some(dir) = path.parent() {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            log::error!("Failed to create archive directory {}: {}", dir.display(), e);
+            return;
+        }
+    }
+    // アーカイブが使えなくても送受信は続ける
+    let writer = match Archive::open(&path) {
+        Ok(archive) => archive,
+        Err(e) => {
+            log::error!("Failed to open archive {}: {}", path.display(), e);
+            return;
+        }
+    };
+    let reader = match Archive::open_read_only(&path) {
+        Ok(archive) => Arc::new(Mutex::new(archive)),
+        Err(e) => {
+            log::error!("Failed to open archive {} for search: {}", path.display(), e);
+            return;
+        }
+    };
+    let retention_days = Arc::new(Mutex::new(Some(DEFAULT_RETENTION_DAYS)));
+    let (queue, receiver) = ArchiveQueue::new(WRITE_QUEUE_CAPACITY);
+
+    let state = ArchiveState {
+        reader,
+        queue,
+        retention_days: Arc::clone(&retention_days),
+        enabled: AtomicBool::new(true),
+    };
+    if ARCHIVE.set(state).is_err() {
+        return;
+    }
+    std::thread::spawn(move || run_writer(writer, receiver, retention_days));
+}
+
+/// 記録をまとめて書き込み、定期的に保持期間を過ぎたフレームを削除する
+fn run_writer(
+    mut archive: Archive,
+    receiver: mpsc::Receiver<ArchiveCommand>,
+    retention_days: Arc<Mutex<Option<u64>>>,
+) {
+    let cleanup = |archive: &Archive| {
+        let days = *retention_days.lock().unwrap();
+        if let Err(e) = archive.cleanup(days) {
+            log::error!("Failed to clean up archive: {}", e);
+        }
+        Instant::now()
+    };
+    let mut last_cleanup = cleanup(&archive);
+    loop {
+        if last_cleanup.elapsed() >= CLEANUP_INTERVAL {
+            last_cleanup = cleanup(&archive);
+        }
+
+        let first = match receiver.recv_timeout(CLEANUP_INTERVAL) {
+            Ok(command) => command,
+            Err(mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(mpsc::RecvTimeoutError::Disconnected) => break,
+        };
+        let mut batch = Vec::new();
+        let mut next = Some(first);
+        while let Some(command) = next.take() {
+            match command {
+                ArchiveCommand::Insert(record) => batch.push(record),
+                ArchiveCommand::Cleanup(days, reply) => {
+                    // 削除より前に届いた記録を先に書き込む
+                    write_batch(&mut archive, &mut batch);
+                    reply.send(archive.cleanup(days)).ok();
+                }
+            }
+            if batch.len() >= WRITE_BATCH_SIZE {
+                break;
+            }
+            next = receiver.try_recv().ok();
+        }
+        write_batch(&mut archive, &mut batch);
+    }
+}
+
+fn write_batch(archive: &mut Archive, batch: &mut Vec<ArchiveRecord>) {
+    if batch.is_empty() {
+        return;
+    }
+    if let Err(e) = archive.insert(batch) {
+        log::error!("Failed to archive {} frames: {}", batch.len(), e);
+    }
+    batch.clear();
+}
+
+/// 記録をアーカイブに追加する（アーカイブが開けていないか、無効にしている場合は何もしない）
+pub(crate) fn record(source: &TranscriptSource, entry: &TranscriptEntry) {
+    if let Some(state) = ARCHIVE.get() {
+        if !state.enabled.load(Ordering::Relaxed) {
+            return;
+        }
+        state.queue.push(ArchiveRecord {
+            source: source.clone(),
+            entry: entry.clone(),
+        });
+    }
+}
+
+fn archive_state() -> Result<&'static ArchiveState, TcpError> {
+    ARCHIVE
+        .get()
+        .ok_or_else(|| TcpError::Unsupported("Archive is not available".to_string()))
+}
+
+/// アーカイブを検索
+#[tauri::command]
+pub async fn search_archive(query: ArchiveQuery) -> Result<ArchiveSearchResult, TcpError> {
+    let reader = Arc::clone(&archive_state()?.reader);
+    tokio::task::spawn_blocking(move || reader.lock().unwrap().search(&query))
+        .await
+        .map_err(|e| TcpError::StorageFailed(format!("Archive search failed: {}", e)))?
+}
+
+/// アーカイブの保持期間（日）を変更し、過ぎたフレームをすぐに削除して件数を返す（nullで無期限）
+#[tauri::command]
+pub async fn set_archive_retention(retention_days: Option<u64>) -> Result<usize, TcpError> {
+    let state = archive_state()?;
+    *state.retention_days.lock().unwrap() = retention_days;
+    // 削除は書き込み用の接続で行うため、書き込みスレッドに依頼して結果を待つ
+    let sender = state.queue.sender.clone();
+    tokio::task::spawn_blocking(move || {
+        let (reply, result) = mpsc::channel();
+        sender
+            .send(ArchiveCommand::Cleanup(retention_days, reply))
+            .map_err(|_| TcpError::StorageFailed("Archive writer has stopped".to_string()))?;
+        result
+            .recv()
+            .map_err(|_| TcpError::StorageFailed("Archive writer has stopped".to_string()))?
+            .map_err(storage_error)
+    })
+    .await
+    .map_err(|e| TcpError::StorageFailed(format!("Archive cleanup failed: {}", e)))?
+}
+
+/// 送受信のアーカイブへの記録を有効・無効にする（保存済みのフレームは残す）
+#[tauri::command]
+pub async fn set_archive_enabled(enabled: bool) -> Result<(), TcpError> {
+    archive_state()?.enabled.store(enabled, Ordering::Relaxed);
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_archive_status() -> Result<ArchiveStatus, TcpError> {
+    let state = archive_state()?;
+    Ok(ArchiveStatus {
+        enabled: state.enabled.load(Ordering::Relaxed),
+        retention_days: *state.retention_days.lock().unwrap(),
+        dropped: state.queue.dropped(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn archive_record(
+        source: TranscriptSource,
+        direction: Direction,
+        text: &str,
+        timestamp: &str,
+        peer: Option<&str>,
+    ) -> ArchiveRecord {
+        ArchiveRecord {
+            source,
+            entry: TranscriptEntry {
+                seq: 1,
+                direction,
+                timestamp: timestamp.to_string(),
+                bytes: text.len(),
+                text: text.to_string(),
+                raw: text.as_bytes().to_vec(),
+                wire: None,
+                client_id: None,
+                peer: peer.map(str::to_string),
+            },
+        }
+    }
+
+    fn sample_archive() -> Archive {
+        let mut archive = Archive::init(Connection::open_in_memory().unwrap()).unwrap();
+        let connection = || TranscriptSource::Connection("conn-1".to_string());
+        archive
+            .insert(&[
+                archive_record(connection(), Direction::Tx, "READ TEMP", "2024-05-01T10:00:00Z", None),
+                archive_record(connection(), Direction::Rx, "TEMP=21.5", "2024-05-01T10:00:01Z", None),
+                archive_record(connection(), Direction::Tx, "READ HUM", "2024-05-01T10:05:00Z", None),
+                archive_record(connection(), Direction::Rx, "ERROR 42: sensor busy", "2024-05-01T10:05:01Z", None),
+                archive_record(
+                    TranscriptSource::Server("default".to_string()),
+                    Direction::Rx,
+                    "error 7",
+                    "2024-05-02T09:00:00Z",
+                    Some("10.0.0.5:4000"),
+                ),
+            ])
+            .unwrap();
+        archive
+    }
+
+    fn texts(result: &ArchiveSearchResult) -> Vec<&str> {
+        result.frames.iter().map(|frame| frame.text.as_str()).collect()
+    }
+
+    #[test]
+    fn test_search_by_text_and_regex() {
+        let archive = sample_archive();
+
+        // 全文検索は大文字・小文字を区別しない
+        let query = ArchiveQuery { text: Some("error".to_string()), ..Default::default() };
+        assert_eq!(texts(&archive.search(&query).unwrap()), ["ERROR 42: sensor busy", "error 7"]);
+
+        // 3文字未満は部分一致で検索する
+        let query = ArchiveQuery { text: Some("=2".to_string()), ..Default::default() };
+        assert_eq!(texts(&archive.search(&query).unwrap()), ["TEMP=21.5"]);
+
+        let query = ArchiveQuery { regex: Some(r"^ERROR \d+".to_string()), ..Default::default() };
+        assert_eq!(texts(&archive.search(&query).unwrap()), ["ERROR 42: sensor busy"]);
+
+        let query = ArchiveQuery { regex: Some("(".to_string()), ..Default::default() };
+        assert!(matches!(archive.search(&query), Err(TcpError::InvalidQuery(_))));
+    }
+
+    #[test]
+    fn test_search_filters() {
+        let archive = sample_archive();
+
+        let query = ArchiveQuery {
+            connection_id: Some("conn-1".to_string()),
+            direction: Some(Direction::Tx),
+            ..Default::default()
+        };
+        assert_eq!(texts(&archive.search(&query).unwrap()), ["READ TEMP", "READ HUM"]);
+
+        let query = ArchiveQuery {
+            from: Some("2024-05-01T10:00:01Z".to_string()),
+            to: Some("2024-05-01T10:05:01Z".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(texts(&archive.search(&query).unwrap()), ["TEMP=21.5", "READ HUM"]);
+
+        let query = ArchiveQuery { peer: Some("10.0.0.5:4000".to_string()), ..Default::default() };
+        let result = archive.search(&query).unwrap();
+        assert_eq!(result.frames.len(), 1);
+        assert_eq!(result.frames[0].server_id.as_deref(), Some("default"));
+        assert_eq!(result.frames[0].base64, "ZXJyb3IgNw==");
+    }
+
+    #[test]
+    fn test_search_pagination() {
+        let archive = sample_archive();
+        let mut query = ArchiveQuery { limit: Some(2), ..Default::default() };
+        let mut pages = Vec::new();
+        loop {
+            let result = archive.search(&query).unwrap();
+            pages.push(result.frames.len());
+            match result.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        assert_eq!(pages, [2, 2, 1]);
+    }
+
+    #[test]
+    fn test_remove_expired_frames() {
+        let archive = sample_archive();
+        let cutoff = parse_time("2024-05-01T10:05:00Z").unwrap();
+        assert_eq!(archive.remove_before(cutoff).unwrap(), 2);
+        assert_eq!(archive.search(&ArchiveQuery::default()).unwrap().frames.len(), 3);
+
+        // 削除したフレームは全文検索にも残らない
+        let query = ArchiveQuery { text: Some("TEMP".to_string()), ..Default::default() };
+        assert!(archive.search(&query).unwrap().frames.is_empty());
+
+        // 保持期間を指定しない場合は削除しない
+        assert_eq!(archive.cleanup(None).unwrap(), 0);
+        assert_eq!(archive.cleanup(Some(1)).unwrap(), 3);
+    }
+
+    #[test]
+    fn test_queue_drops_records_when_full() {
+        let (queue, receiver) = ArchiveQueue::new(2);
+        for text in ["one", "two", "three", "four"] {
+            let source = TranscriptSource::Connection("conn-1".to_string());
+            queue.push(archive_record(source, Direction::Rx, text, "2024-05-01T10:00:00Z", None));
+        }
+        assert_eq!(queue.dropped(), 2);
+
+        // 溢れる前の記録は順に届く
+        let texts: Vec<String> = receiver
+            .try_iter()
+            .map(|command| match command {
+                ArchiveCommand::Insert(record) => record.entry.text,
+                ArchiveCommand::Cleanup(..) => unreachable!(),
+            })
+            .collect();
+        assert_eq!(texts, ["one", "two"]);
+    }
+
+    #[test]
+    fn test_read_only_connection_searches_file() {
+        let path = std::env::temp_dir().join(format!("archive-test-{}.sqlite3", std::process::id()));
+        let mut writer = Archive::open(&path).unwrap();
+        let reader = Archive::open_read_only(&path).unwrap();
+
+        let source = TranscriptSource::Connection("conn-1".to_string());
+        writer
+            .insert(&[archive_record(source, Direction::Rx, "ERROR 42", "2024-05-01T10:00:00Z", None)])
+            .unwrap();
+        let query = ArchiveQuery { regex: Some(r"^ERROR \d+".to_string()), ..Default::default() };
+        assert_eq!(texts(&reader.search(&query).unwrap()), ["ERROR 42"]);
+
+        // 検索用の接続からは書き込めない
+        assert!(reader.remove_before(i64::MAX).is_err());
+
+        drop((writer, reader));
+        for suffix in ["", "-wal", "-shm"] {
+            std::fs::remove_file(format!("{}{}", path.display(), suffix)).ok();
+        }
+    }
+}
@@ -0,0 +1,562 @@
+use bytes::BytesMut;
+use serde::{Deserialize, Serialize};
+use std::io;
+use tokio_util::codec::Decoder;
+
+use crate::text_encoding::TextEncoding;
+
+/// 1フレームとして受け付ける最大バイト数
+const MAX_FRAME_LEN: usize = 1024 * 1024;
+
+const STX: u8 = 0x02;
+const ETX: u8 = 0x03;
+
+const SLIP_END: u8 = 0xC0;
+const SLIP_ESC: u8 = 0xDB;
+const SLIP_ESC_END: u8 = 0xDC;
+const SLIP_ESC_ESC: u8 = 0xDD;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Endianness {
+    #[default]
+    Big,
+    Little,
+}
+
+/// 送受信時のメッセージ区切り方式
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Framing {
+    /// LF区切り（受信時は末尾のCRも除去する従来の動作）
+    #[default]
+    Line,
+    Cr,
+    Lf,
+    CrLf,
+    /// STX(0x02)で始まりETX(0x03)で終わるフレーム
+    StxEtx,
+    /// 任意のバイト列を区切りとして使用
+    Delimiter { delimiter: Vec<u8> },
+    /// 先頭にペイロード長（1/2/4バイト）を付与
+    LengthPrefixed {
+        width: u8,
+        #[serde(default)]
+        endianness: Endianness,
+    },
+    /// 固定長レコード
+    FixedLength { length: usize },
+    /// RFC 1055 SLIP
+    Slip,
+    /// Consistent Overhead Byte Stuffing（0x00区切り）
+    Cobs,
+    /// 区切りなし（受信したバイト列をそのまま扱う）
+    Raw,
+}
+
+impl Framing {
+    /// 設定値の妥当性をチェック
+    ///
+    /// UTF-16では区切りをコード単位の境界でしか探さないため、任意の区切りは2バイト単位であること
+    pub fn validate(&self, encoding: TextEncoding) -> Result<(), String> {
+        match self {
+            Framing::Delimiter { delimiter } if delimiter.is_empty() => {
+                Err("Delimiter must not be empty".to_string())
+            }
+            Framing::Delimiter { delimiter } if delimiter.len() % encoding.code_unit_len() != 0 => Err(format!(
+                "Delimiter must be a whole number of {} code units ({} bytes)",
+                encoding.label(),
+                delimiter.len()
+            )),
+            Framing::LengthPrefixed { width, .. } if !matches!(width, 1 | 2 | 4) => {
+                Err(format!("Length prefix width must be 1, 2 or 4 (got {})", width))
+            }
+            Framing::FixedLength { length } if *length == 0 || *length > MAX_FRAME_LEN => {
+                Err(format!("Fixed length must be between 1 and {}", MAX_FRAME_LEN))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// 区切り方式の場合の区切りバイト列
+    ///
+    /// 改行系の区切りは文字エンコーディングで符号化する（UTF-16では1文字2バイト）
+    fn delimiter(&self, encoding: TextEncoding) -> Option<Vec<u8>> {
+        match self {
+            Framing::Line | Framing::Lf => Some(encode_control("\n", encoding)),
+            Framing::Cr => Some(encode_control("\r", encoding)),
+            Framing::CrLf => Some(encode_control("\r\n", encoding)),
+            Framing::Delimiter { delimiter } => Some(delimiter.clone()),
+            _ => None,
+        }
+    }
+
+    /// ペイロードを送信用のバイト列に変換
+    pub fn encode(&self, payload: &[u8], encoding: TextEncoding) -> io::Result<Vec<u8>> {
+        let mut out = Vec::with_capacity(payload.len() + 4);
+        if let Some(delimiter) = self.delimiter(encoding) {
+            out.extend_from_slice(payload);
+            out.extend_from_slice(&delimiter);
+            return Ok(out);
+        }
+        match self {
+            Framing::StxEtx => {
+                out.push(STX);
+                out.extend_from_slice(payload);
+                out.push(ETX);
+            }
+            Framing::LengthPrefixed { width, endianness } => {
+                let len = payload.len();
+                let max = match width {
+                    1 => u8::MAX as usize,
+                    2 => u16::MAX as usize,
+                    _ => u32::MAX as usize,
+                };
+                if len > max {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Payload of {} bytes does not fit in a {}-byte length prefix", len, width),
+                    ));
+                }
+                let bytes = (len as u32).to_be_bytes();
+                let mut prefix = bytes[4 - *width as usize..].to_vec();
+                if *endianness == Endianness::Little {
+                    prefix.reverse();
+                }
+                out.extend_from_slice(&prefix);
+                out.extend_from_slice(payload);
+            }
+            Framing::FixedLength { length } => {
+                if payload.len() != *length {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("Payload must be exactly {} bytes (got {})", length, payload.len()),
+                    ));
+                }
+                out.extend_from_slice(payload);
+            }
+            Framing::Slip => {
+                out.push(SLIP_END);
+                for &b in payload {
+                    match b {
+                        SLIP_END => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_END]),
+                        SLIP_ESC => out.extend_from_slice(&[SLIP_ESC, SLIP_ESC_ESC]),
+                        _ => out.push(b),
+                    }
+                }
+                out.push(SLIP_END);
+            }
+            Framing::Cobs => {
+                cobs_encode(payload, &mut out);
+                out.push(0x00);
+            }
+            Framing::Raw => out.extend_from_slice(payload),
+            Framing::Line | Framing::Cr | Framing::Lf | Framing::CrLf | Framing::Delimiter { .. } => {
+                unreachable!("delimited framings are handled above")
+            }
+        }
+        Ok(out)
+    }
+}
+
+/// 受信したフレーム
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    /// 区切りやエスケープを取り除いたデータ
+    pub payload: Vec<u8>,
+    /// 回線上で受信したままのバイト列（区切り・長さ・エスケープと、直前に読み捨てたバイトを含む）
+    pub wire: Vec<u8>,
+}
+
+/// 受信ストリームを`Framing`に従ってフレームへ分割するデコーダー
+///
+/// 空のフレームは返さず、その回線上のバイト列は次のフレームに含める
+pub struct FrameCodec {
+    framing: Framing,
+    /// 区切り方式の場合の区切りバイト列
+    delimiter: Option<Vec<u8>>,
+    /// `Line`で末尾から取り除くCR
+    carriage_return: Vec<u8>,
+    /// 区切りを探す単位（UTF-16では2バイト）
+    code_unit_len: usize,
+    /// 次のフレームの`wire`に含める、読み捨てたバイト列
+    skipped: Vec<u8>,
+    /// 区切りを探し終えたバッファ先頭からのバイト数（次回はここから探す）
+    scanned: usize,
+}
+
+impl FrameCodec {
+    pub fn new(framing: Framing, encoding: TextEncoding) -> Self {
+        Self {
+            delimiter: framing.delimiter(encoding),
+            carriage_return: encode_control("\r", encoding),
+            code_unit_len: encoding.code_unit_len(),
+            framing,
+            skipped: Vec::new(),
+            scanned: 0,
+        }
+    }
+
+    /// 読み捨てたバイト列を残す（フレームが来ないまま溜まり続けないよう上限を超えた古い分は捨てる）
+    fn skip(&mut self, bytes: &[u8]) {
+        self.skipped.extend_from_slice(bytes);
+        if self.skipped.len() > MAX_FRAME_LEN {
+            let excess = self.skipped.len() - MAX_FRAME_LEN;
+            self.skipped.drain(..excess);
+        }
+    }
+
+    /// 先頭のフレームを探し、復号したデータとバッファから消費するバイト数を返す
+    fn next_frame(&mut self, buf: &mut BytesMut) -> io::Result<Option<(Vec<u8>, usize)>> {
+        if let Some(delimiter) = &self.delimiter {
+            let start = self.scanned;
+            let found = find(&buf[start..], delimiter, self.code_unit_len).map(|pos| start + pos);
+            return Ok(match found {
+                Some(pos) => {
+                    self.scanned = 0;
+                    let mut frame = buf[..pos].to_vec();
+                    if self.framing == Framing::Line && frame.ends_with(&self.carriage_return) {
+                        frame.truncate(frame.len() - self.carriage_return.len());
+                    }
+                    Some((frame, pos + delimiter.len()))
+                }
+                // 区切りが来ないまま上限に達した場合はそこまでを1フレームとする
+                None if buf.len() >= MAX_FRAME_LEN => {
+                    self.scanned = 0;
+                    Some((buf[..MAX_FRAME_LEN].to_vec(), MAX_FRAME_LEN))
+                }
+                // 区切りが途中まで届いている可能性があるため、その先頭になり得る位置から再開する
+                None => {
+                    let resume = buf.len().saturating_sub(delimiter.len() - 1);
+                    self.scanned = resume - resume % self.code_unit_len;
+                    None
+                }
+            });
+        }
+
+        match &self.framing {
+            Framing::StxEtx => {
+                // STXより前のゴミは読み捨てる
+                let start = buf.iter().position(|&b| b == STX).unwrap_or(buf.len());
+                let noise = buf.split_to(start);
+                self.skip(&noise);
+                if buf.is_empty() {
+                    return Ok(None);
+                }
+                match buf.iter().position(|&b| b == ETX) {
+                    Some(end) => Ok(Some((buf[1..end].to_vec(), end + 1))),
+                    None if buf.len() > MAX_FRAME_LEN => Err(frame_too_large(buf.len())),
+                    None => Ok(None),
+                }
+            }
+            Framing::LengthPrefixed { width, endianness } => {
+                let width = *width as usize;
+                if buf.len() < width {
+                    return Ok(None);
+                }
+                let mut prefix = buf[..width].to_vec();
+                if *endianness == Endianness::Little {
+                    prefix.reverse();
+                }
+                let len = prefix.iter().fold(0usize, |acc, &b| (acc << 8) | b as usize);
+                if len > MAX_FRAME_LEN {
+                    return Err(frame_too_large(len));
+                }
+                if buf.len() < width + len {
+                    buf.reserve(width + len - buf.len());
+                    return Ok(None);
+                }
+                Ok(Some((buf[width..width + len].to_vec(), width + len)))
+            }
+            Framing::FixedLength { length } => {
+                if buf.len() < *length {
+                    return Ok(None);
+                }
+                Ok(Some((buf[..*length].to_vec(), *length)))
+            }
+            // 連続するENDは空フレームになり、次のフレームに含めて返す
+            Framing::Slip => match buf.iter().position(|&b| b == SLIP_END) {
+                Some(end) => Ok(Some((slip_decode(&buf[..end])?, end + 1))),
+                None if buf.len() > MAX_FRAME_LEN => Err(frame_too_large(buf.len())),
+                None => Ok(None),
+            },
+            Framing::Cobs => match buf.iter().position(|&b| b == 0x00) {
+                Some(end) => Ok(Some((cobs_decode(&buf[..end])?, end + 1))),
+                None if buf.len() > MAX_FRAME_LEN => Err(frame_too_large(buf.len())),
+                None => Ok(None),
+            },
+            Framing::Raw => {
+                if buf.is_empty() {
+                    Ok(None)
+                } else {
+                    Ok(Some((buf.to_vec(), buf.len())))
+                }
+            }
+            Framing::Line | Framing::Cr | Framing::Lf | Framing::CrLf | Framing::Delimiter { .. } => {
+                unreachable!("delimited framings are handled above")
+            }
+        }
+    }
+}
+
+impl FrameCodec {
+    /// UDPのデータグラムのように、1回で完結するバッファをフレームに分割する
+    pub fn split_datagram(&mut self, datagram: &[u8]) -> io::Result<Vec<Frame>> {
+        let mut buf = BytesMut::from(datagram);
+        let mut frames = Vec::new();
+        while let Some(frame) = self.decode_eof(&mut buf)? {
+            frames.push(frame);
+        }
+        Ok(frames)
+    }
+}
+
+impl Decoder for FrameCodec {
+    type Item = Frame;
+    type Error = io::Error;
+
+    fn decode(&mut self, buf: &mut BytesMut) -> io::Result<Option<Frame>> {
+        while let Some((payload, len)) = self.next_frame(buf)? {
+            let wire = buf.split_to(len);
+            if payload.is_empty() {
+                self.skip(&wire);
+                continue;
+            }
+            let mut skipped = std::mem::take(&mut self.skipped);
+            skipped.extend_from_slice(&wire);
+            return Ok(Some(Frame { payload, wire: skipped }));
+        }
+        Ok(None)
+    }
+
+    fn decode_eof(&mut self, buf: &mut BytesMut) -> io::Result<Option<Frame>> {
+        if let Some(frame) = self.decode(buf)? {
+            return Ok(Some(frame));
+        }
+        if buf.is_empty() {
+            return Ok(None);
+        }
+        // 区切り文字ベースの方式では、最後の未終端データもフレームとして扱う
+        if self.delimiter.is_some() {
+            self.scanned = 0;
+            let payload = buf.split().to_vec();
+            let mut wire = std::mem::take(&mut self.skipped);
+            wire.extend_from_slice(&payload);
+            return Ok(Some(Frame { payload, wire }));
+        }
+        log::warn!("Discarding {} bytes of incomplete frame at end of stream", buf.len());
+        buf.clear();
+        Ok(None)
+    }
+}
+
+/// `unit`バイトごとの境界から始まる`needle`の位置を探す
+fn find(haystack: &[u8], needle: &[u8], unit: usize) -> Option<usize> {
+    if haystack.len() < needle.len() {
+        return None;
+    }
+    (0..=haystack.len() - needle.len())
+        .step_by(unit)
+        .find(|&pos| haystack[pos..].starts_with(needle))
+}
+
+/// 改行などの制御文字を文字エンコーディングで符号化する
+fn encode_control(text: &str, encoding: TextEncoding) -> Vec<u8> {
+    encoding.encode(text).unwrap_or_else(|_| text.as_bytes().to_vec())
+}
+
+fn frame_too_large(len: usize) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("Frame of {} bytes exceeds the {} byte limit", len, MAX_FRAME_LEN),
+    )
+}
+
+fn slip_decode(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut iter = raw.iter();
+    while let Some(&b) = iter.next() {
+        if b != SLIP_ESC {
+            out.push(b);
+            continue;
+        }
+        match iter.next() {
+            Some(&SLIP_ESC_END) => out.push(SLIP_END),
+            Some(&SLIP_ESC_ESC) => out.push(SLIP_ESC),
+            other => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid SLIP escape sequence: {:02X?}", other),
+                ))
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn cobs_encode(payload: &[u8], out: &mut Vec<u8>) {
+    let mut code_index = out.len();
+    let mut code = 1u8;
+    out.push(0);
+    for &b in payload {
+        if b == 0 {
+            out[code_index] = code;
+            code_index = out.len();
+            code = 1;
+            out.push(0);
+        } else {
+            out.push(b);
+            code += 1;
+            if code == 0xFF {
+                out[code_index] = code;
+                code_index = out.len();
+                code = 1;
+                out.push(0);
+            }
+        }
+    }
+    out[code_index] = code;
+}
+
+fn cobs_decode(raw: &[u8]) -> io::Result<Vec<u8>> {
+    let mut out = Vec::with_capacity(raw.len());
+    let mut i = 0;
+    while i < raw.len() {
+        let code = raw[i] as usize;
+        if code == 0 || i + code > raw.len() {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Invalid COBS frame"));
+        }
+        out.extend_from_slice(&raw[i + 1..i + code]);
+        i += code;
+        if code < 0xFF && i < raw.len() {
+            out.push(0);
+        }
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decode_all(framing: Framing, input: &[u8]) -> Vec<Vec<u8>> {
+        let mut codec = FrameCodec::new(framing, TextEncoding::Utf8);
+        let mut buf = BytesMut::from(input);
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode_eof(&mut buf).unwrap() {
+            frames.push(frame.payload);
+        }
+        frames
+    }
+
+    #[test]
+    fn test_line_framing_strips_cr() {
+        let frames = decode_all(Framing::Line, b"hello\r\nworld\npartial");
+        assert_eq!(frames, vec![b"hello".to_vec(), b"world".to_vec(), b"partial".to_vec()]);
+    }
+
+    #[test]
+    fn test_custom_delimiter_roundtrip() {
+        let framing = Framing::Delimiter { delimiter: b"||".to_vec() };
+        let encoded = framing.encode(b"abc", TextEncoding::Utf8).unwrap();
+        assert_eq!(encoded, b"abc||");
+        assert_eq!(decode_all(framing, b"abc||def||"), vec![b"abc".to_vec(), b"def".to_vec()]);
+    }
+
+    #[test]
+    fn test_delimiter_split_across_reads() {
+        // 1バイトずつ届いても、途中まで届いた区切りや探し終えた位置を引き継いで分割できる
+        let cases = [
+            (Framing::Delimiter { delimiter: b"|||".to_vec() }, TextEncoding::Utf8, "ab||c|||d|||", ["ab||c", "d"]),
+            (Framing::CrLf, TextEncoding::Utf16Le, "x\ry\r\nz\r\n", ["x\ry", "z"]),
+        ];
+        for (framing, encoding, input, expected) in cases {
+            let mut codec = FrameCodec::new(framing, encoding);
+            let mut buf = BytesMut::new();
+            let mut frames = Vec::new();
+            for byte in encoding.encode(input).unwrap() {
+                buf.extend_from_slice(&[byte]);
+                while let Some(frame) = codec.decode(&mut buf).unwrap() {
+                    frames.push(encoding.decode(&frame.payload).0);
+                }
+            }
+            assert!(buf.is_empty());
+            assert_eq!(frames, expected);
+        }
+    }
+
+    #[test]
+    fn test_length_prefixed_waits_for_full_frame() {
+        let framing = Framing::LengthPrefixed { width: 2, endianness: Endianness::Little };
+        let encoded = framing.encode(b"ABC", TextEncoding::Utf8).unwrap();
+        assert_eq!(encoded, vec![0x03, 0x00, b'A', b'B', b'C']);
+
+        let mut codec = FrameCodec::new(framing, TextEncoding::Utf8);
+        let mut buf = BytesMut::from(&encoded[..4]);
+        assert_eq!(codec.decode(&mut buf).unwrap(), None);
+        buf.extend_from_slice(&encoded[4..]);
+        let frame = codec.decode(&mut buf).unwrap().unwrap();
+        assert_eq!(frame.payload, b"ABC");
+        assert_eq!(frame.wire, encoded);
+    }
+
+    #[test]
+    fn test_stx_etx_skips_noise() {
+        let frames = decode_all(Framing::StxEtx, b"xx\x02one\x03\x02two\x03");
+        assert_eq!(frames, vec![b"one".to_vec(), b"two".to_vec()]);
+    }
+
+    #[test]
+    fn test_wire_keeps_framing_and_skipped_bytes() {
+        let mut codec = FrameCodec::new(Framing::StxEtx, TextEncoding::Utf8);
+        let frames = codec.split_datagram(b"xx\x02one\x03\x02two\x03").unwrap();
+        let wires: Vec<&[u8]> = frames.iter().map(|frame| frame.wire.as_slice()).collect();
+        assert_eq!(wires, [&b"xx\x02one\x03"[..], &b"\x02two\x03"[..]]);
+
+        // 空行は返さず、次のフレームの回線上のバイト列に含める
+        let mut codec = FrameCodec::new(Framing::Line, TextEncoding::Utf8);
+        let frames = codec.split_datagram(b"\r\nstatus\r\n").unwrap();
+        assert_eq!(frames, [Frame { payload: b"status".to_vec(), wire: b"\r\nstatus\r\n".to_vec() }]);
+
+        let mut codec = FrameCodec::new(Framing::Slip, TextEncoding::Utf8);
+        let encoded = Framing::Slip.encode(&[SLIP_END], TextEncoding::Utf8).unwrap();
+        let frames = codec.split_datagram(&encoded).unwrap();
+        assert_eq!(frames, [Frame { payload: vec![SLIP_END], wire: encoded }]);
+    }
+
+    #[test]
+    fn test_slip_and_cobs_roundtrip() {
+        let payload = vec![0x00, 0xC0, 0x11, 0xDB, 0x00];
+        for framing in [Framing::Slip, Framing::Cobs] {
+            let encoded = framing.encode(&payload, TextEncoding::Utf8).unwrap();
+            assert_eq!(decode_all(framing, &encoded), vec![payload.clone()]);
+        }
+    }
+
+    #[test]
+    fn test_validate_rejects_bad_settings() {
+        assert!(Framing::Delimiter { delimiter: vec![] }.validate(TextEncoding::Utf8).is_err());
+        assert!(Framing::LengthPrefixed { width: 3, endianness: Endianness::Big }.validate(TextEncoding::Utf8).is_err());
+        assert!(Framing::FixedLength { length: 8 }.validate(TextEncoding::Utf8).is_ok());
+        assert!(Framing::Delimiter { delimiter: vec![0x0A] }.validate(TextEncoding::Utf16Le).is_err());
+        assert!(Framing::Delimiter { delimiter: vec![0x0A, 0x00] }.validate(TextEncoding::Utf16Le).is_ok());
+    }
+
+    #[test]
+    fn test_utf16_line_matches_code_units_only() {
+        let encoding = TextEncoding::Utf16Le;
+        let encoded = Framing::CrLf.encode(&encoding.encode("ab").unwrap(), encoding).unwrap();
+        assert_eq!(encoded, vec![b'a', 0x00, b'b', 0x00, b'\r', 0x00, b'\n', 0x00]);
+
+        // "\u{0A0D}"（0D 0A）と"\u{0A00}"（00 0A）はコード単位の途中にCR/LFのバイトを含む
+        let mut input = encoding.encode("\u{0A0D}\u{0A00}x\r\ny\n").unwrap();
+        input.extend_from_slice(&encoding.encode("z").unwrap());
+        let mut codec = FrameCodec::new(Framing::Line, encoding);
+        let mut buf = BytesMut::from(&input[..]);
+        let mut frames = Vec::new();
+        while let Some(frame) = codec.decode_eof(&mut buf).unwrap() {
+            frames.push(encoding.decode(&frame.payload).0);
+        }
+        assert_eq!(frames, vec!["\u{0A0D}\u{0A00}x", "y", "z"]);
+    }
+}
@@ -1,4 +1,24 @@
+use tauri::Manager;
+
+mod archive;
+mod framing;
+mod message_store;
+mod payload;
+mod pcapng;
+mod serial;
+mod session_log;
+mod socket_options;
 mod tcp;
+mod tcp_info;
+#[cfg(test)]
+mod test_support;
+mod text_encoding;
+mod timeouts;
+mod tls;
+mod transcript;
+mod udp;
+mod unix;
+mod ws;
 mod settings;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -8,6 +28,9 @@ pub fn run() {
     .setup(|app| {
       // TCP機能用にAppHandleを初期化
       tcp::init_app_handle(app.handle().clone());
+      let app_data_dir = app.path().app_data_dir()?;
+      session_log::init_log_dir(app_data_dir.join("session_logs"));
+      archive::init_archive(app_data_dir.join("archive.sqlite3"));
       
       if cfg!(debug_assertions) {
         app.handle().plugin(
@@ -22,11 +45,56 @@ pub fn run() {
         tcp::send_tcp_message,
         tcp::start_tcp_server,
         tcp::stop_tcp_server,
+        tcp::remove_tcp_server,
+        tcp::list_tcp_servers,
         tcp::get_received_messages,
+        tcp::get_received_messages_since,
+        tcp::get_received_messages_page,
+        tcp::set_server_retention,
         tcp::connect_tcp,
         tcp::disconnect_tcp,
+        tcp::list_connections,
+        tcp::get_connection,
         tcp::send_tcp_message_on_connection,
+        tcp::preview_payload,
+        tcp::get_tls_info,
         tcp::get_received_messages_from_connection,
+        tcp::get_received_messages_page_from_connection,
+        tcp::set_connection_retention,
+        tcp::list_server_clients,
+        tcp::send_to_server_client,
+        tcp::broadcast_to_server_clients,
+        tcp::disconnect_server_client,
+        tcp_info::get_tcp_info,
+        tcp_info::start_tcp_info_sampling,
+        tcp_info::stop_tcp_info_sampling,
+        tcp_info::get_tcp_info_history,
+        transcript::get_connection_transcript,
+        transcript::get_server_transcript,
+        transcript::remove_connection_transcript,
+        transcript::remove_server_transcript,
+        session_log::start_connection_session_log,
+        session_log::start_server_session_log,
+        session_log::stop_connection_session_log,
+        session_log::stop_server_session_log,
+        session_log::list_session_logs,
+        session_log::open_session_log,
+        archive::search_archive,
+        archive::set_archive_retention,
+        archive::set_archive_enabled,
+        archive::get_archive_status,
+        pcapng::export_connection_pcapng,
+        pcapng::export_server_pcapng,
+        udp::connect_udp,
+        udp::join_multicast,
+        unix::connect_unix,
+        unix::start_unix_server,
+        ws::connect_websocket,
+        ws::get_websocket_info,
+        serial::list_serial_ports,
+        serial::connect_serial,
+        serial::set_serial_control_lines,
+        serial::get_serial_line_status,
         settings::open_settings_window,
         settings::close_settings_window,
         settings::is_settings_window_open
@@ -0,0 +1,270 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::tcp::{next_message_seq, TcpReceivedMessage};
+
+/// 1ページで返すメッセージ数の既定値と上限
+const DEFAULT_PAGE_SIZE: usize = 500;
+const MAX_PAGE_SIZE: usize = 5000;
+
+fn default_max_messages() -> Option<usize> {
+    Some(10_000)
+}
+
+fn default_max_bytes() -> Option<usize> {
+    Some(64 * 1024 * 1024)
+}
+
+/// 受信メッセージと送受信記録の保持ポリシー（いずれかを超えたら古いものから破棄する。nullで無制限）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// 保持するメッセージ数の上限
+    #[serde(default = "default_max_messages")]
+    pub max_messages: Option<usize>,
+    /// 保持する生バイト列の合計の上限
+    #[serde(default = "default_max_bytes")]
+    pub max_bytes: Option<usize>,
+    /// 受信してから保持する時間（ミリ秒）
+    #[serde(default)]
+    pub max_age_ms: Option<u64>,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            max_messages: default_max_messages(),
+            max_bytes: default_max_bytes(),
+            max_age_ms: None,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// 保持している件数・バイト数・最も古いものの経過時間のいずれかが上限を超えているか
+    pub(crate) fn exceeded(&self, count: usize, bytes: usize, oldest_age: Duration) -> bool {
+        self.max_messages.is_some_and(|max| count > max)
+            || self.max_bytes.is_some_and(|max| bytes > max)
+            || self.max_age_ms.is_some_and(|max| oldest_age > Duration::from_millis(max))
+    }
+}
+
+/// ページ単位の取得条件
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MessageQuery {
+    /// この通し番号より後のメッセージから返す（前ページのnext_cursor）
+    #[serde(default)]
+    pub after_seq: Option<u64>,
+    /// この通し番号までのメッセージを返す
+    #[serde(default)]
+    pub until_seq: Option<u64>,
+    /// 1ページの件数（既定500、最大5000）
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// ページ単位の取得結果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MessagePage {
+    pub messages: Vec<TcpReceivedMessage>,
+    /// 続きがある場合に次のafter_seqに指定する通し番号
+    pub next_cursor: Option<u64>,
+    pub has_more: bool,
+    /// 保持ポリシーにより破棄したメッセージの累計
+    pub dropped: u64,
+    /// 保持しているメッセージの件数とバイト数
+    pub retained: usize,
+    pub retained_bytes: usize,
+}
+
+struct StoredMessage {
+    received_at: Instant,
+    message: TcpReceivedMessage,
+}
+
+/// 接続・サーバーの受信メッセージを保持するリングバッファ
+pub struct MessageStore {
+    policy: RetentionPolicy,
+    messages: VecDeque<StoredMessage>,
+    bytes: usize,
+    dropped: u64,
+}
+
+pub(crate) type SharedMessageStore = Arc<Mutex<MessageStore>>;
+
+impl MessageStore {
+    pub fn new(policy: RetentionPolicy) -> Self {
+        Self {
+            policy,
+            messages: VecDeque::new(),
+            bytes: 0,
+            dropped: 0,
+        }
+    }
+
+    pub(crate) fn shared(policy: RetentionPolicy) -> SharedMessageStore {
+        Arc::new(Mutex::new(Self::new(policy)))
+    }
+
+    /// メッセージに通し番号を割り当てて保存し、採番したメッセージを返す
+    ///
+    /// 保存順と通し番号の順序を一致させるため、呼び出し側はロックを保持したまま呼ぶ
+    pub fn push(&mut self, mut message: TcpReceivedMessage) -> TcpReceivedMessage {
+        message.seq = next_message_seq();
+        self.bytes += message.raw.len();
+        self.messages.push_back(StoredMessage {
+            received_at: Instant::now(),
+            message: message.clone(),
+        });
+        self.evict(Instant::now());
+        message
+    }
+
+    /// 保持ポリシーを変更し、超えている分をすぐに破棄する
+    pub fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+        self.evict(Instant::now());
+    }
+
+    /// 保持しているメッセージ数
+    pub fn retained_count(&mut self) -> usize {
+        self.evict(Instant::now());
+        self.messages.len()
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some(oldest) = self.messages.front() {
+            let age = now.duration_since(oldest.received_at);
+            if !self.policy.exceeded(self.messages.len(), self.bytes, age) {
+                break;
+            }
+            if let Some(evicted) = self.messages.pop_front() {
+                self.bytes -= evicted.message.raw.len();
+                self.dropped += 1;
+            }
+        }
+    }
+
+    /// 保持しているメッセージをすべて取得
+    pub fn all(&mut self) -> Vec<TcpReceivedMessage> {
+        self.evict(Instant::now());
+        self.messages.iter().map(|stored| stored.message.clone()).collect()
+    }
+
+    /// 指定した通し番号より後のメッセージをすべて取得
+    pub fn since(&mut self, since_seq: u64) -> Vec<TcpReceivedMessage> {
+        self.evict(Instant::now());
+        let start = self.start_index(since_seq);
+        self.messages.range(start..).map(|stored| stored.message.clone()).collect()
+    }
+
+    /// 条件に合うメッセージを1ページ分取得
+    pub fn page(&mut self, query: &MessageQuery) -> MessagePage {
+        self.evict(Instant::now());
+        let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE).clamp(1, MAX_PAGE_SIZE);
+        let start = query.after_seq.map_or(0, |seq| self.start_index(seq));
+
+        let mut matching = self
+            .messages
+            .range(start..)
+            .map(|stored| &stored.message)
+            .take_while(|message| !matches!(query.until_seq, Some(until) if message.seq > until));
+        let messages: Vec<TcpReceivedMessage> = matching.by_ref().take(limit).cloned().collect();
+        let has_more = matching.next().is_some();
+
+        MessagePage {
+            next_cursor: if has_more { messages.last().map(|message| message.seq) } else { None },
+            messages,
+            has_more,
+            dropped: self.dropped,
+            retained: self.messages.len(),
+            retained_bytes: self.bytes,
+        }
+    }
+
+    /// 通し番号は追加順に増加するため二分探索で開始位置を求める
+    fn start_index(&self, after_seq: u64) -> usize {
+        self.messages.partition_point(|stored| stored.message.seq <= after_seq)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::text_encoding::TextEncoding;
+
+    fn message(text: &str) -> TcpReceivedMessage {
+        TcpReceivedMessage::from_frame(text.as_bytes().to_vec(), "peer".to_string(), TextEncoding::Utf8)
+    }
+
+    #[test]
+    fn test_evicts_by_count_and_bytes() {
+        let mut store = MessageStore::new(RetentionPolicy {
+            max_messages: Some(3),
+            max_bytes: Some(10),
+            max_age_ms: None,
+        });
+        for text in ["aa", "bb", "cc", "dd"] {
+            store.push(message(text));
+        }
+        let texts: Vec<String> = store.all().into_iter().map(|m| m.message).collect();
+        assert_eq!(texts, ["bb", "cc", "dd"]);
+        assert_eq!(store.dropped(), 1);
+
+        // バイト数の上限を超えた分も古いものから破棄する
+        store.push(message("eeeeeee"));
+        let texts: Vec<String> = store.all().into_iter().map(|m| m.message).collect();
+        assert_eq!(texts, ["dd", "eeeeeee"]);
+        assert_eq!(store.dropped(), 3);
+    }
+
+    #[test]
+    fn test_evicts_by_age() {
+        let mut store = MessageStore::new(RetentionPolicy {
+            max_age_ms: Some(20),
+            ..Default::default()
+        });
+        store.push(message("old"));
+        std::thread::sleep(Duration::from_millis(40));
+        store.push(message("new"));
+        let texts: Vec<String> = store.all().into_iter().map(|m| m.message).collect();
+        assert_eq!(texts, ["new"]);
+        assert_eq!(store.dropped(), 1);
+    }
+
+    #[test]
+    fn test_page_with_cursor_and_range() {
+        let mut store = MessageStore::new(RetentionPolicy::default());
+        let seqs: Vec<u64> = (0..5).map(|i| store.push(message(&i.to_string())).seq).collect();
+
+        let first = store.page(&MessageQuery { limit: Some(2), ..Default::default() });
+        assert_eq!(first.messages.len(), 2);
+        assert!(first.has_more);
+        assert_eq!(first.next_cursor, Some(seqs[1]));
+
+        let second = store.page(&MessageQuery {
+            after_seq: first.next_cursor,
+            until_seq: Some(seqs[3]),
+            limit: Some(10),
+        });
+        let texts: Vec<&str> = second.messages.iter().map(|m| m.message.as_str()).collect();
+        assert_eq!(texts, ["2", "3"]);
+        assert!(!second.has_more);
+        assert_eq!(second.next_cursor, None);
+        assert_eq!(second.retained, 5);
+        assert_eq!(second.dropped, 0);
+    }
+
+    #[test]
+    fn test_policy_null_means_unlimited() {
+        let policy: RetentionPolicy = serde_json::from_str(r#"{"max_messages": null}"#).unwrap();
+        assert_eq!(policy.max_messages, None);
+        assert_eq!(policy.max_bytes, default_max_bytes());
+    }
+}
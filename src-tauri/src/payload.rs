@@ -0,0 +1,260 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+
+use crate::text_encoding::TextEncoding;
+
+/// 送信メッセージの入力形式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadFormat {
+    /// 文字列を接続の文字エンコーディングで送信
+    #[default]
+    Text,
+    /// 16進表記（例: "02 41 42 03"）
+    Hex,
+    /// C言語風のエスケープシーケンス（例: "\x02\r\n"）
+    Escaped,
+    Base64,
+}
+
+/// ペイロードの解析エラー
+///
+/// `position` は入力文字列中の文字単位の位置
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadError {
+    pub position: usize,
+    pub character: Option<char>,
+    pub reason: String,
+}
+
+impl PayloadError {
+    fn new(position: usize, character: Option<char>, reason: impl Into<String>) -> Self {
+        Self {
+            position,
+            character,
+            reason: reason.into(),
+        }
+    }
+}
+
+impl fmt::Display for PayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.character {
+            Some(c) => write!(f, "{} at position {} ({:?})", self.reason, self.position, c),
+            None => write!(f, "{} at position {}", self.reason, self.position),
+        }
+    }
+}
+
+impl Error for PayloadError {}
+
+/// 入力文字列を指定形式で解析し、送信するバイト列を返す
+///
+/// テキストおよびエスケープ以外の文字は`encoding`でエンコードする
+pub fn parse_payload(input: &str, format: PayloadFormat, encoding: TextEncoding) -> Result<Vec<u8>, PayloadError> {
+    match format {
+        PayloadFormat::Text => encoding.encode(input),
+        PayloadFormat::Hex => parse_hex(input),
+        PayloadFormat::Escaped => parse_escaped(input, encoding),
+        PayloadFormat::Base64 => parse_base64(input),
+    }
+}
+
+fn is_hex_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, ',' | ':' | '-')
+}
+
+fn parse_hex(input: &str) -> Result<Vec<u8>, PayloadError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = Vec::new();
+    // 上位ニブルを読み終えて下位ニブル待ちの状態（値と位置）
+    let mut pending: Option<(u8, usize)> = None;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if is_hex_separator(c) {
+            if let Some((_, pos)) = pending {
+                return Err(PayloadError::new(pos, Some(chars[pos]), "Incomplete hex byte"));
+            }
+            i += 1;
+            continue;
+        }
+        // トークン先頭の "0x" プレフィックスは読み飛ばす
+        let token_start = i == 0 || is_hex_separator(chars[i - 1]);
+        if token_start && c == '0' && matches!(chars.get(i + 1), Some('x') | Some('X')) {
+            i += 2;
+            continue;
+        }
+        let digit = c
+            .to_digit(16)
+            .ok_or_else(|| PayloadError::new(i, Some(c), "Invalid hex digit"))? as u8;
+        match pending.take() {
+            Some((high, _)) => out.push((high << 4) | digit),
+            None => pending = Some((digit, i)),
+        }
+        i += 1;
+    }
+
+    if let Some((_, pos)) = pending {
+        return Err(PayloadError::new(pos, Some(chars[pos]), "Incomplete hex byte"));
+    }
+    Ok(out)
+}
+
+fn parse_escaped(input: &str, encoding: TextEncoding) -> Result<Vec<u8>, PayloadError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut out = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            let encoded = encoding
+                .encode(c.encode_utf8(&mut buf))
+                .map_err(|e| PayloadError { position: i, ..e })?;
+            out.extend_from_slice(&encoded);
+            i += 1;
+            continue;
+        }
+
+        let Some(&escape) = chars.get(i + 1) else {
+            return Err(PayloadError::new(i, Some(c), "Unterminated escape sequence"));
+        };
+        i += 2;
+        match escape {
+            'a' => out.push(0x07),
+            'b' => out.push(0x08),
+            't' => out.push(b'\t'),
+            'n' => out.push(b'\n'),
+            'v' => out.push(0x0B),
+            'f' => out.push(0x0C),
+            'r' => out.push(b'\r'),
+            'e' => out.push(0x1B),
+            '\\' => out.push(b'\\'),
+            '\'' => out.push(b'\''),
+            '"' => out.push(b'"'),
+            '?' => out.push(b'?'),
+            'x' => {
+                let mut value = 0u8;
+                for offset in 0..2 {
+                    let pos = i + offset;
+                    let digit = chars
+                        .get(pos)
+                        .and_then(|c| c.to_digit(16))
+                        .ok_or_else(|| {
+                            PayloadError::new(pos, chars.get(pos).copied(), "Expected two hex digits after \\x")
+                        })?;
+                    value = (value << 4) | digit as u8;
+                }
+                out.push(value);
+                i += 2;
+            }
+            '0'..='7' => {
+                // 最大3桁の8進数
+                let mut value = escape.to_digit(8).unwrap();
+                let mut digits = 1;
+                while digits < 3 {
+                    match chars.get(i).and_then(|c| c.to_digit(8)) {
+                        Some(digit) => {
+                            value = value * 8 + digit;
+                            digits += 1;
+                            i += 1;
+                        }
+                        None => break,
+                    }
+                }
+                if value > 0xFF {
+                    return Err(PayloadError::new(i - digits - 1, Some('\\'), "Octal escape out of range"));
+                }
+                out.push(value as u8);
+            }
+            other => {
+                return Err(PayloadError::new(i - 1, Some(other), "Unknown escape sequence"));
+            }
+        }
+    }
+    Ok(out)
+}
+
+fn parse_base64(input: &str) -> Result<Vec<u8>, PayloadError> {
+    // 空白を除去しつつ元の文字位置を記録しておく
+    let (positions, filtered): (Vec<usize>, String) = input
+        .chars()
+        .enumerate()
+        .filter(|(_, c)| !c.is_whitespace())
+        .unzip();
+
+    BASE64_STANDARD.decode(&filtered).map_err(|e| {
+        let index = match e {
+            base64::DecodeError::InvalidByte(offset, _) => offset,
+            base64::DecodeError::InvalidLastSymbol(offset, _) => offset,
+            _ => filtered.chars().count().saturating_sub(1),
+        };
+        let position = positions.get(index).copied().unwrap_or(0);
+        PayloadError::new(position, input.chars().nth(position), format!("Invalid Base64: {}", e))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_payload("02 41 42 03", PayloadFormat::Hex, TextEncoding::Utf8).unwrap(), vec![0x02, 0x41, 0x42, 0x03]);
+        assert_eq!(parse_payload("0x0d,0x0A", PayloadFormat::Hex, TextEncoding::Utf8).unwrap(), vec![0x0D, 0x0A]);
+        assert_eq!(parse_payload("0241", PayloadFormat::Hex, TextEncoding::Utf8).unwrap(), vec![0x02, 0x41]);
+    }
+
+    #[test]
+    fn test_parse_hex_reports_offending_character() {
+        let err = parse_payload("02 4G", PayloadFormat::Hex, TextEncoding::Utf8).unwrap_err();
+        assert_eq!(err.position, 4);
+        assert_eq!(err.character, Some('G'));
+
+        let err = parse_payload("02 4 41", PayloadFormat::Hex, TextEncoding::Utf8).unwrap_err();
+        assert_eq!(err.position, 3);
+        assert_eq!(err.reason, "Incomplete hex byte");
+    }
+
+    #[test]
+    fn test_parse_escaped() {
+        assert_eq!(
+            parse_payload("\\x02AB\\x03\\r\\n", PayloadFormat::Escaped, TextEncoding::Utf8).unwrap(),
+            vec![0x02, b'A', b'B', 0x03, b'\r', b'\n']
+        );
+        assert_eq!(parse_payload("\\0\\101\\\\", PayloadFormat::Escaped, TextEncoding::Utf8).unwrap(), vec![0x00, b'A', b'\\']);
+
+        let err = parse_payload("ab\\q", PayloadFormat::Escaped, TextEncoding::Utf8).unwrap_err();
+        assert_eq!(err.position, 3);
+        assert_eq!(err.character, Some('q'));
+
+        let err = parse_payload("\\x0", PayloadFormat::Escaped, TextEncoding::Utf8).unwrap_err();
+        assert_eq!(err.position, 3);
+        assert_eq!(err.character, None);
+    }
+
+    #[test]
+    fn test_escaped_literals_use_encoding() {
+        assert_eq!(
+            parse_payload("\\x02あ", PayloadFormat::Escaped, TextEncoding::ShiftJis).unwrap(),
+            vec![0x02, 0x82, 0xA0]
+        );
+
+        let err = parse_payload("\\r€", PayloadFormat::Escaped, TextEncoding::Iso8859_1).unwrap_err();
+        assert_eq!(err.position, 2);
+    }
+
+    #[test]
+    fn test_parse_base64() {
+        assert_eq!(parse_payload("AkH/ Aw==", PayloadFormat::Base64, TextEncoding::Utf8).unwrap(), vec![0x02, 0x41, 0xFF, 0x03]);
+
+        let err = parse_payload("Ak H*", PayloadFormat::Base64, TextEncoding::Utf8).unwrap_err();
+        assert_eq!(err.position, 4);
+        assert_eq!(err.character, Some('*'));
+    }
+}
@@ -0,0 +1,551 @@
+use chrono::DateTime;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+
+use crate::tcp::{server_id_or_default, TcpError};
+use crate::transcript::{self, Direction, EndpointSpan, Endpoints, TranscriptEntry, TranscriptSource};
+
+/// PCAPNGのブロック種別
+const SECTION_HEADER_BLOCK: u32 = 0x0A0D_0D0A;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+const BYTE_ORDER_MAGIC: u32 = 0x1A2B_3C4D;
+/// IPヘッダから始まるパケット（IPv4/IPv6はバージョンで判別される）
+const LINKTYPE_RAW: u16 = 101;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_PSH: u8 = 0x08;
+const TCP_ACK: u8 = 0x10;
+/// 1セグメントに入れるペイロードの上限（IPの全長が16ビットに収まるようにする）
+const MAX_SEGMENT_PAYLOAD: usize = 65_000;
+/// 合成するシーケンス番号の初期値（Wiresharkは相対値で表示する）
+const LOCAL_INITIAL_SEQ: u32 = 1_000;
+const REMOTE_INITIAL_SEQ: u32 = 5_000;
+
+/// PCAPNGの書き出し結果
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PcapExportResult {
+    pub path: String,
+    /// 書き出したパケット数（合成したハンドシェイクを含む）
+    pub packets: usize,
+    /// 書き出した送受信フレーム数
+    pub frames: usize,
+    /// 両端のアドレスが分からず書き出せなかったフレーム数
+    pub skipped: usize,
+}
+
+/// 1つのTCP接続のシーケンス番号
+struct Flow {
+    endpoints: Endpoints,
+    local_seq: u32,
+    remote_seq: u32,
+}
+
+/// PCAPNGのデータを組み立てる
+struct PcapngWriter {
+    buffer: Vec<u8>,
+    packets: usize,
+    identification: u16,
+}
+
+impl PcapngWriter {
+    fn new() -> Self {
+        let mut writer = Self {
+            buffer: Vec::new(),
+            packets: 0,
+            identification: 0,
+        };
+
+        let mut section = Vec::new();
+        section.extend_from_slice(&BYTE_ORDER_MAGIC.to_le_bytes());
+        section.extend_from_slice(&1u16.to_le_bytes());
+        section.extend_from_slice(&0u16.to_le_bytes());
+        // セクション長は不明（-1）
+        section.extend_from_slice(&(-1i64).to_le_bytes());
+        writer.block(SECTION_HEADER_BLOCK, &section);
+
+        let mut interface = Vec::new();
+        interface.extend_from_slice(&LINKTYPE_RAW.to_le_bytes());
+        interface.extend_from_slice(&0u16.to_le_bytes());
+        // スナップ長の制限なし（タイムスタンプは既定のマイクロ秒単位）
+        interface.extend_from_slice(&0u32.to_le_bytes());
+        writer.block(INTERFACE_DESCRIPTION_BLOCK, &interface);
+        writer
+    }
+
+    fn block(&mut self, block_type: u32, body: &[u8]) {
+        let padding = (4 - body.len() % 4) % 4;
+        let total_length = (12 + body.len() + padding) as u32;
+        self.buffer.extend_from_slice(&block_type.to_le_bytes());
+        self.buffer.extend_from_slice(&total_length.to_le_bytes());
+        self.buffer.extend_from_slice(body);
+        self.buffer.resize(self.buffer.len() + padding, 0);
+        self.buffer.extend_from_slice(&total_length.to_le_bytes());
+    }
+
+    fn packet(&mut self, timestamp_us: u64, data: &[u8]) {
+        let mut body = Vec::with_capacity(20 + data.len());
+        body.extend_from_slice(&0u32.to_le_bytes());
+        body.extend_from_slice(&((timestamp_us >> 32) as u32).to_le_bytes());
+        body.extend_from_slice(&(timestamp_us as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        body.extend_from_slice(data);
+        self.block(ENHANCED_PACKET_BLOCK, &body);
+        self.packets += 1;
+    }
+
+    fn segment(&mut self, timestamp_us: u64, segment: TcpSegment) {
+        self.identification = self.identification.wrapping_add(1);
+        let packet = ip_packet(&segment, self.identification);
+        self.packet(timestamp_us, &packet);
+    }
+
+    /// 3ウェイハンドシェイクを合成する
+    fn handshake(&mut self, timestamp_us: u64, flow: &Flow) {
+        let Endpoints { local, remote, initiated_locally } = flow.endpoints;
+        let (client, server, client_seq, server_seq) = if initiated_locally {
+            (local, remote, flow.local_seq, flow.remote_seq)
+        } else {
+            (remote, local, flow.remote_seq, flow.local_seq)
+        };
+        self.segment(timestamp_us, TcpSegment::control(client, server, client_seq.wrapping_sub(1), 0, TCP_SYN));
+        self.segment(
+            timestamp_us,
+            TcpSegment::control(server, client, server_seq.wrapping_sub(1), client_seq, TCP_SYN | TCP_ACK),
+        );
+        self.segment(timestamp_us, TcpSegment::control(client, server, client_seq, server_seq, TCP_ACK));
+    }
+
+    /// 送受信したデータを向きに合わせたセグメントにする
+    fn data(&mut self, timestamp_us: u64, flow: &mut Flow, direction: Direction, payload: &[u8]) {
+        let Endpoints { local, remote, .. } = flow.endpoints;
+        for chunk in payload.chunks(MAX_SEGMENT_PAYLOAD) {
+            let segment = if direction == Direction::Tx {
+                let segment = TcpSegment::control(local, remote, flow.local_seq, flow.remote_seq, TCP_PSH | TCP_ACK);
+                flow.local_seq = flow.local_seq.wrapping_add(chunk.len() as u32);
+                segment
+            } else {
+                let segment = TcpSegment::control(remote, local, flow.remote_seq, flow.local_seq, TCP_PSH | TCP_ACK);
+                flow.remote_seq = flow.remote_seq.wrapping_add(chunk.len() as u32);
+                segment
+            };
+            self.segment(timestamp_us, TcpSegment { payload: chunk, ..segment });
+        }
+    }
+
+    /// こちらから接続を閉じたことにする（再接続で両端が変わった場合）
+    fn close(&mut self, timestamp_us: u64, flow: &Flow) {
+        let Endpoints { local, remote, .. } = flow.endpoints;
+        let (local_seq, remote_seq) = (flow.local_seq, flow.remote_seq);
+        self.segment(timestamp_us, TcpSegment::control(local, remote, local_seq, remote_seq, TCP_FIN | TCP_ACK));
+        self.segment(
+            timestamp_us,
+            TcpSegment::control(remote, local, remote_seq, local_seq.wrapping_add(1), TCP_FIN | TCP_ACK),
+        );
+        self.segment(
+            timestamp_us,
+            TcpSegment::control(local, remote, local_seq.wrapping_add(1), remote_seq.wrapping_add(1), TCP_ACK),
+        );
+    }
+}
+
+/// 1の補数和によるチェックサム
+fn checksum(chunks: &[&[u8]]) -> u16 {
+    let mut sum: u32 = 0;
+    let mut pending: Option<u8> = None;
+    for byte in chunks.iter().flat_map(|chunk| chunk.iter().copied()) {
+        match pending.take() {
+            Some(high) => sum += u32::from(u16::from_be_bytes([high, byte])),
+            None => pending = Some(byte),
+        }
+    }
+    if let Some(high) = pending {
+        sum += u32::from(u16::from_be_bytes([high, 0]));
+    }
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// IPv4-mapped IPv6アドレスを戻し、両端のアドレスファミリーを揃える
+fn normalize(src: SocketAddr, dst: SocketAddr) -> (IpAddr, IpAddr) {
+    let canonical = |ip: IpAddr| match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        v4 => v4,
+    };
+    let to_v6 = |ip: IpAddr| match ip {
+        IpAddr::V4(v4) => IpAddr::V6(v4.to_ipv6_mapped()),
+        v6 => v6,
+    };
+    match (canonical(src.ip()), canonical(dst.ip())) {
+        (src @ IpAddr::V4(_), dst @ IpAddr::V4(_)) | (src @ IpAddr::V6(_), dst @ IpAddr::V6(_)) => (src, dst),
+        (src, dst) => (to_v6(src), to_v6(dst)),
+    }
+}
+
+/// 合成するTCPセグメント
+#[derive(Clone, Copy)]
+struct TcpSegment<'a> {
+    src: SocketAddr,
+    dst: SocketAddr,
+    seq: u32,
+    ack: u32,
+    flags: u8,
+    payload: &'a [u8],
+}
+
+impl TcpSegment<'_> {
+    /// ペイロードのないセグメント
+    fn control(src: SocketAddr, dst: SocketAddr, seq: u32, ack: u32, flags: u8) -> Self {
+        Self {
+            src,
+            dst,
+            seq,
+            ack,
+            flags,
+            payload: &[],
+        }
+    }
+}
+
+/// TCPセグメントを含むIPパケットを組み立てる
+fn ip_packet(segment: &TcpSegment, identification: u16) -> Vec<u8> {
+    let TcpSegment { src, dst, seq, ack, flags, payload } = *segment;
+    let mut tcp = Vec::with_capacity(20 + payload.len());
+    tcp.extend_from_slice(&src.port().to_be_bytes());
+    tcp.extend_from_slice(&dst.port().to_be_bytes());
+    tcp.extend_from_slice(&seq.to_be_bytes());
+    tcp.extend_from_slice(&ack.to_be_bytes());
+    // データオフセット5（20バイト、オプションなし）
+    tcp.push(5 << 4);
+    tcp.push(flags);
+    tcp.extend_from_slice(&u16::MAX.to_be_bytes());
+    tcp.extend_from_slice(&[0, 0, 0, 0]);
+    tcp.extend_from_slice(payload);
+    let tcp_length = tcp.len() as u32;
+
+    let mut packet = Vec::with_capacity(40 + tcp.len());
+    match normalize(src, dst) {
+        (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) => {
+            let pseudo = [&src_ip.octets()[..], &dst_ip.octets(), &[0, 6], &(tcp_length as u16).to_be_bytes()].concat();
+            let tcp_checksum = checksum(&[&pseudo, &tcp]);
+            tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+
+            let mut header = Vec::with_capacity(20);
+            header.push(0x45);
+            header.push(0);
+            header.extend_from_slice(&((20 + tcp_length) as u16).to_be_bytes());
+            header.extend_from_slice(&identification.to_be_bytes());
+            // Don't Fragment
+            header.extend_from_slice(&0x4000u16.to_be_bytes());
+            header.push(64);
+            header.push(6);
+            header.extend_from_slice(&[0, 0]);
+            header.extend_from_slice(&src_ip.octets());
+            header.extend_from_slice(&dst_ip.octets());
+            let header_checksum = checksum(&[&header]);
+            header[10..12].copy_from_slice(&header_checksum.to_be_bytes());
+            packet.extend_from_slice(&header);
+        }
+        (IpAddr::V6(src_ip), IpAddr::V6(dst_ip)) => {
+            let pseudo = [&src_ip.octets()[..], &dst_ip.octets(), &tcp_length.to_be_bytes(), &[0, 0, 0, 6]].concat();
+            let tcp_checksum = checksum(&[&pseudo, &tcp]);
+            tcp[16..18].copy_from_slice(&tcp_checksum.to_be_bytes());
+
+            packet.extend_from_slice(&0x6000_0000u32.to_be_bytes());
+            packet.extend_from_slice(&(tcp_length as u16).to_be_bytes());
+            packet.push(6);
+            packet.push(64);
+            packet.extend_from_slice(&src_ip.octets());
+            packet.extend_from_slice(&dst_ip.octets());
+        }
+        _ => unreachable!("normalize returns addresses of the same family"),
+    }
+    packet.extend_from_slice(&tcp);
+    packet
+}
+
+fn timestamp_us(entry: &TranscriptEntry) -> u64 {
+    DateTime::parse_from_rfc3339(&entry.timestamp)
+        .map(|time| time.timestamp_micros().max(0) as u64)
+        .unwrap_or(0)
+}
+
+/// 記録した送受信をPCAPNGにする（systemの記録は含めない）
+fn build(entries: &[TranscriptEntry], spans: &[EndpointSpan]) -> (Vec<u8>, PcapExportResult) {
+    let mut writer = PcapngWriter::new();
+    // サーバーの場合はクライアントごとに別のTCP接続になる
+    let mut flows: HashMap<Option<String>, (usize, Flow)> = HashMap::new();
+    let mut frames = 0;
+    let mut skipped = 0;
+
+    for entry in entries.iter().filter(|entry| entry.direction != Direction::System && !entry.wire_bytes().is_empty()) {
+        let span = spans
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(_, span)| span.client_id == entry.client_id && span.after_seq < entry.seq);
+        let Some((span_index, span)) = span else {
+            skipped += 1;
+            continue;
+        };
+        let timestamp = timestamp_us(entry);
+
+        let current = flows.get(&entry.client_id).map(|(index, _)| *index);
+        if current != Some(span_index) {
+            // 再接続した場合は前の接続を閉じてから新しい接続を始める
+            if let Some((_, previous)) = flows.remove(&entry.client_id) {
+                writer.close(timestamp, &previous);
+            }
+            let flow = Flow {
+                endpoints: span.endpoints,
+                local_seq: LOCAL_INITIAL_SEQ,
+                remote_seq: REMOTE_INITIAL_SEQ,
+            };
+            writer.handshake(timestamp, &flow);
+            flows.insert(entry.client_id.clone(), (span_index, flow));
+        }
+        if let Some((_, flow)) = flows.get_mut(&entry.client_id) {
+            writer.data(timestamp, flow, entry.direction, entry.wire_bytes());
+            frames += 1;
+        }
+    }
+
+    let result = PcapExportResult {
+        path: String::new(),
+        packets: writer.packets,
+        frames,
+        skipped,
+    };
+    (writer.buffer, result)
+}
+
+fn export(source: TranscriptSource, path: String, not_found: String) -> Result<PcapExportResult, TcpError> {
+    let (entries, spans) = transcript::snapshot(&source).ok_or(TcpError::ConnectionNotFound(not_found))?;
+    if spans.is_empty() {
+        return Err(TcpError::Unsupported(
+            "No TCP/IP endpoints were recorded for this transcript".to_string(),
+        ));
+    }
+    let (data, mut result) = build(&entries, &spans);
+    std::fs::write(&path, data)
+        .map_err(|e| TcpError::StorageFailed(format!("Failed to write {}: {}", path, e)))?;
+    result.path = path;
+    Ok(result)
+}
+
+/// 接続の送受信記録をPCAPNGファイルに書き出す
+///
+/// ペイロードは記録したフレーム（フレーミングの区切りを除き、TLSは復号後）をそのまま使う
+#[tauri::command]
+pub async fn export_connection_pcapng(connection_id: String, path: String) -> Result<PcapExportResult, TcpError> {
+    let not_found = format!("Connection with ID {} not found", connection_id);
+    export(TranscriptSource::Connection(connection_id), path, not_found)
+}
+
+/// サーバーの送受信記録をPCAPNGファイルに書き出す（クライアントごとに別のTCP接続になる）
+#[tauri::command]
+pub async fn export_server_pcapng(server_id: Option<String>, path: String) -> Result<PcapExportResult, TcpError> {
+    let server_id = server_id_or_default(server_id);
+    let not_found = format!("Server with ID {} not found", server_id);
+    export(TranscriptSource::Server(server_id), path, not_found)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(seq: u64, direction: Direction, text: &str, client_id: Option<&str>) -> TranscriptEntry {
+        TranscriptEntry {
+            seq,
+            direction,
+            timestamp: "2024-05-01T10:00:00.250Z".to_string(),
+            bytes: text.len(),
+            text: text.to_string(),
+            raw: text.as_bytes().to_vec(),
+            wire: None,
+            client_id: client_id.map(str::to_string),
+            peer: None,
+        }
+    }
+
+    fn span(after_seq: u64, local: &str, remote: &str, initiated_locally: bool, client_id: Option<&str>) -> EndpointSpan {
+        EndpointSpan {
+            after_seq,
+            client_id: client_id.map(str::to_string),
+            endpoints: Endpoints {
+                local: local.parse().unwrap(),
+                remote: remote.parse().unwrap(),
+                initiated_locally,
+            },
+        }
+    }
+
+    /// PCAPNGのブロックを(種別, 本体)に分解する
+    fn blocks(data: &[u8]) -> Vec<(u32, &[u8])> {
+        let mut blocks = Vec::new();
+        let mut offset = 0;
+        while offset < data.len() {
+            let block_type = u32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+            let length = u32::from_le_bytes(data[offset + 4..offset + 8].try_into().unwrap()) as usize;
+            let trailer = u32::from_le_bytes(data[offset + length - 4..offset + length].try_into().unwrap()) as usize;
+            assert_eq!(length, trailer);
+            assert_eq!(length % 4, 0);
+            blocks.push((block_type, &data[offset + 8..offset + length - 4]));
+            offset += length;
+        }
+        blocks
+    }
+
+    /// Enhanced Packet Blockからパケットを取り出す
+    fn packet(body: &[u8]) -> &[u8] {
+        let length = u32::from_le_bytes(body[12..16].try_into().unwrap()) as usize;
+        &body[20..20 + length]
+    }
+
+    #[test]
+    fn test_connection_export() {
+        let entries = [
+            entry(10, Direction::System, "connected", None),
+            entry(11, Direction::Tx, "PING", None),
+            entry(12, Direction::Rx, "PONG!", None),
+        ];
+        let spans = [span(9, "192.168.1.10:50000", "192.168.1.20:8080", true, None)];
+        let (data, result) = build(&entries, &spans);
+        assert_eq!((result.packets, result.frames, result.skipped), (5, 2, 0));
+
+        let blocks = blocks(&data);
+        assert_eq!(blocks[0].0, SECTION_HEADER_BLOCK);
+        assert_eq!(&blocks[0].1[..4], &BYTE_ORDER_MAGIC.to_le_bytes());
+        assert_eq!(blocks[1].0, INTERFACE_DESCRIPTION_BLOCK);
+        assert_eq!(u16::from_le_bytes(blocks[1].1[..2].try_into().unwrap()), LINKTYPE_RAW);
+        let packets: Vec<&[u8]> = blocks[2..].iter().map(|(_, body)| packet(body)).collect();
+        assert_eq!(packets.len(), 5);
+
+        // タイムスタンプはマイクロ秒
+        let body = blocks[2].1;
+        let high = u32::from_le_bytes(body[4..8].try_into().unwrap()) as u64;
+        let low = u32::from_le_bytes(body[8..12].try_into().unwrap()) as u64;
+        assert_eq!((high << 32) | low, 1_714_557_600_250_000);
+
+        // こちらから接続したのでSYNはローカルから
+        let syn = packets[0];
+        assert_eq!(syn[33], TCP_SYN);
+        assert_eq!(&syn[12..16], &[192, 168, 1, 10]);
+
+        // 送信はローカル→リモート、受信はリモート→ローカル
+        let tx = packets[3];
+        assert_eq!(&tx[12..16], &[192, 168, 1, 10]);
+        assert_eq!(u16::from_be_bytes([tx[20], tx[21]]), 50000);
+        assert_eq!(&tx[40..], b"PING");
+        let rx = packets[4];
+        assert_eq!(&rx[12..16], &[192, 168, 1, 20]);
+        assert_eq!(u16::from_be_bytes([rx[22], rx[23]]), 50000);
+        assert_eq!(&rx[40..], b"PONG!");
+        // 受信の確認応答番号は送信した分だけ進む
+        assert_eq!(u32::from_be_bytes(rx[28..32].try_into().unwrap()), LOCAL_INITIAL_SEQ + 4);
+
+        // 正しいチェックサムを含めて計算すると0になる
+        for packet in &packets {
+            assert_eq!(checksum(&[&packet[..20]]), 0);
+            let pseudo = [&packet[12..20], &[0, 6], &((packet.len() - 20) as u16).to_be_bytes()[..]].concat();
+            assert_eq!(checksum(&[&pseudo, &packet[20..]]), 0);
+        }
+    }
+
+    #[test]
+    fn test_server_export_with_ipv6_and_unknown_client() {
+        let entries = [
+            entry(21, Direction::Rx, "hello", Some("a")),
+            entry(22, Direction::Rx, "lost", Some("unknown")),
+            entry(23, Direction::Tx, "welcome", Some("a")),
+        ];
+        let spans = [span(20, "[::1]:9000", "[::1]:41000", false, Some("a"))];
+        let (data, result) = build(&entries, &spans);
+        assert_eq!((result.packets, result.frames, result.skipped), (5, 2, 1));
+
+        let blocks = blocks(&data);
+        let packets: Vec<&[u8]> = blocks[2..].iter().map(|(_, body)| packet(body)).collect();
+        // 相手から接続したのでSYNはリモート（ポート41000）から
+        let syn = packets[0];
+        assert_eq!(syn[0] >> 4, 6);
+        assert_eq!(u16::from_be_bytes([syn[40], syn[41]]), 41000);
+        assert_eq!(syn[53], TCP_SYN);
+        assert_eq!(&packets[3][60..], b"hello");
+        assert_eq!(&packets[4][60..], b"welcome");
+    }
+
+    #[tokio::test]
+    async fn test_export_connection_to_file() {
+        use crate::payload::PayloadFormat;
+        use crate::tcp::{disconnect_tcp, open_tcp_connection, send_tcp_message_on_connection, TcpMessageOnConnection};
+        use crate::test_support::{connection_request, wait_for_messages};
+        use crate::transcript::remove_connection_transcript;
+        use tokio::io::AsyncWriteExt;
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let (mut peer, local) = listener.accept().await.unwrap();
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "status".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        peer.write_all(b"ok\r\n").await.unwrap();
+        wait_for_messages(&connection.id, 1).await;
+        // 切断後も書き出せる
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+
+        let path = std::env::temp_dir().join(format!("pcapng-test-{}.pcapng", std::process::id()));
+        let result = export_connection_pcapng(connection.id.clone(), path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        assert_eq!((result.packets, result.frames, result.skipped), (5, 2, 0));
+
+        // 実際の送信元ポートで、区切りを含む回線上のバイト列を書き出す
+        let data = std::fs::read(&path).unwrap();
+        let blocks = blocks(&data);
+        let tx = packet(blocks[blocks.len() - 2].1);
+        assert_eq!(u16::from_be_bytes([tx[20], tx[21]]), local.port());
+        assert_eq!(u16::from_be_bytes([tx[22], tx[23]]), port);
+        assert_eq!(&tx[40..], b"status\n");
+        let rx = packet(blocks.last().unwrap().1);
+        assert_eq!(&rx[40..], b"ok\r\n");
+        std::fs::remove_file(&path).ok();
+
+        remove_connection_transcript(connection.id.clone()).await.unwrap();
+        assert!(matches!(
+            export_connection_pcapng(connection.id, String::new()).await,
+            Err(TcpError::ConnectionNotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_reconnect_starts_new_flow() {
+        let entries = [entry(2, Direction::Tx, "one", None), entry(4, Direction::Tx, "two", None)];
+        let spans = [
+            span(1, "10.0.0.1:50000", "10.0.0.2:80", true, None),
+            span(3, "10.0.0.1:50001", "10.0.0.2:80", true, None),
+        ];
+        let (_, result) = build(&entries, &spans);
+        // ハンドシェイク3 + データ1 + 切断3 + ハンドシェイク3 + データ1
+        assert_eq!(result.packets, 11);
+    }
+
+    #[test]
+    fn test_mixed_address_families_are_normalized() {
+        let v4 = "127.0.0.1:1".parse().unwrap();
+        let mapped = "[::ffff:127.0.0.2]:2".parse().unwrap();
+        let v6 = "[::1]:3".parse().unwrap();
+        assert!(matches!(normalize(v4, mapped), (IpAddr::V4(_), IpAddr::V4(_))));
+        assert!(matches!(normalize(v4, v6), (IpAddr::V6(_), IpAddr::V6(_))));
+    }
+}
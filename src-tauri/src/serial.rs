@@ -0,0 +1,392 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::Mutex;
+use tokio_serial::{SerialPort, SerialPortBuilderExt, SerialPortType, SerialStream};
+use uuid::Uuid;
+
+use crate::framing::Framing;
+use crate::message_store::{MessageStore, RetentionPolicy};
+use crate::tcp::{
+    handle_connection_receiver, register_connection, ConnectionData, ConnectionWriter, TcpConnection,
+    TcpConnectionResult, TcpError, Transport,
+};
+use crate::text_encoding::TextEncoding;
+use crate::transcript::{self, TranscriptSource};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerialParity {
+    #[default]
+    None,
+    Odd,
+    Even,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerialStopBits {
+    #[default]
+    One,
+    Two,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SerialFlowControl {
+    #[default]
+    None,
+    /// XON/XOFF
+    Software,
+    /// RTS/CTS
+    Hardware,
+}
+
+fn default_baud_rate() -> u32 {
+    9600
+}
+
+fn default_data_bits() -> u8 {
+    8
+}
+
+/// シリアルポートの接続設定
+///
+/// 受信・送信・アイドルのタイムアウトには未対応（無通信でも切断しない）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SerialConnectionRequest {
+    /// デバイスのパス（例: "/dev/ttyUSB0", "COM3"）
+    pub path: String,
+    #[serde(default = "default_baud_rate")]
+    pub baud_rate: u32,
+    /// 5〜8
+    #[serde(default = "default_data_bits")]
+    pub data_bits: u8,
+    #[serde(default)]
+    pub parity: SerialParity,
+    #[serde(default)]
+    pub stop_bits: SerialStopBits,
+    #[serde(default)]
+    pub flow_control: SerialFlowControl,
+    /// オープン時のDTR（未指定の場合はドライバの既定値）
+    #[serde(default)]
+    pub dtr: Option<bool>,
+    /// オープン時のRTS（未指定の場合はドライバの既定値）
+    #[serde(default)]
+    pub rts: Option<bool>,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// モデム制御線の状態
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialLineStatus {
+    pub cts: bool,
+    pub dsr: bool,
+    pub ring: bool,
+    pub carrier_detect: bool,
+}
+
+/// 利用可能なシリアルポート
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SerialPortEntry {
+    pub path: String,
+    /// "usb", "pci", "bluetooth", "unknown"
+    pub port_type: String,
+    pub vid: Option<u16>,
+    pub pid: Option<u16>,
+    pub serial_number: Option<String>,
+    pub manufacturer: Option<String>,
+    pub product: Option<String>,
+}
+
+/// 受信タスク・送信側・制御線操作で共有するシリアルポート
+///
+/// ポーリングごとに短時間だけロックするため、受信待ちの間も送信や制御線の操作ができる
+#[derive(Clone)]
+struct SharedPort(Arc<std::sync::Mutex<SerialStream>>);
+
+impl SharedPort {
+    fn with_port<T>(&self, f: impl FnOnce(&mut SerialStream) -> T) -> T {
+        f(&mut self.0.lock().unwrap())
+    }
+}
+
+impl AsyncRead for SharedPort {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for SharedPort {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut *self.0.lock().unwrap()).poll_shutdown(cx)
+    }
+}
+
+// 制御線を操作するためのシリアル接続の状態
+static SERIAL_PORTS: std::sync::OnceLock<Arc<Mutex<HashMap<String, SharedPort>>>> = std::sync::OnceLock::new();
+
+fn serial_ports() -> &'static Arc<Mutex<HashMap<String, SharedPort>>> {
+    SERIAL_PORTS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+/// 切断された接続のポートを破棄する
+pub(crate) async fn forget_connection(connection_id: &str) {
+    serial_ports().lock().await.remove(connection_id);
+}
+
+async fn find_port(connection_id: &str) -> Result<SharedPort, TcpError> {
+    serial_ports().lock().await.get(connection_id).cloned().ok_or_else(|| {
+        TcpError::ConnectionNotFound(format!("Serial connection with ID {} not found", connection_id))
+    })
+}
+
+fn control_error(e: tokio_serial::Error) -> TcpError {
+    TcpError::SendFailed(format!("Failed to access serial control lines: {}", e))
+}
+
+/// 利用可能なシリアルポートを列挙
+#[tauri::command]
+pub async fn list_serial_ports() -> Result<Vec<SerialPortEntry>, TcpError> {
+    let ports = tokio_serial::available_ports()
+        .map_err(|e| TcpError::ConnectionFailed(format!("Failed to enumerate serial ports: {}", e)))?;
+
+    Ok(ports
+        .into_iter()
+        .map(|info| {
+            let mut entry = SerialPortEntry {
+                path: info.port_name,
+                port_type: "unknown".to_string(),
+                vid: None,
+                pid: None,
+                serial_number: None,
+                manufacturer: None,
+                product: None,
+            };
+            match info.port_type {
+                SerialPortType::UsbPort(usb) => {
+                    entry.port_type = "usb".to_string();
+                    entry.vid = Some(usb.vid);
+                    entry.pid = Some(usb.pid);
+                    entry.serial_number = usb.serial_number;
+                    entry.manufacturer = usb.manufacturer;
+                    entry.product = usb.product;
+                }
+                SerialPortType::PciPort => entry.port_type = "pci".to_string(),
+                SerialPortType::BluetoothPort => entry.port_type = "bluetooth".to_string(),
+                SerialPortType::Unknown => {}
+            }
+            entry
+        })
+        .collect())
+}
+
+/// シリアルポートを開き、TCP接続と同じレジストリに登録する
+#[tauri::command]
+pub async fn connect_serial(request: SerialConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    request.framing.validate(request.encoding).map_err(TcpError::InvalidFraming)?;
+
+    let data_bits = match request.data_bits {
+        5 => tokio_serial::DataBits::Five,
+        6 => tokio_serial::DataBits::Six,
+        7 => tokio_serial::DataBits::Seven,
+        8 => tokio_serial::DataBits::Eight,
+        other => {
+            return Err(TcpError::InvalidAddress(format!(
+                "Unsupported data bits: {} (expected 5-8)",
+                other
+            )));
+        }
+    };
+    let parity = match request.parity {
+        SerialParity::None => tokio_serial::Parity::None,
+        SerialParity::Odd => tokio_serial::Parity::Odd,
+        SerialParity::Even => tokio_serial::Parity::Even,
+    };
+    let stop_bits = match request.stop_bits {
+        SerialStopBits::One => tokio_serial::StopBits::One,
+        SerialStopBits::Two => tokio_serial::StopBits::Two,
+    };
+    let flow_control = match request.flow_control {
+        SerialFlowControl::None => tokio_serial::FlowControl::None,
+        SerialFlowControl::Software => tokio_serial::FlowControl::Software,
+        SerialFlowControl::Hardware => tokio_serial::FlowControl::Hardware,
+    };
+
+    let mut builder = tokio_serial::new(&request.path, request.baud_rate)
+        .data_bits(data_bits)
+        .parity(parity)
+        .stop_bits(stop_bits)
+        .flow_control(flow_control);
+    if let Some(dtr) = request.dtr {
+        builder = builder.dtr_on_open(dtr);
+    }
+    let mut stream = builder.open_native_async().map_err(|e| {
+        TcpError::ConnectionFailed(format!("Failed to open serial port {}: {}", request.path, e))
+    })?;
+    if let Some(rts) = request.rts {
+        stream.write_request_to_send(rts).map_err(control_error)?;
+    }
+
+    let connection_id = Uuid::new_v4().to_string();
+    let connection = TcpConnection::new(connection_id.clone(), request.path.clone(), 0, Transport::Serial);
+
+    let port = SharedPort(Arc::new(std::sync::Mutex::new(stream)));
+    serial_ports().lock().await.insert(connection_id.clone(), port.clone());
+
+    transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+    let messages = MessageStore::shared(request.retention.clone());
+
+    // 受信処理（登録時にタスクとして開始する）
+    let receiver = handle_connection_receiver(
+        Box::new(port.clone()),
+        connection_id.clone(),
+        request.framing.clone(),
+        request.encoding,
+        Arc::clone(&messages),
+    );
+
+    let connection_data = ConnectionData {
+        info: connection.clone(),
+        writer: Arc::new(Mutex::new(ConnectionWriter::Stream(Box::new(port)))),
+        framing: request.framing,
+        encoding: request.encoding,
+        messages,
+        receiver_handle: None,
+        tls_info: None,
+    };
+    register_connection(connection_data, receiver).await;
+
+    Ok(TcpConnectionResult {
+        success: true,
+        connection: Some(connection),
+        error: None,
+    })
+}
+
+/// 接続中のシリアルポートのDTR/RTSを変更する（未指定の線はそのまま）
+#[tauri::command]
+pub async fn set_serial_control_lines(
+    connection_id: String,
+    dtr: Option<bool>,
+    rts: Option<bool>,
+) -> Result<(), TcpError> {
+    let port = find_port(&connection_id).await?;
+    port.with_port(|stream| {
+        if let Some(dtr) = dtr {
+            stream.write_data_terminal_ready(dtr)?;
+        }
+        if let Some(rts) = rts {
+            stream.write_request_to_send(rts)?;
+        }
+        Ok(())
+    })
+    .map_err(control_error)
+}
+
+/// 接続中のシリアルポートのモデム制御線の状態を取得
+#[tauri::command]
+pub async fn get_serial_line_status(connection_id: String) -> Result<SerialLineStatus, TcpError> {
+    let port = find_port(&connection_id).await?;
+    port.with_port(|stream| {
+        Ok(SerialLineStatus {
+            cts: stream.read_clear_to_send()?,
+            dsr: stream.read_data_set_ready()?,
+            ring: stream.read_ring_indicator()?,
+            carrier_detect: stream.read_carrier_detect()?,
+        })
+    })
+    .map_err(control_error)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::payload::PayloadFormat;
+    use crate::tcp::{disconnect_tcp, send_tcp_message_on_connection, TcpMessageOnConnection};
+    use crate::test_support::wait_for_messages;
+    use std::time::Duration;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[tokio::test]
+    async fn test_serial_over_pty() {
+        // ptyのスレーブ側を接続先のシリアルポートとして開く
+        let (mut master, slave) = SerialStream::pair().unwrap();
+        let request = SerialConnectionRequest {
+            path: slave.name().unwrap(),
+            baud_rate: 115200,
+            data_bits: 8,
+            parity: SerialParity::Even,
+            stop_bits: SerialStopBits::One,
+            flow_control: SerialFlowControl::None,
+            dtr: None,
+            rts: None,
+            framing: Framing::Line,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        };
+        let connection = connect_serial(request).await.unwrap().connection.unwrap();
+        assert_eq!(connection.transport, Transport::Serial);
+
+        master.write_all(b"temp=21.5\r\nhumidity=40\r\n").await.unwrap();
+
+        let messages = wait_for_messages(&connection.id, 2).await;
+        assert_eq!(messages.len(), 2);
+        assert_eq!(messages[0].message, "temp=21.5");
+        assert_eq!(messages[1].message, "humidity=40");
+
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "READ".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+
+        let mut buf = [0u8; 5];
+        tokio::time::timeout(Duration::from_secs(2), master.read_exact(&mut buf))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(&buf, b"READ\n");
+
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+        assert!(find_port(&connection.id).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_serial_rejects_invalid_data_bits() {
+        let request = SerialConnectionRequest {
+            path: "/dev/null".to_string(),
+            baud_rate: 9600,
+            data_bits: 9,
+            parity: SerialParity::None,
+            stop_bits: SerialStopBits::One,
+            flow_control: SerialFlowControl::None,
+            dtr: None,
+            rts: None,
+            framing: Framing::Line,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        };
+        assert!(matches!(connect_serial(request).await, Err(TcpError::InvalidAddress(_))));
+    }
+}
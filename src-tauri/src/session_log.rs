@@ -0,0 +1,725 @@
+use chrono::{DateTime, Local, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::tcp::{get_connection, server_id_or_default, TcpError};
+use crate::transcript::{Direction, TranscriptEntry, TranscriptSource};
+
+/// 1ページで返す行数の既定値
+const DEFAULT_READ_LIMIT: usize = 1000;
+/// 書き込みスレッドに渡す記録の上限（超えた分は破棄する）
+const WRITE_QUEUE_CAPACITY: usize = 10_000;
+
+fn default_max_file_bytes() -> Option<u64> {
+    Some(10 * 1024 * 1024)
+}
+
+fn default_retention_days() -> Option<u64> {
+    Some(30)
+}
+
+/// ログファイルの形式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionLogFormat {
+    /// 1行に1件の記録をJSONで書く（.jsonl）
+    #[default]
+    JsonLines,
+    /// 時刻と向きを付けたテキスト（.log）
+    Text,
+}
+
+impl SessionLogFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            SessionLogFormat::JsonLines => "jsonl",
+            SessionLogFormat::Text => "log",
+        }
+    }
+}
+
+/// セッションログの設定
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionLogOptions {
+    #[serde(default)]
+    pub format: SessionLogFormat,
+    /// 1ファイルの上限バイト数（超える場合は新しいファイルに切り替える。nullで無制限）
+    #[serde(default = "default_max_file_bytes")]
+    pub max_file_bytes: Option<u64>,
+    /// 新しいファイルに切り替える間隔（秒）
+    #[serde(default)]
+    pub rotate_interval_secs: Option<u64>,
+    /// ログファイルを残す日数（nullで無期限）
+    #[serde(default = "default_retention_days")]
+    pub retention_days: Option<u64>,
+}
+
+impl Default for SessionLogOptions {
+    fn default() -> Self {
+        Self {
+            format: SessionLogFormat::default(),
+            max_file_bytes: default_max_file_bytes(),
+            rotate_interval_secs: None,
+            retention_days: default_retention_days(),
+        }
+    }
+}
+
+/// 保存済みのログファイル
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionLogFile {
+    pub name: String,
+    pub size: u64,
+    pub modified: String,
+    /// 現在書き込み中かどうか
+    pub active: bool,
+}
+
+/// ログファイルの内容（行単位で分割して取得する）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionLogContent {
+    pub name: String,
+    pub lines: Vec<String>,
+    /// 続きがある場合に次のoffsetに指定する行番号
+    pub next_offset: Option<usize>,
+}
+
+/// 書き込み中のログファイル
+struct ActiveLog {
+    dir: PathBuf,
+    prefix: String,
+    options: SessionLogOptions,
+    path: PathBuf,
+    /// 一覧で書き込み中のファイルを判定するため、切り替えのたびに更新する
+    current_path: Arc<Mutex<PathBuf>>,
+    writer: BufWriter<File>,
+    opened_at: Instant,
+    written: u64,
+}
+
+impl ActiveLog {
+    fn open(dir: &Path, prefix: String, options: SessionLogOptions) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        remove_expired_logs(dir, &prefix, &options)?;
+        let (path, writer) = create_log_file(dir, &prefix, options.format)?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            prefix,
+            options,
+            current_path: Arc::new(Mutex::new(path.clone())),
+            path,
+            writer,
+            opened_at: Instant::now(),
+            written: 0,
+        })
+    }
+
+    fn write_entry(&mut self, entry: &TranscriptEntry) -> io::Result<()> {
+        let line = match self.options.format {
+            SessionLogFormat::JsonLines => serde_json::to_string(entry).map_err(io::Error::other)?,
+            SessionLogFormat::Text => format_text_line(entry),
+        };
+        let len = line.len() as u64 + 1;
+        if self.should_rotate(len) {
+            self.rotate()?;
+        }
+        writeln!(self.writer, "{}", line)?;
+        self.written += len;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    fn should_rotate(&self, next_len: u64) -> bool {
+        // 空のファイルは切り替えても意味がない
+        if self.written == 0 {
+            return false;
+        }
+        let over_size = self
+            .options
+            .max_file_bytes
+            .is_some_and(|max| self.written + next_len > max);
+        let over_time = self
+            .options
+            .rotate_interval_secs
+            .is_some_and(|secs| self.opened_at.elapsed() >= Duration::from_secs(secs));
+        over_size || over_time
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        remove_expired_logs(&self.dir, &self.prefix, &self.options)?;
+        let (path, writer) = create_log_file(&self.dir, &self.prefix, self.options.format)?;
+        log::info!("Rotated session log {} -> {}", self.path.display(), path.display());
+        *self.current_path.lock().unwrap() = path.clone();
+        self.path = path;
+        self.writer = writer;
+        self.opened_at = Instant::now();
+        self.written = 0;
+        Ok(())
+    }
+}
+
+/// 新しいログファイルを作成する
+///
+/// 同じ時刻に切り替えた場合も名前順が作成順になるよう連番を付ける
+fn create_log_file(dir: &Path, prefix: &str, format: SessionLogFormat) -> io::Result<(PathBuf, BufWriter<File>)> {
+    let stamp = Local::now().format("%Y%m%d-%H%M%S%.3f");
+    for suffix in 0.. {
+        let path = dir.join(format!("{}-{}-{:03}.{}", prefix, stamp, suffix, format.extension()));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, BufWriter::new(file))),
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    unreachable!()
+}
+
+fn is_log_file(path: &Path) -> bool {
+    matches!(path.extension().and_then(|ext| ext.to_str()), Some("jsonl" | "log"))
+}
+
+/// このログが作成したファイルかどうか（`{prefix}-YYYYMMDD-HHMMSS.mmm-NNN`の形式）
+fn belongs_to(path: &Path, prefix: &str) -> bool {
+    let Some(stamp) = path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .and_then(|stem| stem.strip_prefix(prefix))
+        .and_then(|rest| rest.strip_prefix('-'))
+    else {
+        return false;
+    };
+    let digits = |part: &str| part.bytes().all(|b| b.is_ascii_digit());
+    let parts: Vec<&str> = stamp.split(['-', '.']).collect();
+    matches!(
+        parts.as_slice(),
+        [date, time, millis, suffix]
+            if date.len() == 8 && time.len() == 6 && millis.len() == 3 && suffix.len() >= 3
+                && parts.iter().all(|part| digits(part))
+    )
+}
+
+/// 保持期間を過ぎたログファイルを削除する
+///
+/// 他の接続・サーバーのログは別の保持期間で開始されている場合があるため、同じprefixのファイルのみ対象にする
+fn remove_expired_logs(dir: &Path, prefix: &str, options: &SessionLogOptions) -> io::Result<()> {
+    let Some(days) = options.retention_days else {
+        return Ok(());
+    };
+    let Some(cutoff) = SystemTime::now().checked_sub(Duration::from_secs(days * 24 * 60 * 60)) else {
+        return Ok(());
+    };
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if !is_log_file(&path) || !belongs_to(&path, prefix) {
+            continue;
+        }
+        let expired = fs::metadata(&path)
+            .and_then(|metadata| metadata.modified())
+            .is_ok_and(|modified| modified < cutoff);
+        if expired {
+            log::info!("Removing expired session log {}", path.display());
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(())
+}
+
+/// テキスト形式の1行（制御文字はエスケープして1行に収める）
+fn format_text_line(entry: &TranscriptEntry) -> String {
+    let direction = match entry.direction {
+        Direction::Tx => "TX",
+        Direction::Rx => "RX",
+        Direction::System => "--",
+    };
+    let peer = match (&entry.client_id, &entry.peer) {
+        (Some(client_id), Some(peer)) => format!(" [{} {}]", client_id, peer),
+        (Some(client_id), None) => format!(" [{}]", client_id),
+        (None, Some(peer)) => format!(" [{}]", peer),
+        (None, None) => String::new(),
+    };
+    match entry.direction {
+        Direction::System => format!("{} {}{} {}", entry.timestamp, direction, peer, entry.text),
+        _ => format!(
+            "{} {}{} ({} bytes) {}",
+            entry.timestamp,
+            direction,
+            peer,
+            entry.bytes,
+            entry.text.escape_debug()
+        ),
+    }
+}
+
+/// ファイル名に使えない文字を置き換える
+fn sanitize(id: &str) -> String {
+    id.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+        .collect()
+}
+
+fn file_prefix(source: &TranscriptSource) -> String {
+    match source {
+        TranscriptSource::Connection(id) => format!("connection-{}", sanitize(id)),
+        TranscriptSource::Server(id) => format!("server-{}", sanitize(id)),
+    }
+}
+
+// ログの保存先（アプリのデータディレクトリ配下。起動時に設定する）
+static LOG_DIR: std::sync::OnceLock<PathBuf> = std::sync::OnceLock::new();
+
+/// 書き込みスレッドへの指示
+enum LogCommand {
+    /// ログを開始する（実行中の場合は置き換える）
+    Start { source: TranscriptSource, log: ActiveLog },
+    Write { source: TranscriptSource, entry: TranscriptEntry },
+    /// それまでの記録を書き終えてログを閉じ、doneに通知する
+    Close { source: TranscriptSource, done: mpsc::Sender<()> },
+}
+
+// 記録中のログと書き込み中のファイルのパス（記録と同じ同期処理の中から参照できるようstdのMutexを使う）
+static ACTIVE_LOGS: std::sync::OnceLock<Mutex<HashMap<TranscriptSource, Arc<Mutex<PathBuf>>>>> =
+    std::sync::OnceLock::new();
+
+// ファイルへの書き込みを行うスレッドへの送信口（最初の使用時に開始する）
+static WRITER: std::sync::OnceLock<mpsc::SyncSender<LogCommand>> = std::sync::OnceLock::new();
+
+// 書き込みが追いつかず破棄した記録の数
+static DROPPED: AtomicU64 = AtomicU64::new(0);
+
+/// ログの保存先を設定する
+pub fn init_log_dir(dir: PathBuf) {
+    LOG_DIR.set(dir).ok();
+}
+
+fn log_dir() -> PathBuf {
+    LOG_DIR
+        .get()
+        .cloned()
+        .unwrap_or_else(|| std::env::temp_dir().join("session_logs"))
+}
+
+fn active_logs() -> &'static Mutex<HashMap<TranscriptSource, Arc<Mutex<PathBuf>>>> {
+    ACTIVE_LOGS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn writer() -> &'static mpsc::SyncSender<LogCommand> {
+    WRITER.get_or_init(|| {
+        let (sender, receiver) = mpsc::sync_channel(WRITE_QUEUE_CAPACITY);
+        std::thread::spawn(move || run_writer(receiver));
+        sender
+    })
+}
+
+/// 記録をファイルに書き込む（送受信の処理をディスクの速度で止めないよう専用スレッドで行う）
+///
+/// 指示が途切れたときにまとめて書き出す
+fn run_writer(receiver: mpsc::Receiver<LogCommand>) {
+    let mut logs: HashMap<TranscriptSource, ActiveLog> = HashMap::new();
+    while let Ok(first) = receiver.recv() {
+        let mut command = Some(first);
+        while let Some(current) = command.take() {
+            match current {
+                LogCommand::Start { source, log } => {
+                    if let Some(mut previous) = logs.insert(source, log) {
+                        previous.flush().ok();
+                    }
+                }
+                LogCommand::Write { source, entry } => {
+                    let Some(log) = logs.get_mut(&source) else {
+                        continue;
+                    };
+                    if let Err(e) = log.write_entry(&entry) {
+                        // 書き込めなくなったログは止め、送受信は続ける
+                        log::error!("Failed to write session log {}: {}", log.path.display(), e);
+                        if let Some(log) = logs.remove(&source) {
+                            deactivate(&source, &log);
+                        }
+                    }
+                }
+                LogCommand::Close { source, done } => {
+                    if let Some(mut log) = logs.remove(&source) {
+                        if let Err(e) = log.flush() {
+                            log::error!("Failed to write session log {}: {}", log.path.display(), e);
+                        }
+                    }
+                    done.send(()).ok();
+                }
+            }
+            command = receiver.try_recv().ok();
+        }
+
+        // アプリが終了しても直前までの記録が残るよう、溜まった指示を処理し終えるたびに書き出す
+        let mut failed = Vec::new();
+        for (source, log) in logs.iter_mut() {
+            if let Err(e) = log.flush() {
+                log::error!("Failed to write session log {}: {}", log.path.display(), e);
+                failed.push(source.clone());
+            }
+        }
+        for source in failed {
+            if let Some(log) = logs.remove(&source) {
+                deactivate(&source, &log);
+            }
+        }
+    }
+}
+
+/// 書き込みに失敗したログを記録中の一覧から外す（新しく開始したログはそのまま残す）
+fn deactivate(source: &TranscriptSource, log: &ActiveLog) {
+    let mut active = active_logs().lock().unwrap();
+    if active.get(source).is_some_and(|path| Arc::ptr_eq(path, &log.current_path)) {
+        active.remove(source);
+    }
+}
+
+/// 記録をログの書き込みスレッドに渡す（ログが有効な場合のみ）
+pub(crate) fn write(source: &TranscriptSource, entry: &TranscriptEntry) {
+    // 開始・終了の指示と順序が入れ替わらないよう、一覧のロック中に渡す
+    let active = active_logs().lock().unwrap();
+    if !active.contains_key(source) {
+        return;
+    }
+    let command = LogCommand::Write {
+        source: source.clone(),
+        entry: entry.clone(),
+    };
+    if writer().try_send(command).is_err() {
+        let dropped = DROPPED.fetch_add(1, Ordering::Relaxed) + 1;
+        // ログが溢れないよう、破棄した数が2の累乗になったときだけ警告する
+        if dropped.is_power_of_two() {
+            log::warn!("Session log writer is falling behind ({} entries dropped)", dropped);
+        }
+    }
+}
+
+/// ログを閉じ、それまでの記録を書き終えるまで待つ（接続の削除時）
+///
+/// ログが実行中でなかった場合はfalseを返す
+pub(crate) async fn close(source: &TranscriptSource) -> bool {
+    if active_logs().lock().unwrap().remove(source).is_none() {
+        return false;
+    }
+    // 一覧から外した後は新しい記録が渡されないため、閉じる指示はそれまでの記録の後に届く
+    let source = source.clone();
+    let sender = writer().clone();
+    tokio::task::spawn_blocking(move || {
+        let (done, finished) = mpsc::channel();
+        if sender.send(LogCommand::Close { source, done }).is_ok() {
+            finished.recv().ok();
+        }
+    })
+    .await
+    .ok();
+    true
+}
+
+fn start(source: TranscriptSource, options: SessionLogOptions) -> Result<String, TcpError> {
+    let log = ActiveLog::open(&log_dir(), file_prefix(&source), options)
+        .map_err(|e| TcpError::StorageFailed(format!("Failed to open session log: {}", e)))?;
+    let name = file_name(&log.path);
+    let path = log.path.clone();
+    let current_path = Arc::clone(&log.current_path);
+
+    // 実行中の場合は新しい設定のファイルに切り替える
+    let mut active = active_logs().lock().unwrap();
+    if writer().try_send(LogCommand::Start { source: source.clone(), log }).is_err() {
+        fs::remove_file(&path).ok();
+        return Err(TcpError::StorageFailed("Session log writer is busy".to_string()));
+    }
+    active.insert(source, current_path);
+    Ok(name)
+}
+
+async fn stop(source: &TranscriptSource) -> Result<(), TcpError> {
+    if close(source).await {
+        Ok(())
+    } else {
+        Err(TcpError::InvalidQuery("Session log is not running".to_string()))
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+/// 接続の送受信のログ記録を開始し、書き込み先のファイル名を返す
+#[tauri::command]
+pub async fn start_connection_session_log(
+    connection_id: String,
+    options: Option<SessionLogOptions>,
+) -> Result<String, TcpError> {
+    // 切断した接続の記録は残っているため、接続中かどうかはレジストリで確認する
+    get_connection(connection_id.clone()).await?;
+    start(TranscriptSource::Connection(connection_id), options.unwrap_or_default())
+}
+
+/// サーバーの送受信のログ記録を開始し、書き込み先のファイル名を返す（起動前に開始しておける）
+#[tauri::command]
+pub async fn start_server_session_log(
+    server_id: Option<String>,
+    options: Option<SessionLogOptions>,
+) -> Result<String, TcpError> {
+    start(TranscriptSource::Server(server_id_or_default(server_id)), options.unwrap_or_default())
+}
+
+#[tauri::command]
+pub async fn stop_connection_session_log(connection_id: String) -> Result<(), TcpError> {
+    stop(&TranscriptSource::Connection(connection_id)).await
+}
+
+#[tauri::command]
+pub async fn stop_server_session_log(server_id: Option<String>) -> Result<(), TcpError> {
+    stop(&TranscriptSource::Server(server_id_or_default(server_id))).await
+}
+
+/// 保存済みのログファイルの一覧（新しい順）
+#[tauri::command]
+pub async fn list_session_logs() -> Result<Vec<SessionLogFile>, TcpError> {
+    let dir = log_dir();
+    let active: Vec<PathBuf> = active_logs()
+        .lock()
+        .unwrap()
+        .values()
+        .map(|path| path.lock().unwrap().clone())
+        .collect();
+
+    let read_dir = match fs::read_dir(&dir) {
+        Ok(read_dir) => read_dir,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(TcpError::StorageFailed(format!("Failed to list session logs: {}", e))),
+    };
+    let mut files = Vec::new();
+    for entry in read_dir.flatten() {
+        let path = entry.path();
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() || !is_log_file(&path) {
+            continue;
+        }
+        let modified = metadata
+            .modified()
+            .map(|modified| DateTime::<Utc>::from(modified).to_rfc3339())
+            .unwrap_or_default();
+        files.push(SessionLogFile {
+            name: file_name(&path),
+            size: metadata.len(),
+            modified,
+            active: active.contains(&path),
+        });
+    }
+    files.sort_by(|a, b| b.modified.cmp(&a.modified).then_with(|| b.name.cmp(&a.name)));
+    Ok(files)
+}
+
+/// ログファイルを開き、指定した行から読み込む
+#[tauri::command]
+pub async fn open_session_log(
+    name: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<SessionLogContent, TcpError> {
+    // ログディレクトリの外を読めないようファイル名のみ受け付ける
+    let is_plain_name = Path::new(&name).file_name().is_some_and(|file_name| file_name == name.as_str());
+    let path = log_dir().join(&name);
+    if !is_plain_name || !is_log_file(&path) {
+        return Err(TcpError::InvalidQuery(format!("Invalid session log name: {}", name)));
+    }
+    let file = File::open(&path)
+        .map_err(|e| TcpError::StorageFailed(format!("Failed to open session log {}: {}", name, e)))?;
+
+    let offset = offset.unwrap_or(0);
+    let limit = limit.unwrap_or(DEFAULT_READ_LIMIT).max(1);
+    let mut lines = BufReader::new(file).lines().skip(offset);
+    let page = lines
+        .by_ref()
+        .take(limit)
+        .collect::<io::Result<Vec<String>>>()
+        .map_err(|e| TcpError::StorageFailed(format!("Failed to read session log {}: {}", name, e)))?;
+    let next_offset = lines.next().map(|_| offset + page.len());
+
+    Ok(SessionLogContent {
+        name,
+        lines: page,
+        next_offset,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("session-log-test-{}-{}", name, std::process::id()));
+        fs::remove_dir_all(&dir).ok();
+        dir
+    }
+
+    fn entry(direction: Direction, text: &str) -> TranscriptEntry {
+        TranscriptEntry {
+            seq: 1,
+            direction,
+            timestamp: "2024-01-01T00:00:00+00:00".to_string(),
+            bytes: text.len(),
+            text: text.to_string(),
+            raw: text.as_bytes().to_vec(),
+            wire: None,
+            client_id: None,
+            peer: Some("127.0.0.1:5000".to_string()),
+        }
+    }
+
+    fn log_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = fs::read_dir(dir).unwrap().map(|e| e.unwrap().path()).collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_text_line_format() {
+        assert_eq!(
+            format_text_line(&entry(Direction::Rx, "ok\r\n")),
+            "2024-01-01T00:00:00+00:00 RX [127.0.0.1:5000] (4 bytes) ok\\r\\n"
+        );
+        assert_eq!(
+            format_text_line(&entry(Direction::System, "connected")),
+            "2024-01-01T00:00:00+00:00 -- [127.0.0.1:5000] connected"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_open_rejects_paths_outside_log_dir() {
+        for name in ["../secret.log", "/etc/passwd", "nested/file.jsonl", "notes.txt"] {
+            assert!(matches!(
+                open_session_log(name.to_string(), None, None).await,
+                Err(TcpError::InvalidQuery(_))
+            ));
+        }
+        assert!(matches!(
+            open_session_log("connection-missing-20000101-000000.000-000.log".to_string(), None, None).await,
+            Err(TcpError::StorageFailed(_))
+        ));
+    }
+
+    #[test]
+    fn test_belongs_to_matches_own_files_only() {
+        let own = Path::new("connection-a-20240101-120000.123-000.jsonl");
+        assert!(belongs_to(own, "connection-a"));
+        assert!(!belongs_to(own, "connection"));
+        assert!(!belongs_to(Path::new("connection-a-b-20240101-120000.123-000.jsonl"), "connection-a"));
+        assert!(!belongs_to(Path::new("connection-a-notes.log"), "connection-a"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_traffic_is_logged() {
+        use crate::payload::PayloadFormat;
+        use crate::tcp::{disconnect_tcp, open_tcp_connection, send_tcp_message_on_connection, TcpMessageOnConnection};
+        use crate::test_support::connection_request;
+        use tokio::net::TcpListener;
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let _peer = listener.accept().await.unwrap();
+
+        let options = SessionLogOptions {
+            format: SessionLogFormat::Text,
+            ..Default::default()
+        };
+        let name = start_connection_session_log(connection.id.clone(), Some(options)).await.unwrap();
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "ping".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+
+        let logs = list_session_logs().await.unwrap();
+        assert!(logs.iter().any(|log| log.name == name && log.active));
+
+        // 切断すると記録を書き終えてログを閉じる
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+        let content = open_session_log(name.clone(), None, None).await.unwrap();
+        assert_eq!(content.lines.len(), 2);
+        assert!(content.lines[0].ends_with(" TX (4 bytes) ping"));
+        assert!(content.lines[1].contains(" -- closed"));
+        assert_eq!(content.next_offset, None);
+        assert!(matches!(
+            stop_connection_session_log(connection.id).await,
+            Err(TcpError::InvalidQuery(_))
+        ));
+
+        let first = open_session_log(name.clone(), Some(0), Some(1)).await.unwrap();
+        assert_eq!(first.next_offset, Some(1));
+        fs::remove_file(log_dir().join(name)).ok();
+    }
+
+    #[test]
+    fn test_rotates_by_size() {
+        let dir = temp_dir("size");
+        let options = SessionLogOptions {
+            max_file_bytes: Some(300),
+            ..Default::default()
+        };
+        let mut log = ActiveLog::open(&dir, "connection-test".to_string(), options).unwrap();
+        for i in 0..4 {
+            log.write_entry(&entry(Direction::Tx, &format!("message {}", i))).unwrap();
+        }
+        log.flush().unwrap();
+
+        let files = log_files(&dir);
+        assert!(files.len() >= 2);
+        let lines: Vec<String> = files
+            .iter()
+            .flat_map(|path| fs::read_to_string(path).unwrap().lines().map(String::from).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(lines.len(), 4);
+        let first: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(first["direction"], "tx");
+        assert_eq!(first["text"], "message 0");
+        fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_rotates_by_time_and_removes_expired() {
+        let dir = temp_dir("time");
+        fs::create_dir_all(&dir).unwrap();
+        // 保持期間を過ぎたファイル（他のサーバーのファイルは残す）
+        let expired = dir.join("server-default-20000101-000000.000-000.log");
+        let other = dir.join("server-other-20000101-000000.000-000.log");
+        for path in [&expired, &other] {
+            File::create(path)
+                .unwrap()
+                .set_modified(SystemTime::now() - Duration::from_secs(3 * 24 * 60 * 60))
+                .unwrap();
+        }
+
+        let options = SessionLogOptions {
+            format: SessionLogFormat::Text,
+            rotate_interval_secs: Some(0),
+            retention_days: Some(1),
+            ..Default::default()
+        };
+        let mut log = ActiveLog::open(&dir, "server-default".to_string(), options).unwrap();
+        assert!(!expired.exists());
+        assert!(other.exists());
+
+        log.write_entry(&entry(Direction::Rx, "one")).unwrap();
+        log.write_entry(&entry(Direction::Rx, "two")).unwrap();
+        assert_eq!(log_files(&dir).len(), 3);
+        fs::remove_dir_all(&dir).ok();
+    }
+}
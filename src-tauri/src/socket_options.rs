@@ -0,0 +1,245 @@
+use serde::{Deserialize, Serialize};
+use socket2::{SockRef, TcpKeepalive};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::net::{lookup_host, TcpListener, TcpSocket, TcpStream};
+
+/// TCPソケットのオプション（省略した項目はOSの既定値）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocketOptions {
+    /// TCP_NODELAY（trueでNagleアルゴリズムを無効化）
+    #[serde(default)]
+    pub nodelay: Option<bool>,
+    /// 指定した場合はTCPキープアライブを有効化
+    #[serde(default)]
+    pub keepalive: Option<KeepaliveOptions>,
+    /// SO_RCVBUF（バイト）
+    #[serde(default)]
+    pub recv_buffer_size: Option<u32>,
+    /// SO_SNDBUF（バイト）
+    #[serde(default)]
+    pub send_buffer_size: Option<u32>,
+    /// SO_LINGER（秒）。0にするとクローズ時にRSTを送る
+    #[serde(default)]
+    pub linger_secs: Option<u64>,
+    /// 接続前にバインドするローカルアドレス（クライアント接続のみ）
+    #[serde(default)]
+    pub local_address: Option<String>,
+    /// 接続前にバインドするローカルポート（クライアント接続のみ）
+    #[serde(default)]
+    pub local_port: Option<u16>,
+}
+
+/// TCPキープアライブの設定（省略した項目はOSの既定値）
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeepaliveOptions {
+    /// 最初のプローブを送るまでの無通信時間（秒）
+    #[serde(default)]
+    pub time_secs: Option<u64>,
+    /// プローブの送信間隔（秒）
+    #[serde(default)]
+    pub interval_secs: Option<u64>,
+    /// 切断と判断するまでのプローブ回数（Windowsでは無視される）
+    #[serde(default)]
+    pub retries: Option<u32>,
+}
+
+impl SocketOptions {
+    /// 設定値の妥当性をチェック
+    pub fn validate(&self) -> Result<(), String> {
+        self.local_ip().map(|_| ())
+    }
+
+    fn local_ip(&self) -> Result<Option<IpAddr>, String> {
+        match &self.local_address {
+            Some(address) => address
+                .parse()
+                .map(Some)
+                .map_err(|_| format!("Invalid local address: {}", address)),
+            None => Ok(None),
+        }
+    }
+
+    /// 接続先のアドレスファミリーに合わせたバインド先
+    fn local_bind_address(&self, remote: &SocketAddr) -> io::Result<Option<SocketAddr>> {
+        let ip = self
+            .local_ip()
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        let ip = match (ip, self.local_port) {
+            (None, None) => return Ok(None),
+            (Some(ip), _) => ip,
+            (None, Some(_)) if remote.is_ipv4() => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            (None, Some(_)) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+        };
+        Ok(Some(SocketAddr::new(ip, self.local_port.unwrap_or(0))))
+    }
+
+    /// 接続・待ち受け前に設定する必要があるオプション
+    fn apply_to_socket(&self, socket: &TcpSocket) -> io::Result<()> {
+        if let Some(size) = self.recv_buffer_size {
+            socket.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buffer_size {
+            socket.set_send_buffer_size(size)?;
+        }
+        Ok(())
+    }
+
+    /// 接続済みのストリームに設定するオプション
+    pub fn apply_to_stream(&self, stream: &TcpStream) -> io::Result<()> {
+        if let Some(nodelay) = self.nodelay {
+            stream.set_nodelay(nodelay)?;
+        }
+        let socket = SockRef::from(stream);
+        if let Some(keepalive) = &self.keepalive {
+            socket.set_tcp_keepalive(&keepalive.to_tcp_keepalive())?;
+        }
+        if let Some(secs) = self.linger_secs {
+            socket.set_linger(Some(Duration::from_secs(secs)))?;
+        }
+        Ok(())
+    }
+}
+
+impl KeepaliveOptions {
+    fn to_tcp_keepalive(&self) -> TcpKeepalive {
+        let mut keepalive = TcpKeepalive::new();
+        if let Some(secs) = self.time_secs {
+            keepalive = keepalive.with_time(Duration::from_secs(secs));
+        }
+        if let Some(secs) = self.interval_secs {
+            keepalive = keepalive.with_interval(Duration::from_secs(secs));
+        }
+        #[cfg(not(windows))]
+        if let Some(retries) = self.retries {
+            keepalive = keepalive.with_retries(retries);
+        }
+        keepalive
+    }
+}
+
+fn new_socket(address: &SocketAddr) -> io::Result<TcpSocket> {
+    if address.is_ipv4() {
+        TcpSocket::new_v4()
+    } else {
+        TcpSocket::new_v6()
+    }
+}
+
+/// オプションを適用して接続する（名前解決の結果を順に試す）
+pub async fn connect(address: &str, options: &SocketOptions) -> io::Result<TcpStream> {
+    let mut last_error = None;
+    for remote in lookup_host(address).await? {
+        let socket = new_socket(&remote)?;
+        options.apply_to_socket(&socket)?;
+        if let Some(local) = options.local_bind_address(&remote)? {
+            // 固定ポートで再接続できるようにする
+            socket.set_reuseaddr(true)?;
+            socket.bind(local)?;
+        }
+        match socket.connect(remote).await {
+            Ok(stream) => {
+                options.apply_to_stream(&stream)?;
+                return Ok(stream);
+            }
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Could not resolve {}", address))
+    }))
+}
+
+/// オプションを適用して待ち受ける
+///
+/// バッファサイズは受け付けた接続に引き継がれる
+pub async fn listen(address: &str, options: &SocketOptions) -> io::Result<TcpListener> {
+    let mut last_error = None;
+    for local in lookup_host(address).await? {
+        let socket = new_socket(&local)?;
+        #[cfg(not(windows))]
+        socket.set_reuseaddr(true)?;
+        options.apply_to_socket(&socket)?;
+        match socket.bind(local).and_then(|_| socket.listen(1024)) {
+            Ok(listener) => return Ok(listener),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    Err(last_error.unwrap_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Could not resolve {}", address))
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let options: SocketOptions = serde_json::from_str(r#"{"nodelay": true, "keepalive": {}}"#).unwrap();
+        assert_eq!(options.nodelay, Some(true));
+        assert_eq!(options.keepalive, Some(KeepaliveOptions::default()));
+        assert_eq!(options.linger_secs, None);
+    }
+
+    #[test]
+    fn test_validate_local_address() {
+        let options = SocketOptions {
+            local_address: Some("not-an-ip".to_string()),
+            ..Default::default()
+        };
+        assert!(options.validate().is_err());
+
+        // ポートのみ指定した場合は接続先と同じファミリーの任意アドレスにバインドする
+        let options = SocketOptions {
+            local_port: Some(40000),
+            ..Default::default()
+        };
+        let remote: SocketAddr = "[::1]:80".parse().unwrap();
+        assert_eq!(
+            options.local_bind_address(&remote).unwrap(),
+            Some("[::]:40000".parse().unwrap())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_connect_applies_options() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+
+        let options = SocketOptions {
+            nodelay: Some(true),
+            keepalive: Some(KeepaliveOptions {
+                time_secs: Some(30),
+                interval_secs: Some(5),
+                retries: Some(3),
+            }),
+            recv_buffer_size: Some(64 * 1024),
+            linger_secs: Some(0),
+            local_address: Some("127.0.0.1".to_string()),
+            ..Default::default()
+        };
+        let stream = connect(&address, &options).await.unwrap();
+        let (mut peer, peer_addr) = listener.accept().await.unwrap();
+        assert_eq!(peer_addr, stream.local_addr().unwrap());
+
+        let socket = SockRef::from(&stream);
+        assert!(stream.nodelay().unwrap());
+        assert!(socket.keepalive().unwrap());
+        #[cfg(target_os = "linux")]
+        {
+            assert_eq!(socket.tcp_keepalive_time().unwrap(), Duration::from_secs(30));
+            assert_eq!(socket.tcp_keepalive_interval().unwrap(), Duration::from_secs(5));
+            assert_eq!(socket.tcp_keepalive_retries().unwrap(), 3);
+        }
+        assert_eq!(socket.linger().unwrap(), Some(Duration::ZERO));
+        assert!(socket.recv_buffer_size().unwrap() >= 64 * 1024);
+
+        // linger 0でクローズすると相手にはRSTが届く
+        drop(stream);
+        let mut buf = [0u8; 1];
+        let error = tokio::io::AsyncReadExt::read(&mut peer, &mut buf).await.unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::ConnectionReset);
+    }
+}
@@ -2,26 +2,147 @@ use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::error::Error;
 use std::fmt;
+use std::future::Future;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
-use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
-use tokio::net::{TcpListener, TcpStream};
+use tokio::io::{AsyncRead, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket};
 use tokio::sync::Mutex;
-use tokio::task::JoinHandle;
+use tokio::task::{JoinHandle, JoinSet};
+use tokio_util::codec::FramedRead;
+use futures_util::StreamExt;
+use base64::prelude::{Engine as _, BASE64_STANDARD};
 use chrono::{Local, Utc};
 use uuid::Uuid;
 use tauri::{AppHandle, Emitter};
 
+use crate::framing::{FrameCodec, Framing};
+use crate::message_store::{MessagePage, MessageQuery, MessageStore, RetentionPolicy, SharedMessageStore};
+use crate::payload::{parse_payload, PayloadError, PayloadFormat};
+use crate::serial;
+use crate::session_log;
+use crate::socket_options::{self, SocketOptions};
+use crate::tcp_info;
+use crate::text_encoding::TextEncoding;
+use crate::timeouts::{self, ConnectionActivity, ConnectionTimeout, MonitoredReader, MonitoredWriter, SendTimeouts, TcpTimeouts, TimeoutKind};
+use crate::transcript::{self, Direction, Endpoints, TranscriptSource, TranscriptTarget};
+use crate::tls::{self, TlsClientOptions, TlsServerOptions, TlsSessionInfo};
+use crate::ws::{self, WebSocketWriter};
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TcpMessage {
     pub host: String,
     pub port: u16,
     pub message: String,
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 省略時は従来通りCRデリミタを付与
+    #[serde(default)]
+    pub framing: Option<Framing>,
+    /// 接続・送信のタイムアウト
+    #[serde(default)]
+    pub timeouts: SendTimeouts,
 }
 
+/// TCPサーバーの設定
+///
+/// 受け付けたクライアントにはタイムアウトを適用しない（無通信でも切断しない）
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TcpServerConfig {
+    /// サーバーID（省略時は"default"。同じIDのサーバーは再起動される）
+    #[serde(default)]
+    pub id: Option<String>,
     pub host: String,
     pub port: u16,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 指定した場合はTLSを終端するサーバーとして待ち受ける
+    #[serde(default)]
+    pub tls: Option<TlsServerOptions>,
+    /// 待ち受けソケットと受け付けた接続に適用する（ローカルバインドの指定は無視）
+    #[serde(default)]
+    pub socket_options: SocketOptions,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// 接続の通信方式
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Transport {
+    #[default]
+    Tcp,
+    Udp,
+    Multicast,
+    Unix,
+    #[serde(rename = "websocket")]
+    WebSocket,
+    Serial,
+}
+
+/// WebSocketのフレーム種別
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FrameType {
+    #[default]
+    Text,
+    Binary,
+}
+
+/// 接続の状態
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ConnectionState {
+    Connecting,
+    #[default]
+    Connected,
+    /// 切断後、再接続ポリシーに従って再接続を試みている
+    Reconnecting,
+    /// 相手から切断された
+    RemoteClosed,
+    /// 受信中のエラーで切断された
+    Error,
+    /// こちらから切断した
+    Closed,
+}
+
+/// 切断の理由
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CloseReason {
+    /// 相手が正常に切断した（FIN）
+    Fin,
+    /// 相手が接続をリセットした（RST）
+    Reset,
+    Error,
+    /// こちらから切断した
+    Local,
+    /// 接続の確立が時間内に終わらなかった
+    ConnectTimeout,
+    /// 受信がないまま時間が経過した
+    ReadTimeout,
+    /// 送信が時間内に進まなかった
+    WriteTimeout,
+    /// 送受信がないまま時間が経過したためこちらから切断した
+    IdleTimeout,
+}
+
+impl From<TimeoutKind> for CloseReason {
+    fn from(kind: TimeoutKind) -> Self {
+        match kind {
+            TimeoutKind::Connect => CloseReason::ConnectTimeout,
+            TimeoutKind::Read => CloseReason::ReadTimeout,
+            TimeoutKind::Write => CloseReason::WriteTimeout,
+            TimeoutKind::Idle => CloseReason::IdleTimeout,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +152,55 @@ pub struct TcpConnection {
     pub port: u16,
     pub connected: bool,
     pub connected_at: String,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default)]
+    pub state: ConnectionState,
+    #[serde(default)]
+    pub close_reason: Option<CloseReason>,
+    /// 切断・接続失敗時のエラー内容
+    #[serde(default)]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub closed_at: Option<String>,
+    /// 自動再接続に成功した回数
+    #[serde(default)]
+    pub reconnects: u32,
+}
+
+impl TcpConnection {
+    /// 接続済みの状態で生成
+    pub(crate) fn new(id: String, host: String, port: u16, transport: Transport) -> Self {
+        Self {
+            id,
+            host,
+            port,
+            connected: true,
+            connected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            transport,
+            state: ConnectionState::Connected,
+            close_reason: None,
+            error: None,
+            closed_at: None,
+            reconnects: 0,
+        }
+    }
+
+    /// 切断後の状態にする
+    fn mark_closed(&mut self, state: ConnectionState, reason: CloseReason, error: Option<String>) {
+        self.connected = false;
+        self.state = state;
+        self.close_reason = Some(reason);
+        self.error = error;
+        self.closed_at = Some(Local::now().format("%Y-%m-%d %H:%M:%S").to_string());
+    }
+}
+
+/// 接続状態の変化を通知するイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConnectionStateEvent {
+    pub connection: TcpConnection,
+    pub timestamp: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,23 +210,293 @@ pub struct TcpConnectionResult {
     pub error: Option<String>,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TcpConnectionRequest {
     pub host: String,
     pub port: u16,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 指定した場合はTLSで接続
+    #[serde(default)]
+    pub tls: Option<TlsClientOptions>,
+    /// 指定した場合は相手から切断されたときに自動で再接続する
+    #[serde(default)]
+    pub reconnect: Option<ReconnectPolicy>,
+    #[serde(default)]
+    pub timeouts: TcpTimeouts,
+    #[serde(default)]
+    pub socket_options: SocketOptions,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// 自動再接続の設定
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconnectPolicy {
+    /// 1回目の試行までの待ち時間
+    #[serde(default = "default_reconnect_initial_delay_ms")]
+    pub initial_delay_ms: u64,
+    /// 待ち時間の上限
+    #[serde(default = "default_reconnect_max_delay_ms")]
+    pub max_delay_ms: u64,
+    /// 試行ごとに待ち時間に掛ける倍率
+    #[serde(default = "default_reconnect_multiplier")]
+    pub multiplier: f64,
+    /// 待ち時間に加える揺らぎの割合（0.0〜1.0）
+    #[serde(default = "default_reconnect_jitter")]
+    pub jitter: f64,
+    /// 最大試行回数（省略時は無制限）
+    #[serde(default)]
+    pub max_attempts: Option<u32>,
+    /// 接続直後と再接続のたびに送信するメッセージ
+    #[serde(default)]
+    pub init_script: Vec<String>,
+    #[serde(default)]
+    pub init_payload_format: PayloadFormat,
+}
+
+fn default_reconnect_initial_delay_ms() -> u64 {
+    500
+}
+
+fn default_reconnect_max_delay_ms() -> u64 {
+    30_000
+}
+
+fn default_reconnect_multiplier() -> f64 {
+    2.0
+}
+
+fn default_reconnect_jitter() -> f64 {
+    0.2
+}
+
+impl ReconnectPolicy {
+    /// n回目（1始まり）の試行までの待ち時間を計算する
+    ///
+    /// `random`は0.0〜1.0の乱数で、待ち時間を±jitterの範囲で揺らす
+    pub(crate) fn delay(&self, attempt: u32, random: f64) -> std::time::Duration {
+        let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+        let base = (self.initial_delay_ms as f64 * self.multiplier.max(1.0).powi(exponent))
+            .min(self.max_delay_ms as f64);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        let factor = 1.0 - jitter + 2.0 * jitter * random.clamp(0.0, 1.0);
+        std::time::Duration::from_millis((base * factor).round() as u64)
+    }
+}
+
+/// 再接続の試行ごとに発行するイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReconnectAttemptEvent {
+    pub connection_id: String,
+    /// 今回の試行番号（1始まり、再接続に成功するとリセット）
+    pub attempt: u32,
+    pub max_attempts: Option<u32>,
+    /// 試行前に待った時間
+    pub delay_ms: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub timestamp: String,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct TcpMessageOnConnection {
     pub connection_id: String,
     pub message: String,
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+}
+
+/// 起動中のサーバー
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpServerInfo {
+    pub id: String,
+    /// 待ち受けアドレス（Unixソケットの場合はパス）
+    pub address: String,
+    pub transport: Transport,
+    pub tls: bool,
+    pub started_at: String,
+    pub client_count: usize,
+    pub message_count: usize,
+}
+
+/// サーバーが受け付けたクライアント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerClientInfo {
+    pub id: String,
+    pub server_id: String,
+    pub client_addr: String,
+    pub connected_at: String,
+    /// TLSサーバーの場合のセッション情報（mTLSのクライアント証明書を含む）
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tls: Option<TlsSessionInfo>,
+    /// Unixソケットの場合の相手プロセスの資格情報
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gid: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pid: Option<i32>,
+}
+
+impl ServerClientInfo {
+    pub(crate) fn new(server_id: &str, client_id: &str, client_addr: String) -> Self {
+        Self {
+            id: client_id.to_string(),
+            server_id: server_id.to_string(),
+            client_addr,
+            connected_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            tls: None,
+            uid: None,
+            gid: None,
+            pid: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerClientMessage {
+    pub client_id: String,
+    pub message: String,
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerBroadcastMessage {
+    /// 送信先のサーバーID（省略時は"default"）
+    #[serde(default)]
+    pub server_id: Option<String>,
+    pub message: String,
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerBroadcastResult {
+    pub success: bool,
+    /// 送信できたクライアント数
+    pub sent: usize,
+    /// 送信に失敗したクライアントのIDとエラー内容
+    pub failures: Vec<ServerBroadcastFailure>,
+    pub timestamp: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ServerBroadcastFailure {
+    pub client_id: String,
+    pub error: String,
+}
+
+/// サーバー停止時の動作
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ServerStopMode {
+    /// 受信タスクを即座に中断してソケットを破棄する
+    #[default]
+    Abort,
+    /// 受付を止め、各クライアントにFINを送って相手が切断するまで待つ
+    Graceful,
+}
+
+fn default_stop_timeout_ms() -> u64 {
+    3000
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpServerStopOptions {
+    #[serde(default)]
+    pub mode: ServerStopMode,
+    /// 切断前に各クライアントへ送るメッセージ（Gracefulモードのみ）
+    #[serde(default)]
+    pub goodbye_message: Option<String>,
+    #[serde(default)]
+    pub payload_format: PayloadFormat,
+    /// Gracefulモードでクライアントの切断を待つ時間
+    #[serde(default = "default_stop_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for TcpServerStopOptions {
+    fn default() -> Self {
+        Self {
+            mode: ServerStopMode::default(),
+            goodbye_message: None,
+            payload_format: PayloadFormat::default(),
+            timeout_ms: default_stop_timeout_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TcpServerStopResult {
+    pub success: bool,
+    pub message: String,
+    /// 停止時にサーバーが起動していたかどうか
+    pub was_running: bool,
+    /// 切断したクライアント数
+    pub closed_clients: usize,
+    /// タイムアウトまでに切断が完了せず、強制的に中断したクライアント数
+    pub timed_out_clients: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PayloadPreview {
+    pub length: usize,
+    pub hex: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TcpReceivedMessage {
+    /// 受信データを接続の文字エンコーディングでデコードしたもの（不正なバイトは置換文字になる）
     pub message: String,
+    /// デコード時に置換文字が発生したかどうか
+    pub lossy: bool,
     pub timestamp: String,
     pub client_addr: String,
+    /// 受信フレームの生バイト列
+    #[serde(skip)]
+    pub raw: Vec<u8>,
+    /// 生バイト列の16進表記（例: "02 41 42 03"）
+    pub hex: String,
+    /// 生バイト列のBase64表記
+    pub base64: String,
+    /// WebSocketで受信した場合のフレーム種別
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frame_type: Option<FrameType>,
+    /// 保存時に割り当てる通し番号（全接続・サーバーで単調増加）
+    #[serde(default)]
+    pub seq: u64,
+}
+
+impl TcpReceivedMessage {
+    /// 受信したフレームからメッセージを生成
+    pub(crate) fn from_frame(frame: Vec<u8>, client_addr: String, encoding: TextEncoding) -> Self {
+        let (message, lossy) = encoding.decode(&frame);
+        Self {
+            message,
+            lossy,
+            timestamp: Utc::now().to_rfc3339(),
+            client_addr,
+            hex: format_hex(&frame),
+            base64: BASE64_STANDARD.encode(&frame),
+            raw: frame,
+            frame_type: None,
+            seq: 0,
+        }
+    }
+}
+
+/// バイト列を空白区切りの16進文字列に変換
+fn format_hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -72,6 +512,9 @@ pub struct TcpReceiveResult {
     pub success: bool,
     pub messages: Vec<TcpReceivedMessage>,
     pub error: Option<String>,
+    /// 保持ポリシーにより破棄したメッセージの累計
+    #[serde(default)]
+    pub dropped: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -80,6 +523,23 @@ pub struct TcpMessageReceivedEvent {
     pub message: TcpReceivedMessage,
 }
 
+/// サーバーがクライアントから受信したメッセージのイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerMessageReceivedEvent {
+    pub server_id: String,
+    pub client_id: String,
+    pub message: TcpReceivedMessage,
+}
+
+/// サーバーのクライアントが切断されたイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServerClientDisconnectedEvent {
+    pub client: ServerClientInfo,
+    /// "closed"（相手が切断）, "error", "kicked", "server_stopped"
+    pub reason: String,
+    pub error: Option<String>,
+}
+
 #[derive(Debug)]
 pub enum TcpError {
     ConnectionFailed(String),
@@ -87,6 +547,16 @@ pub enum TcpError {
     InvalidAddress(String),
     ServerStartFailed(String),
     ConnectionNotFound(String),
+    InvalidFraming(String),
+    InvalidPayload(PayloadError),
+    TlsFailed(String),
+    ConnectTimeout(String),
+    ReadTimeout(String),
+    WriteTimeout(String),
+    IdleTimeout(String),
+    Unsupported(String),
+    StorageFailed(String),
+    InvalidQuery(String),
 }
 
 impl fmt::Display for TcpError {
@@ -97,18 +567,79 @@ impl fmt::Display for TcpError {
             TcpError::InvalidAddress(msg) => write!(f, "Invalid address: {}", msg),
             TcpError::ServerStartFailed(msg) => write!(f, "Server start failed: {}", msg),
             TcpError::ConnectionNotFound(msg) => write!(f, "Connection not found: {}", msg),
+            TcpError::InvalidFraming(msg) => write!(f, "Invalid framing: {}", msg),
+            TcpError::InvalidPayload(err) => write!(f, "Invalid payload: {}", err),
+            TcpError::TlsFailed(msg) => write!(f, "TLS failed: {}", msg),
+            TcpError::ConnectTimeout(msg) => write!(f, "Connect timeout: {}", msg),
+            TcpError::ReadTimeout(msg) => write!(f, "Read timeout: {}", msg),
+            TcpError::WriteTimeout(msg) => write!(f, "Write timeout: {}", msg),
+            TcpError::IdleTimeout(msg) => write!(f, "Idle timeout: {}", msg),
+            TcpError::Unsupported(msg) => write!(f, "Unsupported: {}", msg),
+            TcpError::StorageFailed(msg) => write!(f, "Storage failed: {}", msg),
+            TcpError::InvalidQuery(msg) => write!(f, "Invalid query: {}", msg),
         }
     }
 }
 
 impl Error for TcpError {}
 
+impl From<ConnectionTimeout> for TcpError {
+    fn from(timeout: ConnectionTimeout) -> Self {
+        let msg = timeout.to_string();
+        match timeout.kind {
+            TimeoutKind::Connect => TcpError::ConnectTimeout(msg),
+            TimeoutKind::Read => TcpError::ReadTimeout(msg),
+            TimeoutKind::Write => TcpError::WriteTimeout(msg),
+            TimeoutKind::Idle => TcpError::IdleTimeout(msg),
+        }
+    }
+}
+
+impl TcpError {
+    /// フロントエンドでエラーを判別するための種別名
+    pub fn kind(&self) -> &'static str {
+        match self {
+            TcpError::ConnectionFailed(_) => "connection_failed",
+            TcpError::SendFailed(_) => "send_failed",
+            TcpError::InvalidAddress(_) => "invalid_address",
+            TcpError::ServerStartFailed(_) => "server_start_failed",
+            TcpError::ConnectionNotFound(_) => "connection_not_found",
+            TcpError::InvalidFraming(_) => "invalid_framing",
+            TcpError::InvalidPayload(_) => "invalid_payload",
+            TcpError::TlsFailed(_) => "tls_failed",
+            TcpError::ConnectTimeout(_) => "connect_timeout",
+            TcpError::ReadTimeout(_) => "read_timeout",
+            TcpError::WriteTimeout(_) => "write_timeout",
+            TcpError::IdleTimeout(_) => "idle_timeout",
+            TcpError::Unsupported(_) => "unsupported",
+            TcpError::StorageFailed(_) => "storage_failed",
+            TcpError::InvalidQuery(_) => "invalid_query",
+        }
+    }
+}
+
+/// `{kind, message, detail}` の形でシリアライズする
+///
+/// `detail` はペイロードの解析エラーのときだけ付き、エラー位置を含む
 impl Serialize for TcpError {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        use serde::ser::SerializeStruct;
+
+        let detail = match self {
+            TcpError::InvalidPayload(err) => Some(err),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("TcpError", 2 + usize::from(detail.is_some()))?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        match detail {
+            Some(detail) => state.serialize_field("detail", detail)?,
+            None => state.skip_field("detail")?,
+        }
+        state.end()
     }
 }
 
@@ -123,10 +654,21 @@ pub async fn send_tcp_message(tcp_message: TcpMessage) -> Result<String, TcpErro
         ));
     }
 
+    let payload = parse_payload(&tcp_message.message, tcp_message.payload_format, tcp_message.encoding)
+        .map_err(TcpError::InvalidPayload)?;
+    let framing = tcp_message.framing.unwrap_or(Framing::Cr);
+    framing.validate(tcp_message.encoding).map_err(TcpError::InvalidFraming)?;
+    let frame = framing
+        .encode(&payload, tcp_message.encoding)
+        .map_err(|e| TcpError::InvalidFraming(e.to_string()))?;
+
     // TCP接続を確立
-    let mut stream = match TcpStream::connect(&address).await {
+    let mut stream = match with_timeout(TimeoutKind::Connect, tcp_message.timeouts.connect(), TcpStream::connect(&address)).await {
         Ok(stream) => stream,
         Err(e) => {
+            if let Some(timeout) = ConnectionTimeout::from_io(&e) {
+                return Err(timeout.into());
+            }
             return Err(TcpError::ConnectionFailed(format!(
                 "Failed to connect to {}: {}",
                 address, e
@@ -134,11 +676,11 @@ pub async fn send_tcp_message(tcp_message: TcpMessage) -> Result<String, TcpErro
         }
     };
 
-    // メッセージにCRデリミタを追加
-    let message_with_cr = format!("{}\r", tcp_message.message);
-
     // メッセージを送信
-    if let Err(e) = stream.write_all(message_with_cr.as_bytes()).await {
+    if let Err(e) = with_timeout(TimeoutKind::Write, tcp_message.timeouts.write(), stream.write_all(&frame)).await {
+        if let Some(timeout) = ConnectionTimeout::from_io(&e) {
+            return Err(timeout.into());
+        }
         return Err(TcpError::SendFailed(format!(
             "Failed to send message: {}",
             e
@@ -156,227 +698,1292 @@ pub async fn send_tcp_message(tcp_message: TcpMessage) -> Result<String, TcpErro
     ))
 }
 
-// TCP接続管理のためのグローバル状態
-struct ConnectionData {
-    writer: Arc<Mutex<tokio::net::tcp::OwnedWriteHalf>>,
-    messages: Arc<Mutex<Vec<TcpReceivedMessage>>>,
-    receiver_handle: Option<JoinHandle<()>>,
+/// 指定された時間内に終わらなければタイムアウトのエラーにする
+async fn with_timeout<T>(
+    kind: TimeoutKind,
+    timeout: Option<std::time::Duration>,
+    future: impl Future<Output = io::Result<T>>,
+) -> io::Result<T> {
+    match timeout {
+        Some(after) => tokio::time::timeout(after, future)
+            .await
+            .unwrap_or_else(|_| Err(ConnectionTimeout::new(kind, after).into())),
+        None => future.await,
+    }
 }
 
-static CONNECTIONS: std::sync::OnceLock<Arc<Mutex<HashMap<String, ConnectionData>>>> = std::sync::OnceLock::new();
-
-// 旧サーバー機能のためのグローバル状態（後方互換性）
-static RECEIVED_MESSAGES: std::sync::OnceLock<Arc<Mutex<Vec<TcpReceivedMessage>>>> = std::sync::OnceLock::new();
-static SERVER_HANDLE: std::sync::OnceLock<Arc<Mutex<Option<tokio::task::JoinHandle<()>>>>> = std::sync::OnceLock::new();
+// 平文TCPとTLSを同じように扱うための型
+pub(crate) type BoxedReader = Box<dyn AsyncRead + Send + Unpin>;
+pub(crate) type BoxedWriter = Box<dyn AsyncWrite + Send + Unpin>;
 
-// アプリハンドルを保存するためのグローバル状態
-static APP_HANDLE: std::sync::OnceLock<AppHandle> = std::sync::OnceLock::new();
+/// 接続ごとの送信先
+pub(crate) enum ConnectionWriter {
+    Stream(BoxedWriter),
+    Udp {
+        socket: Arc<UdpSocket>,
+        /// 固定の送信先（未指定の場合は最後に受信した相手へ返信する）
+        target: Option<SocketAddr>,
+        last_peer: Arc<std::sync::Mutex<Option<SocketAddr>>>,
+        /// マルチキャストグループに参加している場合は切断時に離脱する
+        membership: Option<MulticastMembership>,
+    },
+    WebSocket(WebSocketWriter),
+}
 
-/// AppHandleを初期化する関数
-pub fn init_app_handle(app_handle: AppHandle) {
-    APP_HANDLE.set(app_handle).ok();
+/// 参加中のマルチキャストグループ
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum MulticastMembership {
+    V4 { group: std::net::Ipv4Addr, interface: std::net::Ipv4Addr },
+    V6 { group: std::net::Ipv6Addr, interface: u32 },
 }
 
-#[tauri::command]
-pub async fn start_tcp_server(config: TcpServerConfig) -> Result<String, TcpError> {
-    let address = format!("{}:{}", config.host, config.port);
-    
-    // アドレスの妥当性をチェック
-    if config.host.is_empty() || config.port == 0 {
-        return Err(TcpError::InvalidAddress(
-            "Host and port must be valid".to_string(),
-        ));
+impl ConnectionWriter {
+    async fn write_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        match self {
+            ConnectionWriter::Stream(writer) => writer.write_all(frame).await,
+            ConnectionWriter::Udp { socket, target, last_peer, .. } => {
+                let peer = target.or(*last_peer.lock().unwrap()).ok_or_else(|| {
+                    io::Error::new(io::ErrorKind::NotConnected, "No UDP destination yet")
+                })?;
+                socket.send_to(frame, peer).await.map(|_| ())
+            }
+            ConnectionWriter::WebSocket(writer) => writer.send_frame(frame).await,
+        }
     }
 
-    // 既存のサーバーを停止
-    stop_tcp_server().await.ok();
-
-    // メッセージ履歴をクリア
-    let messages = RECEIVED_MESSAGES.get_or_init(|| Arc::new(Mutex::new(Vec::new())));
-    messages.lock().await.clear();
-
-    // TCP リスナーを開始
-    let listener = match TcpListener::bind(&address).await {
-        Ok(listener) => listener,
-        Err(e) => {
-            return Err(TcpError::ServerStartFailed(format!(
-                "Failed to bind to {}: {}",
-                address, e
-            )));
+    async fn flush(&mut self) -> io::Result<()> {
+        match self {
+            ConnectionWriter::Stream(writer) => writer.flush().await,
+            ConnectionWriter::Udp { .. } => Ok(()),
+            ConnectionWriter::WebSocket(writer) => writer.flush().await,
         }
-    };
+    }
 
-    let messages_clone = Arc::clone(messages);
-    
-    // サーバータスクを開始
-    let server_task = tokio::spawn(async move {
-        loop {
-            match listener.accept().await {
-                Ok((stream, addr)) => {
-                    let messages = Arc::clone(&messages_clone);
-                    tokio::spawn(handle_tcp_client(stream, addr.to_string(), messages));
-                }
-                Err(e) => {
-                    log::error!("Failed to accept connection: {}", e);
-                    break;
-                }
-            }
+    async fn shutdown(&mut self) -> io::Result<()> {
+        match self {
+            ConnectionWriter::Stream(writer) => writer.shutdown().await,
+            ConnectionWriter::Udp { socket, membership, .. } => match membership.take() {
+                Some(MulticastMembership::V4 { group, interface }) => socket.leave_multicast_v4(group, interface),
+                Some(MulticastMembership::V6 { group, interface }) => socket.leave_multicast_v6(&group, interface),
+                None => Ok(()),
+            },
+            ConnectionWriter::WebSocket(writer) => writer.close().await,
         }
-    });
+    }
+}
 
-    // サーバーハンドルを保存
-    let handle_storage = SERVER_HANDLE.get_or_init(|| Arc::new(Mutex::new(None)));
-    *handle_storage.lock().await = Some(server_task);
+// TCP接続管理のためのグローバル状態
+pub(crate) struct ConnectionData {
+    pub(crate) info: TcpConnection,
+    pub(crate) writer: Arc<Mutex<ConnectionWriter>>,
+    pub(crate) framing: Framing,
+    pub(crate) encoding: TextEncoding,
+    pub(crate) messages: SharedMessageStore,
+    pub(crate) receiver_handle: Option<JoinHandle<()>>,
+    pub(crate) tls_info: Option<TlsSessionInfo>,
+}
+
+static CONNECTIONS: std::sync::OnceLock<Arc<Mutex<HashMap<String, ConnectionData>>>> = std::sync::OnceLock::new();
 
-    Ok(format!("TCP server started on {}", address))
+/// 接続をレジストリに登録し、受信タスクを開始する
+///
+/// 受信処理が終了した時点で、その結果から切断理由を判定して状態を更新する
+pub(crate) async fn register_connection(
+    mut connection_data: ConnectionData,
+    receiver: impl Future<Output = io::Result<()>> + Send + 'static,
+) {
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    let connection_id = connection_data.info.id.clone();
+    emit_connection_state(&connection_data.info);
+
+    // 受信タスクがすぐに終了しても状態を更新できるよう、登録してから開始する
+    let mut connections_guard = connections.lock().await;
+    connection_data.receiver_handle = Some(tokio::spawn({
+        let connection_id = connection_id.clone();
+        async move {
+            let result = receiver.await;
+            mark_connection_closed(&connection_id, result).await;
+        }
+    }));
+    connections_guard.insert(connection_id, connection_data);
 }
 
-#[tauri::command]
-pub async fn stop_tcp_server() -> Result<String, TcpError> {
-    let handle_storage = SERVER_HANDLE.get_or_init(|| Arc::new(Mutex::new(None)));
-    let mut handle_guard = handle_storage.lock().await;
-    
-    if let Some(handle) = handle_guard.take() {
-        handle.abort();
-        Ok("TCP server stopped".to_string())
-    } else {
-        Ok("No server was running".to_string())
+fn emit_connection_state(connection: &TcpConnection) {
+    if connection.state != ConnectionState::Connecting {
+        transcript::record_system(&TranscriptTarget::connection(&connection.id, None), describe_state(connection));
     }
+    let event = ConnectionStateEvent {
+        connection: connection.clone(),
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    emit_event("tcp_connection_state_changed", &event);
 }
 
-#[tauri::command]
-pub async fn get_received_messages() -> Result<TcpReceiveResult, TcpError> {
-    let messages = RECEIVED_MESSAGES.get_or_init(|| Arc::new(Mutex::new(Vec::new())));
-    let messages_guard = messages.lock().await;
-    
-    Ok(TcpReceiveResult {
-        success: true,
-        messages: messages_guard.clone(),
-        error: None,
-    })
+/// 受信処理の結果から切断理由を判定し、接続の状態を更新する
+async fn mark_connection_closed(connection_id: &str, result: io::Result<()>) {
+    let (state, reason, error) = close_outcome(&result);
+
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    let mut connections_guard = connections.lock().await;
+    // こちらから切断した場合は既にレジストリから削除されている
+    if let Some(connection_data) = connections_guard.get_mut(connection_id) {
+        log::info!("Connection {} closed ({:?})", connection_id, reason);
+        connection_data.info.mark_closed(state, reason, error);
+        emit_connection_state(&connection_data.info);
+    }
+    // 閉じたソケットを保持し続けないようTCP_INFOの登録も破棄する
+    tcp_info::forget_socket(connection_id);
 }
 
-async fn handle_tcp_client(
-    stream: TcpStream,
-    client_addr: String,
-    messages: Arc<Mutex<Vec<TcpReceivedMessage>>>,
-) {
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
+/// 接続状態の変化を記録用の文字列にする（例: "remote_closed (reset): Connection reset by peer"）
+fn describe_state(connection: &TcpConnection) -> String {
+    fn name<T: Serialize>(value: &T) -> String {
+        serde_json::to_value(value)
+            .ok()
+            .and_then(|value| value.as_str().map(str::to_string))
+            .unwrap_or_default()
+    }
 
-    loop {
-        line.clear();
-        match reader.read_line(&mut line).await {
-            Ok(0) => {
-                // 接続が閉じられた
-                break;
-            }
-            Ok(_) => {
-                let message = line.trim_end_matches('\r').trim_end_matches('\n');
-                if !message.is_empty() {
-                    let received_msg = TcpReceivedMessage {
-                        message: message.to_string(),
-                        timestamp: Utc::now().to_rfc3339(),
-                        client_addr: client_addr.clone(),
-                    };
-                    
-                    let mut messages_guard = messages.lock().await;
-                    messages_guard.push(received_msg);
-                    
-                    log::info!("Received message from {}: {}", client_addr, message);
-                }
-            }
-            Err(e) => {
-                log::error!("Error reading from {}: {}", client_addr, e);
-                break;
-            }
+    let mut text = name(&connection.state);
+    if let Some(reason) = &connection.close_reason {
+        text = format!("{} ({})", text, name(reason));
+    }
+    if let Some(error) = &connection.error {
+        text = format!("{}: {}", text, error);
+    }
+    text
+}
+
+/// TCP接続の両端のアドレス（取得できない場合はNone）
+pub(crate) fn stream_endpoints(stream: &TcpStream, initiated_locally: bool) -> Option<Endpoints> {
+    match (stream.local_addr(), stream.peer_addr()) {
+        (Ok(local), Ok(remote)) => Some(Endpoints {
+            local,
+            remote,
+            initiated_locally,
+        }),
+        (Err(e), _) | (_, Err(e)) => {
+            log::warn!("Failed to read socket addresses: {}", e);
+            None
         }
     }
 }
 
-// 新しい接続管理機能
-#[tauri::command]
-pub async fn connect_tcp(app_handle: AppHandle, request: TcpConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
-    // AppHandleを保存
-    APP_HANDLE.set(app_handle).ok();
-    let address = format!("{}:{}", request.host, request.port);
-    
-    // アドレスの妥当性をチェック
-    if request.host.is_empty() || request.port == 0 {
-        return Err(TcpError::InvalidAddress(
-            "Host and port must be valid".to_string(),
-        ));
+/// PCAPNGに書き出せるようTCP接続の両端のアドレスを記録する
+fn record_endpoints(target: &TranscriptTarget, stream: &TcpStream, initiated_locally: bool) {
+    if let Some(endpoints) = stream_endpoints(stream, initiated_locally) {
+        transcript::record_endpoints(target, endpoints);
     }
+}
 
-    // TCP接続を確立
-    let stream = match TcpStream::connect(&address).await {
-        Ok(stream) => stream,
-        Err(e) => {
-            return Err(TcpError::ConnectionFailed(format!(
-                "Failed to connect to {}: {}",
-                address, e
-            )));
+/// 受信処理の結果を切断後の状態と理由に変換する
+fn close_outcome(result: &io::Result<()>) -> (ConnectionState, CloseReason, Option<String>) {
+    if let Some(timeout) = result.as_ref().err().and_then(ConnectionTimeout::from_io) {
+        // アイドル切断はこちらから閉じたものとして扱う
+        let state = match timeout.kind {
+            TimeoutKind::Idle => ConnectionState::Closed,
+            _ => ConnectionState::Error,
+        };
+        return (state, timeout.kind.into(), Some(TcpError::from(timeout).to_string()));
+    }
+
+    match result {
+        Ok(()) => (ConnectionState::RemoteClosed, CloseReason::Fin, None),
+        Err(e) if matches!(e.kind(), io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted) => {
+            (ConnectionState::RemoteClosed, CloseReason::Reset, Some(e.to_string()))
         }
-    };
+        // TLSのclose_notifyなしでTCPが閉じられた場合
+        Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => (ConnectionState::RemoteClosed, CloseReason::Fin, None),
+        Err(e) => (ConnectionState::Error, CloseReason::Error, Some(e.to_string())),
+    }
+}
 
-    // 接続IDを生成
-    let connection_id = Uuid::new_v4().to_string();
-    let connected_at = Local::now().format("%Y-%m-%d %H:%M:%S").to_string();
-
-    let connection = TcpConnection {
-        id: connection_id.clone(),
-        host: request.host.clone(),
-        port: request.port,
-        connected: true,
-        connected_at,
-    };
+/// 登録済みの接続データを更新し、状態の変化を通知する
+///
+/// 接続が既に削除されている場合はfalseを返す
+async fn update_connection(connection_id: &str, update: impl FnOnce(&mut ConnectionData)) -> bool {
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    let mut connections_guard = connections.lock().await;
+    match connections_guard.get_mut(connection_id) {
+        Some(connection_data) => {
+            update(connection_data);
+            emit_connection_state(&connection_data.info);
+            true
+        }
+        None => false,
+    }
+}
+
+/// 接続で受信したメッセージを保存し、フロントエンドに通知する
+///
+/// `wire`は回線上で受信したバイト列（Noneの場合はメッセージの生バイト列と同じ）
+pub(crate) async fn deliver_connection_message(
+    connection_id: &str,
+    messages: &SharedMessageStore,
+    received_msg: TcpReceivedMessage,
+    wire: Option<Vec<u8>>,
+) {
+    log::info!("Received message on connection {}: {}", connection_id, received_msg.message);
 
-    // ストリームを読み取り用と書き込み用に分割
-    let (reader, writer) = stream.into_split();
-    let writer_arc = Arc::new(Mutex::new(writer));
-    let messages = Arc::new(Mutex::new(Vec::new()));
+    let received_msg = messages.lock().await.push(received_msg);
 
-    // 受信タスクを開始
-    let messages_clone = Arc::clone(&messages);
-    let connection_id_clone = connection_id.clone();
-    
-    let receiver_handle = tokio::spawn(async move {
-        handle_connection_receiver(reader, connection_id_clone, messages_clone).await;
-    });
+    // UDPのように送信元が変わる場合だけ相手のアドレスを残す
+    let peer = received_msg
+        .client_addr
+        .parse::<SocketAddr>()
+        .ok()
+        .map(|addr| addr.to_string());
+    transcript::record_frame(
+        &TranscriptTarget::connection(connection_id, peer),
+        Direction::Rx,
+        &received_msg.raw,
+        wire.as_deref().unwrap_or(&received_msg.raw),
+        received_msg.message.clone(),
+        received_msg.timestamp.clone(),
+    );
 
-    // 接続データを作成
-    let connection_data = ConnectionData {
-        writer: writer_arc,
-        messages,
-        receiver_handle: Some(receiver_handle),
+    // イベントを発行してフロントエンドに通知
+    let event = TcpMessageReceivedEvent {
+        connection_id: connection_id.to_string(),
+        message: received_msg,
     };
+    emit_event("tcp_message_received", &event);
+}
 
-    // 接続を登録
-    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
-    connections.lock().await.insert(connection_id.clone(), connection_data);
+static NEXT_MESSAGE_SEQ: std::sync::atomic::AtomicU64 = std::sync::atomic::AtomicU64::new(1);
 
-    Ok(TcpConnectionResult {
-        success: true,
-        connection: Some(connection),
-        error: None,
-    })
+/// 受信メッセージの通し番号を採番する
+pub(crate) fn next_message_seq() -> u64 {
+    NEXT_MESSAGE_SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
 }
 
-#[tauri::command]
-pub async fn disconnect_tcp(connection_id: String) -> Result<String, TcpError> {
+/// AppHandleが初期化済みであればフロントエンドにイベントを発行する
+pub(crate) fn emit_event<S: Serialize + Clone>(event: &str, payload: &S) {
+    if let Some(app_handle) = APP_HANDLE.get() {
+        if let Err(e) = app_handle.emit(event, payload) {
+            log::error!("Failed to emit {} event: {}", event, e);
+        }
+    }
+}
+
+/// IDを指定しない呼び出しで使うサーバーID（単一サーバー時代との互換用）
+pub const DEFAULT_SERVER_ID: &str = "default";
+
+/// 応答しないクライアントがハンドシェイク途中で残り続けないようにするための上限
+const TLS_HANDSHAKE_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(10);
+
+pub(crate) fn server_id_or_default(server_id: Option<String>) -> String {
+    server_id
+        .filter(|id| !id.is_empty())
+        .unwrap_or_else(|| DEFAULT_SERVER_ID.to_string())
+}
+
+// サーバーが受け付けたクライアントの管理
+struct ServerClient {
+    info: ServerClientInfo,
+    writer: Arc<Mutex<ConnectionWriter>>,
+    handle: JoinHandle<()>,
+}
+
+type ServerClients = Arc<Mutex<HashMap<String, ServerClient>>>;
+
+/// サーバーごとの設定・受信メッセージ・クライアント一覧
+#[derive(Clone)]
+pub(crate) struct ServerContext {
+    pub(crate) id: String,
+    framing: Framing,
+    encoding: TextEncoding,
+    messages: SharedMessageStore,
+    clients: ServerClients,
+    /// 停止後にハンドシェイクを終えたクライアントを登録しないよう、停止時にfalseにする
+    running: Arc<AtomicBool>,
+}
+
+// サーバー管理のためのグローバル状態
+struct ServerData {
+    context: ServerContext,
+    address: String,
+    transport: Transport,
+    tls: bool,
+    started_at: String,
+    /// 停止後も受信メッセージを参照できるよう、エントリは同じIDで再起動するまで残す
+    handle: Option<JoinHandle<()>>,
+}
+
+static SERVERS: std::sync::OnceLock<Arc<Mutex<HashMap<String, ServerData>>>> = std::sync::OnceLock::new();
+
+fn servers() -> &'static Arc<Mutex<HashMap<String, ServerData>>> {
+    SERVERS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+// アプリハンドルを保存するためのグローバル状態
+static APP_HANDLE: std::sync::OnceLock<AppHandle> = std::sync::OnceLock::new();
+
+/// AppHandleを初期化する関数
+pub fn init_app_handle(app_handle: AppHandle) {
+    APP_HANDLE.set(app_handle).ok();
+}
+
+/// 同じIDの既存サーバーを停止し、新しいサーバーの状態を用意する
+pub(crate) async fn prepare_server(
+    server_id: &str,
+    framing: Framing,
+    encoding: TextEncoding,
+    retention: RetentionPolicy,
+) -> ServerContext {
+    // 中断モードではメッセージを送らないため失敗しない
+    stop_server(server_id, &TcpServerStopOptions::default()).await.ok();
+    // 送受信記録は再起動後も続けて記録する（PCAPNGでは別のTCP接続として書き出す）
+    transcript::set_retention(&TranscriptSource::Server(server_id.to_string()), retention.clone());
+
+    ServerContext {
+        id: server_id.to_string(),
+        framing,
+        encoding,
+        messages: MessageStore::shared(retention),
+        clients: Arc::new(Mutex::new(HashMap::new())),
+        running: Arc::new(AtomicBool::new(true)),
+    }
+}
+
+/// 起動したサーバーをレジストリに登録する
+pub(crate) async fn register_server(
+    context: ServerContext,
+    address: String,
+    transport: Transport,
+    tls: bool,
+    server_task: JoinHandle<()>,
+) {
+    transcript::record_system(&TranscriptTarget::server(&context.id), format!("server started on {}", address));
+    let server_data = ServerData {
+        context,
+        address,
+        transport,
+        tls,
+        started_at: Local::now().format("%Y-%m-%d %H:%M:%S").to_string(),
+        handle: Some(server_task),
+    };
+    let previous = servers().lock().await.insert(server_data.context.id.clone(), server_data);
+
+    // 準備から登録までの間に同じIDで起動されたサーバーがあれば停止する
+    if let Some(mut previous) = previous {
+        if let Some(handle) = previous.handle.take() {
+            handle.abort();
+            abort_server_clients(&previous.context.clients).await;
+        }
+    }
+}
+
+/// サーバーを停止し、接続中のクライアントを切断する
+///
+/// 起動中だった場合は切断したクライアント数とタイムアウトしたクライアント数を返す
+pub(crate) async fn stop_server(server_id: &str, options: &TcpServerStopOptions) -> Result<Option<(usize, usize)>, TcpError> {
+    let mut servers_guard = servers().lock().await;
+    let Some(server_data) = servers_guard.get_mut(server_id) else {
+        return Ok(None);
+    };
+    if server_data.handle.is_none() {
+        return Ok(None);
+    }
+    let context = server_data.context.clone();
+
+    // 送信できないメッセージの場合はサーバーを止める前にエラーにする
+    let goodbye = match (&options.goodbye_message, options.mode) {
+        (Some(message), ServerStopMode::Graceful) => {
+            let payload = parse_payload(message, options.payload_format, context.encoding)
+                .map_err(TcpError::InvalidPayload)?;
+            let frame = context.framing.encode(&payload, context.encoding).map_err(|e| TcpError::InvalidFraming(e.to_string()))?;
+            Some((payload, frame))
+        }
+        _ => None,
+    };
+
+    let handle = server_data.handle.take();
+    drop(servers_guard); // Release the lock early
+    context.running.store(false, Ordering::SeqCst);
+
+    // 受付ループを止め、タスクの終了を待ってリスナーを閉じる
+    if let Some(handle) = handle {
+        handle.abort();
+        let _ = handle.await;
+    }
+
+    let counts = match options.mode {
+        ServerStopMode::Abort => (abort_server_clients(&context.clients).await, 0),
+        ServerStopMode::Graceful => {
+            let clients: Vec<ServerClient> = context.clients.lock().await.drain().map(|(_, client)| client).collect();
+            let timeout = std::time::Duration::from_millis(options.timeout_ms);
+            let results = futures_util::future::join_all(
+                clients
+                    .into_iter()
+                    .map(|client| close_server_client_gracefully(client, goodbye.as_ref(), context.encoding, timeout)),
+            )
+            .await;
+            (results.len(), results.iter().filter(|completed| !**completed).count())
+        }
+    };
+    transcript::record_system(&TranscriptTarget::server(server_id), "server stopped".to_string());
+    Ok(Some(counts))
+}
+
+/// 起動中のサーバーの待ち受けアドレス
+pub(crate) async fn running_server_address(server_id: &str) -> Option<String> {
+    servers()
+        .lock()
+        .await
+        .get(server_id)
+        .filter(|server_data| server_data.handle.is_some())
+        .map(|server_data| server_data.address.clone())
+}
+
+/// サーバーの受信メッセージを取得（既定のサーバーが未起動の場合は空）
+async fn server_messages(server_id: Option<String>) -> Result<Option<SharedMessageStore>, TcpError> {
+    let server_id = server_id_or_default(server_id);
+    match servers().lock().await.get(&server_id) {
+        Some(server_data) => Ok(Some(Arc::clone(&server_data.context.messages))),
+        None if server_id == DEFAULT_SERVER_ID => Ok(None),
+        None => Err(TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id))),
+    }
+}
+
+#[tauri::command]
+pub async fn start_tcp_server(config: TcpServerConfig) -> Result<String, TcpError> {
+    let address = format!("{}:{}", config.host, config.port);
+    
+    // アドレスの妥当性をチェック
+    if config.host.is_empty() || config.port == 0 {
+        return Err(TcpError::InvalidAddress(
+            "Host and port must be valid".to_string(),
+        ));
+    }
+
+    config.framing.validate(config.encoding).map_err(TcpError::InvalidFraming)?;
+    config.socket_options.validate().map_err(TcpError::InvalidAddress)?;
+
+    let acceptor = match &config.tls {
+        Some(options) => Some(tls::build_acceptor(options, &config.host).map_err(TcpError::TlsFailed)?),
+        None => None,
+    };
+
+    // 待ち受けに失敗しても同じIDの既存サーバーが止まらないよう、先にリスナーを開始する
+    let server_id = server_id_or_default(config.id);
+    let listener = match socket_options::listen(&address, &config.socket_options).await {
+        // 同じサーバーが同じアドレスで待ち受けている場合（再起動）は止めてから待ち受け直す
+        Err(e) if e.kind() == io::ErrorKind::AddrInUse && running_server_address(&server_id).await.as_ref() == Some(&address) => {
+            stop_server(&server_id, &TcpServerStopOptions::default()).await.ok();
+            socket_options::listen(&address, &config.socket_options).await
+        }
+        result => result,
+    }
+    .map_err(|e| TcpError::ServerStartFailed(format!("Failed to bind to {}: {}", address, e)))?;
+
+    // 同じIDの既存サーバーを停止し、メッセージ履歴を新しくする
+    let context = prepare_server(&server_id, config.framing, config.encoding, config.retention).await;
+
+    let tls_enabled = acceptor.is_some();
+    let server_context = context.clone();
+    let socket_options = config.socket_options;
+    
+    // サーバータスクを開始
+    let server_task = tokio::spawn(async move {
+        // ハンドシェイク中・登録前のクライアント（サーバーのタスクと一緒に中断され、ソケットも閉じる）
+        let mut pending = JoinSet::new();
+        loop {
+            let (stream, addr) = tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok(accepted) => accepted,
+                    Err(e) => {
+                        log::error!("Failed to accept connection: {}", e);
+                        break;
+                    }
+                },
+                // 登録まで終えたタスクを回収する
+                Some(_) = pending.join_next() => continue,
+            };
+            if let Err(e) = socket_options.apply_to_stream(&stream) {
+                log::warn!("Failed to apply socket options to {}: {}", addr, e);
+            }
+            let client_id = Uuid::new_v4().to_string();
+            record_endpoints(
+                &TranscriptTarget::server_client(&server_context.id, &client_id, &addr.to_string()),
+                &stream,
+                false,
+            );
+            let context = server_context.clone();
+            let acceptor = acceptor.clone();
+            pending.spawn(async move {
+                let mut info = ServerClientInfo::new(&context.id, &client_id, addr.to_string());
+                // TLSの場合はハンドシェイクをクライアントごとのタスクで行う
+                let (reader, writer): (BoxedReader, BoxedWriter) = match acceptor {
+                    Some(acceptor) => match tokio::time::timeout(TLS_HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                        Ok(Ok(tls_stream)) => {
+                            tcp_info::register_socket(&client_id, tls_stream.get_ref().0);
+                            info.tls = Some(tls::accepted_session_info(tls_stream.get_ref().1));
+                            let (reader, writer) = tokio::io::split(tls_stream);
+                            (Box::new(reader), Box::new(writer))
+                        }
+                        Ok(Err(e)) => {
+                            log::error!("TLS handshake with {} failed: {}", addr, e);
+                            return;
+                        }
+                        Err(_) => {
+                            log::warn!("TLS handshake with {} timed out", addr);
+                            return;
+                        }
+                    },
+                    None => {
+                        tcp_info::register_socket(&client_id, &stream);
+                        let (reader, writer) = stream.into_split();
+                        (Box::new(reader), Box::new(writer))
+                    }
+                };
+                accept_server_client(context, info, reader, writer).await;
+            });
+        }
+    });
+
+    // サーバーを登録
+    register_server(context, address.clone(), Transport::Tcp, tls_enabled, server_task).await;
+
+    if tls_enabled {
+        Ok(format!("TCP server started on {} (TLS)", address))
+    } else {
+        Ok(format!("TCP server started on {}", address))
+    }
+}
+
+/// サーバーを停止（IDを省略した場合は既定のサーバー）
+#[tauri::command]
+pub async fn stop_tcp_server(
+    server_id: Option<String>,
+    options: Option<TcpServerStopOptions>,
+) -> Result<TcpServerStopResult, TcpError> {
+    let options = options.unwrap_or_default();
+    match stop_server(&server_id_or_default(server_id), &options).await? {
+        Some((closed_clients, timed_out_clients)) => Ok(TcpServerStopResult {
+            success: true,
+            message: "TCP server stopped".to_string(),
+            was_running: true,
+            closed_clients,
+            timed_out_clients,
+        }),
+        None => Ok(TcpServerStopResult {
+            success: true,
+            message: "No server was running".to_string(),
+            was_running: false,
+            closed_clients: 0,
+            timed_out_clients: 0,
+        }),
+    }
+}
+
+/// サーバーをレジストリから削除する（起動中の場合は中断モードで停止する）
+///
+/// 停止したサーバーの受信履歴は削除するまで残る
+#[tauri::command]
+pub async fn remove_tcp_server(server_id: Option<String>) -> Result<String, TcpError> {
+    let server_id = server_id_or_default(server_id);
+    stop_server(&server_id, &TcpServerStopOptions::default()).await?;
+    match servers().lock().await.remove(&server_id) {
+        Some(_) => {
+            transcript::forget(&TranscriptSource::Server(server_id));
+            Ok("Server removed".to_string())
+        }
+        None => Err(TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id))),
+    }
+}
+
+/// 起動中のサーバー一覧を取得
+#[tauri::command]
+pub async fn list_tcp_servers() -> Result<Vec<TcpServerInfo>, TcpError> {
+    let running: Vec<(TcpServerInfo, ServerContext)> = servers()
+        .lock()
+        .await
+        .values()
+        .filter(|server_data| server_data.handle.is_some())
+        .map(|server_data| {
+            let info = TcpServerInfo {
+                id: server_data.context.id.clone(),
+                address: server_data.address.clone(),
+                transport: server_data.transport,
+                tls: server_data.tls,
+                started_at: server_data.started_at.clone(),
+                client_count: 0,
+                message_count: 0,
+            };
+            (info, server_data.context.clone())
+        })
+        .collect();
+
+    let mut servers = Vec::with_capacity(running.len());
+    for (mut info, context) in running {
+        info.client_count = context.clients.lock().await.len();
+        info.message_count = context.messages.lock().await.retained_count();
+        servers.push(info);
+    }
+    servers.sort_by(|a, b| a.id.cmp(&b.id));
+    Ok(servers)
+}
+
+#[tauri::command]
+pub async fn get_received_messages(server_id: Option<String>) -> Result<TcpReceiveResult, TcpError> {
+    let (messages, dropped) = match server_messages(server_id).await? {
+        Some(messages) => {
+            let mut store = messages.lock().await;
+            (store.all(), store.dropped())
+        }
+        None => (Vec::new(), 0),
+    };
+    
+    Ok(TcpReceiveResult {
+        success: true,
+        messages,
+        error: None,
+        dropped,
+    })
+}
+
+/// サーバーの受信メッセージのうち、指定した通し番号より後のものだけを取得
+#[tauri::command]
+pub async fn get_received_messages_since(since_seq: u64, server_id: Option<String>) -> Result<TcpReceiveResult, TcpError> {
+    let (messages, dropped) = match server_messages(server_id).await? {
+        Some(messages) => {
+            let mut store = messages.lock().await;
+            (store.since(since_seq), store.dropped())
+        }
+        None => (Vec::new(), 0),
+    };
+
+    Ok(TcpReceiveResult {
+        success: true,
+        messages,
+        error: None,
+        dropped,
+    })
+}
+
+/// サーバーの受信メッセージをページ単位で取得
+#[tauri::command]
+pub async fn get_received_messages_page(query: MessageQuery, server_id: Option<String>) -> Result<MessagePage, TcpError> {
+    match server_messages(server_id).await? {
+        Some(messages) => Ok(messages.lock().await.page(&query)),
+        None => Ok(MessageStore::new(RetentionPolicy::default()).page(&query)),
+    }
+}
+
+/// サーバーの受信メッセージと送受信記録の保持ポリシーを変更（超えている分はすぐに破棄する）
+#[tauri::command]
+pub async fn set_server_retention(retention: RetentionPolicy, server_id: Option<String>) -> Result<(), TcpError> {
+    let server_id = server_id_or_default(server_id);
+    match server_messages(Some(server_id.clone())).await? {
+        Some(messages) => {
+            transcript::set_retention(&TranscriptSource::Server(server_id), retention.clone());
+            messages.lock().await.set_policy(retention);
+            Ok(())
+        }
+        None => Err(TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id))),
+    }
+}
+
+async fn handle_tcp_client(
+    stream: BoxedReader,
+    context: &ServerContext,
+    client_id: &str,
+    client_addr: &str,
+) -> io::Result<()> {
+    let mut frames = FramedRead::new(stream, FrameCodec::new(context.framing.clone(), context.encoding));
+
+    // ストリームが終了した場合は接続が閉じられた
+    while let Some(result) = frames.next().await {
+        match result {
+            Ok(frame) => {
+                let received_msg =
+                    TcpReceivedMessage::from_frame(frame.payload, client_addr.to_string(), context.encoding);
+                log::info!("Received message from {}: {}", client_addr, received_msg.message);
+                let received_msg = context.messages.lock().await.push(received_msg);
+
+                transcript::record_frame(
+                    &TranscriptTarget::server_client(&context.id, client_id, client_addr),
+                    Direction::Rx,
+                    &received_msg.raw,
+                    &frame.wire,
+                    received_msg.message.clone(),
+                    received_msg.timestamp.clone(),
+                );
+
+                let event = ServerMessageReceivedEvent {
+                    server_id: context.id.clone(),
+                    client_id: client_id.to_string(),
+                    message: received_msg,
+                };
+                emit_event("tcp_server_message_received", &event);
+            }
+            Err(e) => {
+                log::error!("Error reading from {}: {}", client_addr, e);
+                return Err(e);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 受け付けたクライアントを登録し、切断されるまで受信する
+pub(crate) async fn accept_server_client(
+    context: ServerContext,
+    info: ServerClientInfo,
+    reader: BoxedReader,
+    writer: BoxedWriter,
+) {
+    let client_id = info.id.clone();
+    let client_addr = info.client_addr.clone();
+
+    // 受信タスクが先に終了しても登録後に削除されるよう、ロックを保持したまま起動する
+    // （停止時はこのロックを取ってからクライアントを取り出すため、停止後に登録されることはない）
+    let clients = Arc::clone(&context.clients);
+    let mut clients_guard = clients.lock().await;
+    if !context.running.load(Ordering::SeqCst) {
+        log::info!("Dropping client {} because server {} has stopped", client_addr, context.id);
+        tcp_info::forget_socket(&client_id);
+        return;
+    }
+
+    log::info!("Client {} connected to server {} from {}", client_id, context.id, client_addr);
+    transcript::record_system(
+        &TranscriptTarget::server_client(&context.id, &client_id, &client_addr),
+        "client connected".to_string(),
+    );
+    emit_event("tcp_server_client_connected", &info);
+
+    let handle = tokio::spawn({
+        let client_id = client_id.clone();
+        async move {
+            let result = handle_tcp_client(reader, &context, &client_id, &client_addr).await;
+            // 切断・サーバー停止で既に削除されている場合はそちらで通知済み
+            if let Some(client) = context.clients.lock().await.remove(&client_id) {
+                let (reason, error) = match result {
+                    Ok(()) => ("closed", None),
+                    Err(e) => ("error", Some(e.to_string())),
+                };
+                emit_client_disconnected(client.info, reason, error);
+            }
+        }
+    });
+    clients_guard.insert(
+        client_id,
+        ServerClient {
+            info,
+            writer: Arc::new(Mutex::new(ConnectionWriter::Stream(writer))),
+            handle,
+        },
+    );
+}
+
+fn emit_client_disconnected(client: ServerClientInfo, reason: &str, error: Option<String>) {
+    log::info!("Client {} disconnected ({})", client.id, reason);
+    tcp_info::forget_socket(&client.id);
+    let text = match &error {
+        Some(error) => format!("client disconnected ({}): {}", reason, error),
+        None => format!("client disconnected ({})", reason),
+    };
+    transcript::record_system(&TranscriptTarget::server_client(&client.server_id, &client.id, &client.client_addr), text);
+    let event = ServerClientDisconnectedEvent {
+        client,
+        reason: reason.to_string(),
+        error,
+    };
+    emit_event("tcp_server_client_disconnected", &event);
+}
+
+/// 全クライアントの受信タスクを中断し、ソケットを破棄する
+async fn abort_server_clients(clients: &ServerClients) -> usize {
+    let clients: Vec<ServerClient> = clients.lock().await.drain().map(|(_, client)| client).collect();
+    let count = clients.len();
+    for client in clients {
+        client.handle.abort();
+        emit_client_disconnected(client.info, "server_stopped", None);
+    }
+    count
+}
+
+/// お別れメッセージとFINを送り、相手が切断するまで受信を続ける
+///
+/// タイムアウトまでに切断が完了しなかった場合はfalse
+async fn close_server_client_gracefully(
+    client: ServerClient,
+    goodbye: Option<&(Vec<u8>, Vec<u8>)>,
+    encoding: TextEncoding,
+    timeout: std::time::Duration,
+) -> bool {
+    let ServerClient { info, writer, mut handle } = client;
+
+    let result = tokio::time::timeout(timeout, async {
+        let mut writer_guard = writer.lock().await;
+        if let Some((payload, frame)) = goodbye {
+            writer_guard.write_frame(frame).await?;
+            writer_guard.flush().await?;
+            let target = TranscriptTarget::server_client(&info.server_id, &info.id, &info.client_addr);
+            let text = encoding.decode(payload).0;
+            transcript::record_frame(&target, Direction::Tx, payload, frame, text, Utc::now().to_rfc3339());
+        }
+        writer_guard.shutdown().await?;
+        drop(writer_guard);
+
+        // 受信タスクは相手からEOFを受け取ると終了する
+        let _ = (&mut handle).await;
+        Ok::<(), io::Error>(())
+    })
+    .await;
+
+    handle.abort();
+    let (completed, error) = match result {
+        Ok(Ok(())) => (true, None),
+        Ok(Err(e)) => {
+            log::warn!("Failed to close client {} cleanly: {}", info.id, e);
+            (true, Some(e.to_string()))
+        }
+        Err(_) => {
+            log::warn!("Client {} did not close within {:?}", info.id, timeout);
+            (false, None)
+        }
+    };
+    emit_client_disconnected(info, "server_stopped", error);
+    completed
+}
+
+async fn close_server_client(client: ServerClient, reason: &str) {
+    client.handle.abort();
+    if let Err(e) = client.writer.lock().await.shutdown().await {
+        log::warn!("Failed to close client {} cleanly: {}", client.info.id, e);
+    }
+    emit_client_disconnected(client.info, reason, None);
+}
+
+/// 全サーバーのクライアント一覧（クライアントIDはサーバーをまたいで一意）
+async fn all_server_clients() -> Vec<ServerClients> {
+    servers()
+        .lock()
+        .await
+        .values()
+        .map(|server_data| Arc::clone(&server_data.context.clients))
+        .collect()
+}
+
+/// クライアントの送信先と、所属するサーバーのフレーミング設定を探す
+async fn find_server_client(
+    client_id: &str,
+) -> Result<(Arc<Mutex<ConnectionWriter>>, Framing, TextEncoding, TranscriptTarget), TcpError> {
+    let contexts: Vec<ServerContext> = servers()
+        .lock()
+        .await
+        .values()
+        .map(|server_data| server_data.context.clone())
+        .collect();
+    for context in contexts {
+        if let Some(client) = context.clients.lock().await.get(client_id) {
+            let target = TranscriptTarget::server_client(&context.id, client_id, &client.info.client_addr);
+            return Ok((Arc::clone(&client.writer), context.framing.clone(), context.encoding, target));
+        }
+    }
+    Err(TcpError::ConnectionNotFound(format!("Client with ID {} not found", client_id)))
+}
+
+/// サーバーに接続中のクライアント一覧を取得（IDを省略した場合は全サーバー）
+#[tauri::command]
+pub async fn list_server_clients(server_id: Option<String>) -> Result<Vec<ServerClientInfo>, TcpError> {
+    let registries = match server_id {
+        Some(server_id) => match servers().lock().await.get(&server_id) {
+            Some(server_data) => vec![Arc::clone(&server_data.context.clients)],
+            None => return Err(TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id))),
+        },
+        None => all_server_clients().await,
+    };
+
+    let mut clients = Vec::new();
+    for registry in registries {
+        clients.extend(registry.lock().await.values().map(|client| client.info.clone()));
+    }
+    clients.sort_by(|a, b| a.connected_at.cmp(&b.connected_at).then_with(|| a.client_addr.cmp(&b.client_addr)));
+    Ok(clients)
+}
+
+/// サーバーに接続中のクライアント1つにメッセージを送信
+#[tauri::command]
+pub async fn send_to_server_client(message_request: ServerClientMessage) -> Result<TcpSendResult, TcpError> {
+    let (writer, framing, encoding, target) = find_server_client(&message_request.client_id).await?;
+
+    send_framed(&writer, &framing, encoding, &message_request.message, message_request.payload_format, &target).await
+}
+
+/// サーバーに接続中の全クライアントにメッセージを送信
+#[tauri::command]
+pub async fn broadcast_to_server_clients(message_request: ServerBroadcastMessage) -> Result<ServerBroadcastResult, TcpError> {
+    let server_id = server_id_or_default(message_request.server_id);
+    let Some(context) = servers().lock().await.get(&server_id).map(|server_data| server_data.context.clone()) else {
+        return Err(TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id)));
+    };
+    let targets: Vec<(ServerClientInfo, Arc<Mutex<ConnectionWriter>>)> = context
+        .clients
+        .lock()
+        .await
+        .values()
+        .map(|client| (client.info.clone(), Arc::clone(&client.writer)))
+        .collect();
+
+    let mut sent = 0;
+    let mut failures = Vec::new();
+    for (client, writer) in targets {
+        let client_id = client.id;
+        let target = TranscriptTarget::server_client(&server_id, &client_id, &client.client_addr);
+        // 1つのクライアントへの送信に失敗しても残りのクライアントには送信する
+        let result = send_framed(&writer, &context.framing, context.encoding, &message_request.message, message_request.payload_format, &target).await;
+        match result {
+            Ok(result) if result.success => sent += 1,
+            Ok(result) => failures.push(ServerBroadcastFailure {
+                client_id,
+                error: result.error.unwrap_or(result.message),
+            }),
+            Err(e) => failures.push(ServerBroadcastFailure { client_id, error: e.to_string() }),
+        }
+    }
+
+    Ok(ServerBroadcastResult {
+        success: failures.is_empty(),
+        sent,
+        failures,
+        timestamp: Utc::now().to_rfc3339(),
+    })
+}
+
+/// サーバーに接続中のクライアントを強制的に切断
+#[tauri::command]
+pub async fn disconnect_server_client(client_id: String) -> Result<String, TcpError> {
+    for registry in all_server_clients().await {
+        let client = registry.lock().await.remove(&client_id);
+        if let Some(client) = client {
+            close_server_client(client, "kicked").await;
+            return Ok(format!("Client {} disconnected", client_id));
+        }
+    }
+    Err(TcpError::ConnectionNotFound(format!("Client with ID {} not found", client_id)))
+}
+
+// 新しい接続管理機能
+#[tauri::command]
+pub async fn connect_tcp(app_handle: AppHandle, request: TcpConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    // AppHandleを保存
+    APP_HANDLE.set(app_handle).ok();
+    open_tcp_connection(request).await
+}
+
+/// TCP接続を確立してレジストリに登録する
+pub(crate) async fn open_tcp_connection(request: TcpConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    // アドレスの妥当性をチェック
+    if request.host.is_empty() || request.port == 0 {
+        return Err(TcpError::InvalidAddress(
+            "Host and port must be valid".to_string(),
+        ));
+    }
+
+    request.framing.validate(request.encoding).map_err(TcpError::InvalidFraming)?;
+    request.socket_options.validate().map_err(TcpError::InvalidAddress)?;
+
+    // 接続IDを生成し、接続開始を通知
+    let connection_id = Uuid::new_v4().to_string();
+    let mut connection = TcpConnection::new(connection_id.clone(), request.host.clone(), request.port, Transport::Tcp);
+    connection.connected = false;
+    connection.state = ConnectionState::Connecting;
+    emit_connection_state(&connection);
+
+    // TCP接続を確立（TLSの場合はハンドシェイクまで行う）
+    let activity = Arc::new(ConnectionActivity::new());
+    let (reader, writer, tls_info) = match establish_tcp_stream(&request, &connection_id, &activity).await {
+        Ok(result) => result,
+        Err(e) => {
+            let (reason, error) = match &e {
+                TcpError::TlsFailed(message) => (CloseReason::Error, message.clone()),
+                TcpError::ConnectTimeout(_) => (CloseReason::ConnectTimeout, e.to_string()),
+                other => (CloseReason::Error, other.to_string()),
+            };
+            connection.mark_closed(ConnectionState::Error, reason, Some(error));
+            emit_connection_state(&connection);
+            transcript::forget(&TranscriptSource::Connection(connection_id));
+            return Err(e);
+        }
+    };
+    let writer_arc = Arc::new(Mutex::new(ConnectionWriter::Stream(writer)));
+    transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+    let messages = MessageStore::shared(request.retention.clone());
+    let connection = TcpConnection::new(connection_id.clone(), request.host.clone(), request.port, Transport::Tcp);
+
+    // 受信処理（登録時にタスクとして開始する）
+    let receiver = run_tcp_connection(
+        request.clone(),
+        connection_id.clone(),
+        reader,
+        Arc::clone(&writer_arc),
+        Arc::clone(&messages),
+        activity,
+    );
+
+    // 接続データを作成
+    let connection_data = ConnectionData {
+        info: connection.clone(),
+        writer: Arc::clone(&writer_arc),
+        framing: request.framing.clone(),
+        encoding: request.encoding,
+        messages,
+        receiver_handle: None,
+        tls_info,
+    };
+
+    // 接続を登録
+    register_connection(connection_data, receiver).await;
+
+    if let Some(policy) = &request.reconnect {
+        run_init_script(&connection_id, &writer_arc, &request, policy).await;
+    }
+
+    Ok(TcpConnectionResult {
+        success: true,
+        connection: Some(connection),
+        error: None,
+    })
+}
+
+/// TCP接続を確立し、読み取り用と書き込み用に分割する（TLSの場合はハンドシェイク後に分割）
+///
+/// 分割した読み書きはタイムアウト監視用に送受信時刻を記録する
+async fn establish_tcp_stream(
+    request: &TcpConnectionRequest,
+    connection_id: &str,
+    activity: &Arc<ConnectionActivity>,
+) -> Result<(BoxedReader, BoxedWriter, Option<TlsSessionInfo>), TcpError> {
+    let connected = match request.timeouts.connect() {
+        Some(after) => tokio::time::timeout(after, connect_tcp_stream(request, connection_id))
+            .await
+            .unwrap_or_else(|_| Err(TcpError::from(ConnectionTimeout::new(TimeoutKind::Connect, after)))),
+        None => connect_tcp_stream(request, connection_id).await,
+    };
+    // 登録後にTLSやタイムアウトで失敗した場合、また再接続に失敗した場合は前のソケットを破棄する
+    let (reader, writer, tls_info) = connected.inspect_err(|_| tcp_info::forget_socket(connection_id))?;
+
+    activity.reset();
+    let reader: BoxedReader = Box::new(MonitoredReader::new(reader, Arc::clone(activity)));
+    let writer: BoxedWriter = Box::new(MonitoredWriter::new(writer, Arc::clone(activity), request.timeouts.write()));
+    Ok((reader, writer, tls_info))
+}
+
+/// 接続に成功したソケットはTCP_INFOを読めるよう接続IDで登録する
+async fn connect_tcp_stream(
+    request: &TcpConnectionRequest,
+    connection_id: &str,
+) -> Result<(BoxedReader, BoxedWriter, Option<TlsSessionInfo>), TcpError> {
+    let address = format!("{}:{}", request.host, request.port);
+    let stream = socket_options::connect(&address, &request.socket_options).await.map_err(|e| {
+        TcpError::ConnectionFailed(format!("Failed to connect to {}: {}", address, e))
+    })?;
+    record_endpoints(&TranscriptTarget::connection(connection_id, None), &stream, true);
+
+    match &request.tls {
+        Some(options) => {
+            let (tls_stream, info) = tls::connect(stream, &request.host, options)
+                .await
+                .map_err(TcpError::TlsFailed)?;
+            tcp_info::register_socket(connection_id, tls_stream.get_ref().0);
+            let (reader, writer) = tokio::io::split(tls_stream);
+            Ok((Box::new(reader), Box::new(writer), Some(info)))
+        }
+        None => {
+            tcp_info::register_socket(connection_id, &stream);
+            let (reader, writer) = stream.into_split();
+            Ok((Box::new(reader), Box::new(writer), None))
+        }
+    }
+}
+
+/// 受信を続け、相手から切断された場合は再接続ポリシーに従って再接続する
+///
+/// 再接続しても接続IDとメッセージ履歴はそのまま引き継ぐ。
+/// 受信・送信・アイドルのタイムアウトが発生した場合はこちらから接続を閉じる
+async fn run_tcp_connection(
+    request: TcpConnectionRequest,
+    connection_id: String,
+    reader: BoxedReader,
+    writer: Arc<Mutex<ConnectionWriter>>,
+    messages: SharedMessageStore,
+    activity: Arc<ConnectionActivity>,
+) -> io::Result<()> {
+    let mut reader = reader;
+    loop {
+        let receiver = handle_connection_receiver(
+            reader,
+            connection_id.clone(),
+            request.framing.clone(),
+            request.encoding,
+            Arc::clone(&messages),
+        );
+        let result = tokio::select! {
+            result = receiver => result,
+            error = timeouts::watch(&activity, &request.timeouts) => Err(error),
+        };
+
+        let timeout = result.as_ref().err().and_then(ConnectionTimeout::from_io);
+        if let Some(timeout) = timeout {
+            log::info!("Connection {} timed out: {}", connection_id, timeout);
+            close_writer(&writer).await;
+        }
+
+        // アイドル切断は意図した切断なので再接続しない
+        let idle = matches!(timeout, Some(timeout) if timeout.kind == TimeoutKind::Idle);
+        let Some(policy) = request.reconnect.as_ref().filter(|_| !idle) else {
+            return result;
+        };
+
+        let (_, reason, error) = close_outcome(&result);
+        log::info!("Connection {} lost ({:?}), reconnecting", connection_id, reason);
+        let still_registered = update_connection(&connection_id, |connection_data| {
+            connection_data.info.mark_closed(ConnectionState::Reconnecting, reason, error);
+        })
+        .await;
+        if !still_registered {
+            return result;
+        }
+
+        reader = reconnect_tcp_connection(&request, policy, &connection_id, &writer, &activity).await?;
+    }
+}
+
+/// 書き込み側を閉じる（送信が詰まっている場合に備えて待ち時間を区切る）
+async fn close_writer(writer: &Arc<Mutex<ConnectionWriter>>) {
+    let shutdown = async { writer.lock().await.shutdown().await };
+    match tokio::time::timeout(std::time::Duration::from_secs(1), shutdown).await {
+        Ok(Ok(())) => {}
+        Ok(Err(e)) => log::warn!("Failed to close writer cleanly: {}", e),
+        Err(_) => log::warn!("Timed out while closing writer"),
+    }
+}
+
+/// バックオフしながら再接続を試み、成功したら書き込み側を差し替えて読み取り側を返す
+async fn reconnect_tcp_connection(
+    request: &TcpConnectionRequest,
+    policy: &ReconnectPolicy,
+    connection_id: &str,
+    writer: &Arc<Mutex<ConnectionWriter>>,
+    activity: &Arc<ConnectionActivity>,
+) -> io::Result<BoxedReader> {
+    let mut attempt = 0;
+    let mut last_error = String::new();
+
+    loop {
+        attempt += 1;
+        if policy.max_attempts.is_some_and(|max_attempts| attempt > max_attempts) {
+            return Err(io::Error::other(format!(
+                "Gave up reconnecting after {} attempts: {}",
+                attempt - 1,
+                last_error
+            )));
+        }
+
+        let delay = policy.delay(attempt, random_unit());
+        tokio::time::sleep(delay).await;
+
+        let result = establish_tcp_stream(request, connection_id, activity).await;
+        let event = ReconnectAttemptEvent {
+            connection_id: connection_id.to_string(),
+            attempt,
+            max_attempts: policy.max_attempts,
+            delay_ms: delay.as_millis() as u64,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            timestamp: Utc::now().to_rfc3339(),
+        };
+        emit_event("tcp_reconnect_attempt", &event);
+        let text = match &event.error {
+            Some(error) => format!("reconnect attempt {} failed: {}", attempt, error),
+            None => format!("reconnect attempt {} succeeded", attempt),
+        };
+        transcript::record_system(&TranscriptTarget::connection(connection_id, None), text);
+
+        match result {
+            Ok((reader, new_writer, tls_info)) => {
+                *writer.lock().await = ConnectionWriter::Stream(new_writer);
+                let still_registered = update_connection(connection_id, |connection_data| {
+                    let info = &mut connection_data.info;
+                    info.connected = true;
+                    info.state = ConnectionState::Connected;
+                    info.close_reason = None;
+                    info.error = None;
+                    info.closed_at = None;
+                    info.reconnects += 1;
+                    connection_data.tls_info = tls_info;
+                })
+                .await;
+                if !still_registered {
+                    return Err(io::Error::new(io::ErrorKind::NotConnected, "Connection was removed"));
+                }
+
+                log::info!("Connection {} reconnected after {} attempt(s)", connection_id, attempt);
+                run_init_script(connection_id, writer, request, policy).await;
+                return Ok(reader);
+            }
+            Err(e) => {
+                log::warn!("Reconnect attempt {} for {} failed: {}", attempt, connection_id, e);
+                last_error = e.to_string();
+            }
+        }
+    }
+}
+
+/// 再接続ポリシーの初期化メッセージを順に送信する
+async fn run_init_script(
+    connection_id: &str,
+    writer: &Arc<Mutex<ConnectionWriter>>,
+    request: &TcpConnectionRequest,
+    policy: &ReconnectPolicy,
+) {
+    for message in &policy.init_script {
+        let target = TranscriptTarget::connection(connection_id, None);
+        let result = send_framed(writer, &request.framing, request.encoding, message, policy.init_payload_format, &target).await;
+        match result {
+            Ok(result) if result.success => {}
+            Ok(result) => log::warn!("Init script failed on {}: {}", connection_id, result.message),
+            Err(e) => log::warn!("Init script failed on {}: {}", connection_id, e),
+        }
+    }
+}
+
+/// 0.0〜1.0の乱数（バックオフの揺らぎ用）
+fn random_unit() -> f64 {
+    use std::hash::{BuildHasher, Hasher};
+    let bits = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[tauri::command]
+pub async fn disconnect_tcp(connection_id: String) -> Result<String, TcpError> {
     let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
-    let mut connections_guard = connections.lock().await;
-    
-    if let Some(mut connection_data) = connections_guard.remove(&connection_id) {
+    // 書き込みの完了を待つ間に他の接続の操作を止めないよう、レジストリのロックは先に手放す
+    let removed = connections.lock().await.remove(&connection_id);
+
+    if let Some(mut connection_data) = removed {
         // 受信タスクを停止
         if let Some(handle) = connection_data.receiver_handle.take() {
             handle.abort();
         }
 
-        // 接続を閉じる
-        if let Ok(mut writer) = connection_data.writer.try_lock() {
-            let _ = writer.shutdown().await;
+        // 送信中の書き込みを待ってから閉じる（マルチキャストの場合はグループから離脱）
+        close_writer(&connection_data.writer).await;
+        ws::forget_connection(&connection_id).await;
+        serial::forget_connection(&connection_id).await;
+        tcp_info::forget_socket(&connection_id);
+
+        // 既に相手から切断されていた場合は通知済み（再接続中の場合は再接続を打ち切る）
+        if matches!(connection_data.info.state, ConnectionState::Connected | ConnectionState::Reconnecting) {
+            connection_data.info.mark_closed(ConnectionState::Closed, CloseReason::Local, None);
+            emit_connection_state(&connection_data.info);
         }
+        // 送受信記録は切断後も書き出せるよう、削除されるまで残す
+        session_log::close(&TranscriptSource::Connection(connection_id.clone())).await;
 
         Ok("Connection closed successfully".to_string())
     } else {
@@ -387,48 +1994,118 @@ pub async fn disconnect_tcp(connection_id: String) -> Result<String, TcpError> {
     }
 }
 
+/// 登録中の全接続の状態を取得（相手から切断された接続も含む）
 #[tauri::command]
-pub async fn send_tcp_message_on_connection(message_request: TcpMessageOnConnection) -> Result<TcpSendResult, TcpError> {
+pub async fn list_connections() -> Result<Vec<TcpConnection>, TcpError> {
     let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
-    let connections_guard = connections.lock().await;
-    
-    if let Some(connection_data) = connections_guard.get(&message_request.connection_id) {
-        let writer = Arc::clone(&connection_data.writer);
-        drop(connections_guard); // Release the lock early
+    let mut list: Vec<TcpConnection> = connections
+        .lock()
+        .await
+        .values()
+        .map(|connection_data| connection_data.info.clone())
+        .collect();
+    list.sort_by(|a, b| a.connected_at.cmp(&b.connected_at).then_with(|| a.id.cmp(&b.id)));
+    Ok(list)
+}
 
-        // メッセージ送信時刻をRust側で生成
-        let send_timestamp = Utc::now().to_rfc3339();
+/// 接続の現在の状態を取得
+#[tauri::command]
+pub async fn get_connection(connection_id: String) -> Result<TcpConnection, TcpError> {
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    connections
+        .lock()
+        .await
+        .get(&connection_id)
+        .map(|connection_data| connection_data.info.clone())
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("Connection with ID {} not found", connection_id)))
+}
 
-        // メッセージにCRデリミタを追加
-        let message_with_cr = format!("{}
-", message_request.message);
+/// ペイロードを解析し、フレーミングを適用して送信する
+async fn send_framed(
+    writer: &Arc<Mutex<ConnectionWriter>>,
+    framing: &Framing,
+    encoding: TextEncoding,
+    message: &str,
+    payload_format: PayloadFormat,
+    transcript_target: &TranscriptTarget,
+) -> Result<TcpSendResult, TcpError> {
+    let payload = parse_payload(message, payload_format, encoding)
+        .map_err(TcpError::InvalidPayload)?;
 
-        // メッセージを送信
-        let mut writer_guard = writer.lock().await;
-        if let Err(e) = writer_guard.write_all(message_with_cr.as_bytes()).await {
+    // メッセージ送信時刻をRust側で生成
+    let send_timestamp = Utc::now().to_rfc3339();
+
+    // 接続のフレーミング設定に従ってデリミタ等を付与
+    let frame = match framing.encode(&payload, encoding) {
+        Ok(frame) => frame,
+        Err(e) => {
             return Ok(TcpSendResult {
                 success: false,
-                message: format!("Failed to send message: {}", e),
+                message: format!("Failed to frame message: {}", e),
                 timestamp: Some(send_timestamp),
-                error: Some(format!("Send failed: {}", e)),
+                error: Some(format!("Framing failed: {}", e)),
             });
         }
+    };
 
-        if let Err(e) = writer_guard.flush().await {
-            return Ok(TcpSendResult {
-                success: false,
-                message: format!("Failed to flush message: {}", e),
-                timestamp: Some(send_timestamp),
-                error: Some(format!("Flush failed: {}", e)),
-            });
+    // メッセージを送信
+    let mut writer_guard = writer.lock().await;
+    if let Err(e) = writer_guard.write_frame(&frame).await {
+        if let Some(timeout) = ConnectionTimeout::from_io(&e) {
+            return Err(timeout.into());
         }
+        return Ok(TcpSendResult {
+            success: false,
+            message: format!("Failed to send message: {}", e),
+            timestamp: Some(send_timestamp),
+            error: Some(format!("Send failed: {}", e)),
+        });
+    }
 
-        Ok(TcpSendResult {
-            success: true,
-            message: "Message sent successfully".to_string(),
+    if let Err(e) = writer_guard.flush().await {
+        if let Some(timeout) = ConnectionTimeout::from_io(&e) {
+            return Err(timeout.into());
+        }
+        return Ok(TcpSendResult {
+            success: false,
+            message: format!("Failed to flush message: {}", e),
             timestamp: Some(send_timestamp),
-            error: None,
-        })
+            error: Some(format!("Flush failed: {}", e)),
+        });
+    }
+
+    // 送信順と記録順を一致させるため書き込み側のロック中に記録する
+    let text = encoding.decode(&payload).0;
+    transcript::record_frame(transcript_target, Direction::Tx, &payload, &frame, text, send_timestamp.clone());
+    drop(writer_guard);
+
+    Ok(TcpSendResult {
+        success: true,
+        message: "Message sent successfully".to_string(),
+        timestamp: Some(send_timestamp),
+        error: None,
+    })
+}
+
+#[tauri::command]
+pub async fn send_tcp_message_on_connection(message_request: TcpMessageOnConnection) -> Result<TcpSendResult, TcpError> {
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    let connections_guard = connections.lock().await;
+    
+    if let Some(connection_data) = connections_guard.get(&message_request.connection_id) {
+        if connection_data.info.state != ConnectionState::Connected {
+            return Err(TcpError::SendFailed(format!(
+                "Connection {} is closed",
+                message_request.connection_id
+            )));
+        }
+        let writer = Arc::clone(&connection_data.writer);
+        let framing = connection_data.framing.clone();
+        let encoding = connection_data.encoding;
+        drop(connections_guard); // Release the lock early
+
+        let target = TranscriptTarget::connection(&message_request.connection_id, None);
+        send_framed(&writer, &framing, encoding, &message_request.message, message_request.payload_format, &target).await
     } else {
         Err(TcpError::ConnectionNotFound(format!(
             "Connection with ID {} not found",
@@ -437,77 +2114,107 @@ pub async fn send_tcp_message_on_connection(message_request: TcpMessageOnConnect
     }
 }
 
+/// TLS接続でネゴシエートされたプロトコル・暗号スイート・証明書チェーンを取得
+///
+/// 平文接続の場合は`None`を返す
 #[tauri::command]
-pub async fn get_received_messages_from_connection(connection_id: String) -> Result<TcpReceiveResult, TcpError> {
+pub async fn get_tls_info(connection_id: String) -> Result<Option<TlsSessionInfo>, TcpError> {
     let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
     let connections_guard = connections.lock().await;
-    
-    if let Some(connection_data) = connections_guard.get(&connection_id) {
-        let messages = Arc::clone(&connection_data.messages);
-        drop(connections_guard); // Release the lock early
 
-        let messages_guard = messages.lock().await;
-        
-        Ok(TcpReceiveResult {
-            success: true,
-            messages: messages_guard.clone(),
-            error: None,
-        })
-    } else {
-        Err(TcpError::ConnectionNotFound(format!(
+    match connections_guard.get(&connection_id) {
+        Some(connection_data) => Ok(connection_data.tls_info.clone()),
+        None => Err(TcpError::ConnectionNotFound(format!(
             "Connection with ID {} not found",
             connection_id
-        )))
+        ))),
+    }
+}
+
+/// 送信前にペイロードを解析し、エラー位置をそのままフロントエンドへ返す
+#[tauri::command]
+pub async fn preview_payload(
+    message: String,
+    payload_format: PayloadFormat,
+    encoding: Option<TextEncoding>,
+) -> Result<PayloadPreview, PayloadError> {
+    let payload = parse_payload(&message, payload_format, encoding.unwrap_or_default())?;
+
+    Ok(PayloadPreview {
+        length: payload.len(),
+        hex: format_hex(&payload),
+    })
+}
+
+/// 接続の受信メッセージを取得
+async fn connection_messages(connection_id: &str) -> Result<SharedMessageStore, TcpError> {
+    let connections = CONNECTIONS.get_or_init(|| Arc::new(Mutex::new(HashMap::new())));
+    match connections.lock().await.get(connection_id) {
+        Some(connection_data) => Ok(Arc::clone(&connection_data.messages)),
+        None => Err(TcpError::ConnectionNotFound(format!(
+            "Connection with ID {} not found",
+            connection_id
+        ))),
     }
 }
 
-async fn handle_connection_receiver(
-    mut reader: tokio::net::tcp::OwnedReadHalf,
+#[tauri::command]
+pub async fn get_received_messages_from_connection(connection_id: String) -> Result<TcpReceiveResult, TcpError> {
+    let messages = connection_messages(&connection_id).await?;
+    let mut store = messages.lock().await;
+
+    Ok(TcpReceiveResult {
+        success: true,
+        messages: store.all(),
+        error: None,
+        dropped: store.dropped(),
+    })
+}
+
+/// 接続の受信メッセージをページ単位で取得
+#[tauri::command]
+pub async fn get_received_messages_page_from_connection(
     connection_id: String,
-    messages: Arc<Mutex<Vec<TcpReceivedMessage>>>,
-) {
-    let mut buf_reader = BufReader::new(&mut reader);
-    let mut line = String::new();
+    query: MessageQuery,
+) -> Result<MessagePage, TcpError> {
+    let messages = connection_messages(&connection_id).await?;
+    let page = messages.lock().await.page(&query);
+    Ok(page)
+}
+
+/// 接続の受信メッセージと送受信記録の保持ポリシーを変更（超えている分はすぐに破棄する）
+#[tauri::command]
+pub async fn set_connection_retention(connection_id: String, retention: RetentionPolicy) -> Result<(), TcpError> {
+    let messages = connection_messages(&connection_id).await?;
+    transcript::set_retention(&TranscriptSource::Connection(connection_id), retention.clone());
+    messages.lock().await.set_policy(retention);
+    Ok(())
+}
+
+pub(crate) async fn handle_connection_receiver(
+    reader: BoxedReader,
+    connection_id: String,
+    framing: Framing,
+    encoding: TextEncoding,
+    messages: SharedMessageStore,
+) -> io::Result<()> {
+    let mut frames = FramedRead::new(reader, FrameCodec::new(framing, encoding));
 
     loop {
-        line.clear();
-        match buf_reader.read_line(&mut line).await {
-            Ok(0) => {
+        match frames.next().await {
+            None => {
                 // Connection closed
                 log::info!("Connection {} closed", connection_id);
-                break;
+                return Ok(());
             }
-            Ok(_) => {
-                let message = line.trim_end_matches('\r').trim_end_matches('\n');
-                if !message.is_empty() {
-                    let received_msg = TcpReceivedMessage {
-                        message: message.to_string(),
-                        timestamp: Utc::now().to_rfc3339(),
-                        client_addr: format!("Connection {}", connection_id),
-                    };
-                    
-                    let mut messages_guard = messages.lock().await;
-                    messages_guard.push(received_msg.clone());
-                    drop(messages_guard); // Release the lock early
-                    
-                    // イベントを発行してフロントエンドに通知
-                    if let Some(app_handle) = APP_HANDLE.get() {
-                        let event = TcpMessageReceivedEvent {
-                            connection_id: connection_id.clone(),
-                            message: received_msg,
-                        };
-                        
-                        if let Err(e) = app_handle.emit("tcp_message_received", &event) {
-                            log::error!("Failed to emit tcp_message_received event: {}", e);
-                        }
-                    }
-                    
-                    log::info!("Received message on connection {}: {}", connection_id, message);
-                }
+            Some(Ok(frame)) => {
+                let received_msg =
+                    TcpReceivedMessage::from_frame(frame.payload, format!("Connection {}", connection_id), encoding);
+                deliver_connection_message(&connection_id, &messages, received_msg, Some(frame.wire)).await;
             }
-            Err(e) => {
+            Some(Err(e)) => {
                 log::error!("Error reading from connection {}: {}", connection_id, e);
-                break;
+                return Err(e);
             }
         }
     }
@@ -516,6 +2223,11 @@ async fn handle_connection_receiver(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::{
+        connection_request, poll_until, server_config, start_test_server, wait_for_clients,
+        wait_for_messages, wait_for_server_messages, wait_for_state,
+    };
+    use tokio::net::TcpListener;
 
     #[test]
     fn test_tcp_message_serialization() {
@@ -523,6 +2235,10 @@ mod tests {
             host: "localhost".to_string(),
             port: 8080,
             message: "Hello, World!".to_string(),
+            payload_format: PayloadFormat::Text,
+            encoding: TextEncoding::Utf8,
+            framing: None,
+            timeouts: SendTimeouts::default(),
         };
 
         let json = serde_json::to_string(&message).unwrap();
@@ -533,9 +2249,587 @@ mod tests {
         assert_eq!(message.message, deserialized.message);
     }
 
+    #[test]
+    fn test_received_message_keeps_invalid_utf8() {
+        let received = TcpReceivedMessage::from_frame(
+            vec![0x02, b'A', 0xFF, 0x03],
+            "peer".to_string(),
+            TextEncoding::Utf8,
+        );
+
+        assert_eq!(received.raw, vec![0x02, b'A', 0xFF, 0x03]);
+        assert_eq!(received.message, "\u{2}A\u{FFFD}\u{3}");
+        assert!(received.lossy);
+        assert_eq!(received.hex, "02 41 FF 03");
+        assert_eq!(received.base64, "AkH/Aw==");
+    }
+
+    #[tokio::test]
+    async fn test_server_client_management() {
+        use tokio::io::AsyncReadExt;
+
+        let server_id = "client-management";
+        let port = start_test_server(server_id).await;
+
+        let mut first = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut second = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let clients = wait_for_clients(server_id, 2).await;
+        assert_eq!(clients.len(), 2);
+        assert!(clients.iter().all(|client| client.server_id == server_id));
+
+        let first_id = clients
+            .iter()
+            .find(|client| client.client_addr == first.local_addr().unwrap().to_string())
+            .unwrap()
+            .id
+            .clone();
+
+        // 個別送信
+        let result = send_to_server_client(ServerClientMessage {
+            client_id: first_id.clone(),
+            message: "hello".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert!(result.success);
+        let mut buf = [0u8; 6];
+        first.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello\n");
+
+        // 一斉送信
+        let result = broadcast_to_server_clients(ServerBroadcastMessage {
+            server_id: Some(server_id.to_string()),
+            message: "all".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert_eq!(result.sent, 2);
+        let mut buf = [0u8; 4];
+        first.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"all\n");
+        second.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"all\n");
+
+        // 強制切断すると相手側はEOFになり、一覧から消える
+        disconnect_server_client(first_id.clone()).await.unwrap();
+        assert_eq!(first.read(&mut buf).await.unwrap(), 0);
+        assert_eq!(wait_for_clients(server_id, 1).await.len(), 1);
+        assert!(matches!(
+            disconnect_server_client(first_id).await,
+            Err(TcpError::ConnectionNotFound(_))
+        ));
+
+        // クライアント側から切断した場合も一覧から消える
+        drop(second);
+        assert!(wait_for_clients(server_id, 0).await.is_empty());
+
+        stop_tcp_server(Some(server_id.to_string()), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_broadcast_continues_past_failed_client() {
+        use tokio::io::AsyncReadExt;
+
+        let server_id = "broadcast-dead-peer";
+        let port = start_test_server(server_id).await;
+        let mut alive = Vec::new();
+        for _ in 0..3 {
+            alive.push(TcpStream::connect(("127.0.0.1", port)).await.unwrap());
+        }
+        let clients = wait_for_clients(server_id, 3).await;
+
+        // 1つのクライアントの書き込み先を、相手がいなくなったストリームに差し替える
+        let dead_id = clients[1].id.clone();
+        let context = servers().lock().await.get(server_id).unwrap().context.clone();
+        let writer = Arc::clone(&context.clients.lock().await.get(&dead_id).unwrap().writer);
+        let (dead, peer) = tokio::io::duplex(64);
+        drop(peer);
+        *writer.lock().await = ConnectionWriter::Stream(Box::new(dead));
+
+        let result = broadcast_to_server_clients(ServerBroadcastMessage {
+            server_id: Some(server_id.to_string()),
+            message: "all".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert!(!result.success);
+        assert_eq!(result.sent, 2);
+        assert_eq!(result.failures.len(), 1);
+        assert_eq!(result.failures[0].client_id, dead_id);
+
+        // 差し替えなかったクライアントには届いている
+        let mut received = 0;
+        for stream in &mut alive {
+            let mut buf = [0u8; 4];
+            let read = tokio::time::timeout(std::time::Duration::from_millis(200), stream.read_exact(&mut buf)).await;
+            if let Ok(Ok(_)) = read {
+                assert_eq!(&buf, b"all\n");
+                received += 1;
+            }
+        }
+        assert_eq!(received, 2);
+
+        remove_tcp_server(Some(server_id.to_string())).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_server_messages_since_sequence() {
+        use tokio::io::AsyncWriteExt;
+
+        let server_id = Some("since-sequence".to_string());
+        let port = start_test_server("since-sequence").await;
+
+        let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        client.write_all(b"one\ntwo\n").await.unwrap();
+
+        let messages = poll_until(
+            || async { get_received_messages_since(0, server_id.clone()).await.unwrap().messages },
+            |messages| messages.len() == 2,
+        )
+        .await;
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].seq < messages[1].seq);
+
+        // 最後に取得した通し番号以降だけが返る
+        let last_seq = messages[1].seq;
+        assert!(get_received_messages_since(last_seq, server_id.clone()).await.unwrap().messages.is_empty());
+
+        client.write_all(b"three\n").await.unwrap();
+        let messages = poll_until(
+            || async { get_received_messages_since(last_seq, server_id.clone()).await.unwrap().messages },
+            |messages| !messages.is_empty(),
+        )
+        .await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "three");
+
+        stop_tcp_server(server_id, None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multiple_servers_are_independent() {
+        use tokio::io::AsyncWriteExt;
+
+        let port_a = start_test_server("multi-a").await;
+        let port_b = start_test_server("multi-b").await;
+
+        let servers = list_tcp_servers().await.unwrap();
+        let ids: Vec<&str> = servers.iter().map(|server| server.id.as_str()).collect();
+        assert!(ids.contains(&"multi-a") && ids.contains(&"multi-b"));
+
+        let mut client_a = TcpStream::connect(("127.0.0.1", port_a)).await.unwrap();
+        let mut client_b = TcpStream::connect(("127.0.0.1", port_b)).await.unwrap();
+        client_a.write_all(b"from a\n").await.unwrap();
+        client_b.write_all(b"from b\n").await.unwrap();
+
+        let messages_a = wait_for_server_messages("multi-a", 1).await;
+        let messages_b = wait_for_server_messages("multi-b", 1).await;
+        assert_eq!(messages_a.len(), 1);
+        assert_eq!(messages_a[0].message, "from a");
+        assert_eq!(messages_b.len(), 1);
+        assert_eq!(messages_b[0].message, "from b");
+
+        // 一方を停止してももう一方は動作し続け、停止したサーバーの履歴は残る
+        stop_tcp_server(Some("multi-a".to_string()), None).await.unwrap();
+        let ids: Vec<String> = list_tcp_servers().await.unwrap().into_iter().map(|server| server.id).collect();
+        assert!(!ids.contains(&"multi-a".to_string()));
+        assert!(ids.contains(&"multi-b".to_string()));
+        assert_eq!(get_received_messages(Some("multi-a".to_string())).await.unwrap().messages.len(), 1);
+        assert_eq!(wait_for_clients("multi-b", 1).await.len(), 1);
+
+        // 削除すると履歴も消える
+        remove_tcp_server(Some("multi-a".to_string())).await.unwrap();
+        assert!(matches!(
+            get_received_messages(Some("multi-a".to_string())).await,
+            Err(TcpError::ConnectionNotFound(_))
+        ));
+        assert!(remove_tcp_server(Some("multi-a".to_string())).await.is_err());
+
+        assert!(matches!(
+            get_received_messages(Some("missing".to_string())).await,
+            Err(TcpError::ConnectionNotFound(_))
+        ));
+
+        stop_tcp_server(Some("multi-b".to_string()), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failed_restart_keeps_running_server() {
+        let server_id = "restart-busy";
+        let port = start_test_server(server_id).await;
+        let busy = TcpListener::bind("127.0.0.1:0").await.unwrap();
+
+        // 使用中のポートでの再起動は失敗し、元のサーバーは動作し続ける
+        let result = start_tcp_server(server_config(server_id, busy.local_addr().unwrap().port())).await;
+        assert!(matches!(result, Err(TcpError::ServerStartFailed(_))));
+        let _client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(wait_for_clients(server_id, 1).await.len(), 1);
+
+        // 同じポートでの再起動は既存のサーバーを置き換える
+        start_tcp_server(server_config(server_id, port)).await.unwrap();
+        assert!(wait_for_clients(server_id, 0).await.is_empty());
+        let _client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(wait_for_clients(server_id, 1).await.len(), 1);
+
+        remove_tcp_server(Some(server_id.to_string())).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_graceful_stop_closes_clients() {
+        use tokio::io::AsyncReadExt;
+
+        let server_id = "graceful-stop";
+        let port = start_test_server(server_id).await;
+
+        let mut polite = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let _stubborn = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(wait_for_clients(server_id, 2).await.len(), 2);
+
+        // お別れメッセージとEOFを受け取ったら切断するクライアント
+        let polite_task = tokio::spawn(async move {
+            let mut received = Vec::new();
+            polite.read_to_end(&mut received).await.unwrap();
+            received
+        });
+
+        let result = stop_tcp_server(
+            Some(server_id.to_string()),
+            Some(TcpServerStopOptions {
+                mode: ServerStopMode::Graceful,
+                goodbye_message: Some("bye".to_string()),
+                payload_format: PayloadFormat::Text,
+                timeout_ms: 200,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert!(result.was_running);
+        assert_eq!(result.closed_clients, 2);
+        // 切断しないクライアントはタイムアウト後に中断される
+        assert_eq!(result.timed_out_clients, 1);
+        assert_eq!(polite_task.await.unwrap(), b"bye\n");
+        assert!(list_tcp_servers().await.unwrap().iter().all(|server| server.id != server_id));
+
+        // 受付も停止している
+        assert!(TcpStream::connect(("127.0.0.1", port)).await.is_err());
+
+        let result = stop_tcp_server(Some(server_id.to_string()), None).await.unwrap();
+        assert!(!result.was_running);
+    }
+
+    #[tokio::test]
+    async fn test_abort_stop_drops_clients() {
+        use tokio::io::AsyncReadExt;
+
+        let server_id = "abort-stop";
+        let port = start_test_server(server_id).await;
+        let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        assert_eq!(wait_for_clients(server_id, 1).await.len(), 1);
+
+        let result = stop_tcp_server(
+            Some(server_id.to_string()),
+            Some(TcpServerStopOptions {
+                mode: ServerStopMode::Abort,
+                goodbye_message: Some("ignored".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.closed_clients, 1);
+        assert_eq!(result.timed_out_clients, 0);
+
+        // メッセージは送られずに切断される
+        let mut received = Vec::new();
+        let _ = client.read_to_end(&mut received).await;
+        assert!(received.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stop_aborts_pending_tls_handshake() {
+        use crate::test_support::free_port;
+        use tokio::io::AsyncReadExt;
+
+        let server_id = "tls-stop-handshake";
+        let port = free_port();
+        let config = TcpServerConfig {
+            tls: Some(TlsServerOptions { self_signed: true, ..Default::default() }),
+            ..server_config(server_id, port)
+        };
+        start_tcp_server(config).await.unwrap();
+
+        // TCP接続だけしてハンドシェイクを始めないクライアント
+        let mut client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        stop_tcp_server(Some(server_id.to_string()), None).await.unwrap();
+
+        // ハンドシェイクのタイムアウトを待たずにソケットが閉じ、クライアントとして登録されない
+        let mut received = Vec::new();
+        let closed = tokio::time::timeout(std::time::Duration::from_secs(2), client.read_to_end(&mut received)).await;
+        assert!(closed.is_ok());
+        assert!(received.is_empty());
+        let clients = list_server_clients(None).await.unwrap();
+        assert!(clients.iter().all(|client| client.server_id != server_id));
+    }
+
+    #[tokio::test]
+    async fn test_connection_state_distinguishes_fin_and_rst() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // 相手が正常に切断（FIN）
+        let fin = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        assert_eq!(fin.state, ConnectionState::Connected);
+        let (peer, _) = listener.accept().await.unwrap();
+        drop(peer);
+
+        let closed = wait_for_state(&fin.id, ConnectionState::RemoteClosed).await;
+        assert_eq!(closed.close_reason, Some(CloseReason::Fin));
+        assert!(!closed.connected);
+        assert!(closed.closed_at.is_some());
+
+        // 切断済みの接続には送信できない
+        let result = send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: fin.id.clone(),
+            message: "late".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await;
+        assert!(matches!(result, Err(TcpError::SendFailed(_))));
+
+        // 相手がリセット（SO_LINGER=0でクローズするとRSTが送られる）
+        let rst = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        socket2::SockRef::from(&peer).set_linger(Some(std::time::Duration::ZERO)).unwrap();
+        drop(peer);
+
+        let reset = wait_for_state(&rst.id, ConnectionState::RemoteClosed).await;
+        assert_eq!(reset.close_reason, Some(CloseReason::Reset));
+
+        let ids: Vec<String> = list_connections().await.unwrap().into_iter().map(|c| c.id).collect();
+        assert!(ids.contains(&fin.id) && ids.contains(&rst.id));
+
+        // こちらから切断するとレジストリから削除される
+        disconnect_tcp(fin.id.clone()).await.unwrap();
+        disconnect_tcp(rst.id).await.unwrap();
+        assert!(matches!(get_connection(fin.id).await, Err(TcpError::ConnectionNotFound(_))));
+    }
+
+    #[test]
+    fn test_reconnect_backoff_delay() {
+        let policy = ReconnectPolicy {
+            initial_delay_ms: 100,
+            max_delay_ms: 1000,
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_attempts: None,
+            init_script: Vec::new(),
+            init_payload_format: PayloadFormat::Text,
+        };
+
+        // 揺らぎの中央値では指数的に増え、上限で頭打ちになる
+        assert_eq!(policy.delay(1, 0.5).as_millis(), 100);
+        assert_eq!(policy.delay(2, 0.5).as_millis(), 200);
+        assert_eq!(policy.delay(4, 0.5).as_millis(), 800);
+        assert_eq!(policy.delay(5, 0.5).as_millis(), 1000);
+        assert_eq!(policy.delay(u32::MAX, 0.5).as_millis(), 1000);
+
+        // 揺らぎは±jitterの範囲
+        assert_eq!(policy.delay(2, 0.0).as_millis(), 100);
+        assert_eq!(policy.delay(2, 1.0).as_millis(), 300);
+    }
+
+    fn reconnect_request(port: u16, max_attempts: Option<u32>) -> TcpConnectionRequest {
+        TcpConnectionRequest {
+            reconnect: Some(ReconnectPolicy {
+                initial_delay_ms: 10,
+                max_delay_ms: 50,
+                multiplier: 2.0,
+                jitter: 0.0,
+                max_attempts,
+                init_script: vec!["HELLO".to_string()],
+                init_payload_format: PayloadFormat::Text,
+            }),
+            ..connection_request(port)
+        }
+    }
+
+    async fn read_line(peer: &mut TcpStream) -> String {
+        let mut buffer = Vec::new();
+        let mut byte = [0u8; 1];
+        while tokio::io::AsyncReadExt::read(peer, &mut byte).await.unwrap() == 1 && byte[0] != b'\n' {
+            buffer.push(byte[0]);
+        }
+        String::from_utf8(buffer).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_keeps_connection_id_and_history() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connection = open_tcp_connection(reconnect_request(port, None)).await.unwrap().connection.unwrap();
+        let (mut peer, _) = listener.accept().await.unwrap();
+        assert_eq!(read_line(&mut peer).await, "HELLO");
+        peer.write_all(b"before\n").await.unwrap();
+        wait_for_messages(&connection.id, 1).await;
+
+        // 機器の再起動を模して切断すると、同じ接続IDで再接続して初期化メッセージを再送する
+        drop(peer);
+        let (mut peer, _) = listener.accept().await.unwrap();
+        assert_eq!(read_line(&mut peer).await, "HELLO");
+        let reconnected = wait_for_state(&connection.id, ConnectionState::Connected).await;
+        assert_eq!(reconnected.reconnects, 1);
+
+        peer.write_all(b"after\n").await.unwrap();
+        let messages = wait_for_messages(&connection.id, 2).await;
+        let texts: Vec<&str> = messages.iter().map(|m| m.message.as_str()).collect();
+        assert_eq!(texts, ["before", "after"]);
+
+        // 再接続後の書き込み側に送信される
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "ping".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert_eq!(read_line(&mut peer).await, "ping");
+
+        disconnect_tcp(connection.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_gives_up_after_max_attempts() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connection = open_tcp_connection(reconnect_request(port, Some(2))).await.unwrap().connection.unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        drop(listener);
+        drop(peer);
+
+        let failed = wait_for_state(&connection.id, ConnectionState::Error).await;
+        assert_eq!(failed.close_reason, Some(CloseReason::Error));
+        assert!(failed.error.unwrap().contains("after 2 attempts"));
+        assert_eq!(failed.reconnects, 0);
+
+        disconnect_tcp(connection.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_idle_and_read_timeouts_close_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        // 送受信がなければこちらから切断する
+        let request = TcpConnectionRequest {
+            timeouts: TcpTimeouts { idle_ms: Some(100), ..Default::default() },
+            ..connection_request(port)
+        };
+        let idle = open_tcp_connection(request).await.unwrap().connection.unwrap();
+        let (mut peer, _) = listener.accept().await.unwrap();
+        let closed = wait_for_state(&idle.id, ConnectionState::Closed).await;
+        assert_eq!(closed.close_reason, Some(CloseReason::IdleTimeout));
+        assert!(closed.error.unwrap().starts_with("Idle timeout"));
+        let mut buf = [0u8; 8];
+        assert_eq!(tokio::io::AsyncReadExt::read(&mut peer, &mut buf).await.unwrap(), 0);
+
+        // 送信していても受信がなければ受信タイムアウト
+        let request = TcpConnectionRequest {
+            timeouts: TcpTimeouts { read_ms: Some(100), ..Default::default() },
+            ..connection_request(port)
+        };
+        let silent = open_tcp_connection(request).await.unwrap().connection.unwrap();
+        let (_peer, _) = listener.accept().await.unwrap();
+        let failed = wait_for_state(&silent.id, ConnectionState::Error).await;
+        assert_eq!(failed.close_reason, Some(CloseReason::ReadTimeout));
+
+        disconnect_tcp(idle.id).await.unwrap();
+        disconnect_tcp(silent.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_timeout_covers_tls_handshake() {
+        // TCP接続は成立するがTLSハンドシェイクに応答しない相手
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let request = TcpConnectionRequest {
+            tls: Some(TlsClientOptions::default()),
+            timeouts: TcpTimeouts { connect_ms: Some(100), ..Default::default() },
+            ..connection_request(port)
+        };
+        let result = open_tcp_connection(request).await;
+        assert!(matches!(result, Err(TcpError::ConnectTimeout(_))));
+        drop(listener);
+    }
+
+    #[tokio::test]
+    async fn test_transcript_records_both_directions() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        let connection = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let (mut peer, _) = listener.accept().await.unwrap();
+
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "41 42".to_string(),
+            payload_format: PayloadFormat::Hex,
+        })
+        .await
+        .unwrap();
+        assert_eq!(read_line(&mut peer).await, "AB");
+        peer.write_all(b"ok\n").await.unwrap();
+        wait_for_messages(&connection.id, 1).await;
+
+        let entries = transcript::get_connection_transcript(connection.id.clone()).await.unwrap();
+        let summary: Vec<(Direction, &str, usize)> = entries.iter().map(|e| (e.direction, e.text.as_str(), e.bytes)).collect();
+        assert_eq!(
+            summary,
+            [(Direction::System, "connected", 0), (Direction::Tx, "AB", 2), (Direction::Rx, "ok", 2)]
+        );
+        assert!(entries.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+
+        // 接続中の記録は削除できない
+        assert!(matches!(
+            transcript::remove_connection_transcript(connection.id.clone()).await,
+            Err(TcpError::InvalidQuery(_))
+        ));
+
+        // 切断後も記録は残り、削除すると破棄される
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+        let entries = transcript::get_connection_transcript(connection.id.clone()).await.unwrap();
+        assert_eq!(entries.last().map(|e| e.direction), Some(Direction::System));
+        transcript::remove_connection_transcript(connection.id.clone()).await.unwrap();
+        assert!(transcript::get_connection_transcript(connection.id).await.is_err());
+    }
+
     #[test]
     fn test_tcp_error_display() {
         let error = TcpError::ConnectionFailed("Connection refused".to_string());
         assert_eq!(error.to_string(), "Connection failed: Connection refused");
     }
+
+    #[test]
+    fn test_tcp_error_serializes_payload_position() {
+        let error = parse_payload("02 4G", PayloadFormat::Hex, TextEncoding::Utf8)
+            .map_err(TcpError::InvalidPayload)
+            .unwrap_err();
+        let json = serde_json::to_value(&error).unwrap();
+        assert_eq!(json["kind"], "invalid_payload");
+        assert_eq!(json["message"], error.to_string());
+        assert_eq!(json["detail"]["position"], 4);
+        assert_eq!(json["detail"]["character"], "G");
+
+        let json = serde_json::to_value(TcpError::SendFailed("broken pipe".to_string())).unwrap();
+        assert_eq!(json["kind"], "send_failed");
+        assert!(json.get("detail").is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,285 @@
+use serde::{Deserialize, Serialize};
+use socket2::{SockRef, Socket};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use tokio::net::TcpStream;
+use tokio::task::JoinHandle;
+
+use crate::tcp::{emit_event, TcpError};
+
+/// サンプリング間隔の既定値と下限
+const DEFAULT_SAMPLE_INTERVAL_MS: u64 = 1000;
+const MIN_SAMPLE_INTERVAL_MS: u64 = 100;
+/// 保持するサンプル数の既定値
+const DEFAULT_MAX_SAMPLES: usize = 600;
+
+/// カーネルから見たTCPソケットの状態（TCP_INFO）
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TcpInfoSample {
+    pub timestamp: String,
+    /// 平滑化RTT（マイクロ秒）
+    pub rtt_us: u32,
+    /// RTTのばらつき（マイクロ秒）
+    pub rtt_var_us: u32,
+    /// 現在のタイムアウトで連続して再送した回数
+    pub retransmits: u32,
+    /// 接続開始からの再送セグメント数
+    pub total_retransmits: u32,
+    /// 輻輳ウィンドウ（セグメント数）
+    pub snd_cwnd: u32,
+    /// 確認応答を待っているセグメント数
+    pub unacked: u32,
+    /// 失われたと判断されたセグメント数
+    pub lost: u32,
+}
+
+/// 定期サンプリングで発行するイベント
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TcpInfoSampledEvent {
+    /// 接続IDまたはサーバーのクライアントID
+    pub id: String,
+    pub sample: TcpInfoSample,
+}
+
+/// サンプリングの設定
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TcpInfoSamplingOptions {
+    #[serde(default)]
+    pub interval_ms: Option<u64>,
+    /// 保持するサンプル数（古いものから破棄）
+    #[serde(default)]
+    pub max_samples: Option<usize>,
+}
+
+#[derive(Default)]
+struct SocketEntry {
+    socket: Option<Arc<Socket>>,
+    history: Arc<Mutex<VecDeque<TcpInfoSample>>>,
+    sampler: Option<JoinHandle<()>>,
+}
+
+// TCP_INFOを読むために複製したソケット（接続IDまたはクライアントIDごと）
+static SOCKETS: std::sync::OnceLock<Mutex<HashMap<String, SocketEntry>>> = std::sync::OnceLock::new();
+
+fn sockets() -> &'static Mutex<HashMap<String, SocketEntry>> {
+    SOCKETS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// TCP_INFOを読めるようにソケットを登録する（再接続時は置き換える）
+pub(crate) fn register_socket(id: &str, stream: &TcpStream) {
+    match SockRef::from(stream).try_clone() {
+        Ok(socket) => {
+            let mut sockets = sockets().lock().unwrap();
+            sockets.entry(id.to_string()).or_default().socket = Some(Arc::new(socket));
+        }
+        Err(e) => log::warn!("Failed to duplicate socket for {}: {}", id, e),
+    }
+}
+
+/// 接続・クライアントの削除時に呼び出し、複製したソケットとサンプリングを破棄する
+pub(crate) fn forget_socket(id: &str) {
+    if let Some(entry) = sockets().lock().unwrap().remove(id) {
+        if let Some(sampler) = entry.sampler {
+            sampler.abort();
+        }
+    }
+}
+
+fn registered_socket(id: &str) -> Result<Arc<Socket>, TcpError> {
+    sockets()
+        .lock()
+        .unwrap()
+        .get(id)
+        .and_then(|entry| entry.socket.clone())
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("No TCP socket with ID {}", id)))
+}
+
+#[cfg(target_os = "linux")]
+fn read_tcp_info(socket: &Socket) -> Result<TcpInfoSample, TcpError> {
+    use std::os::fd::AsRawFd;
+
+    // SAFETY: tcp_infoはゼロ初期化が有効な値で、長さを渡してカーネルに書き込ませる
+    let mut info: libc::tcp_info = unsafe { std::mem::zeroed() };
+    let mut len = std::mem::size_of::<libc::tcp_info>() as libc::socklen_t;
+    let ret = unsafe {
+        libc::getsockopt(
+            socket.as_raw_fd(),
+            libc::IPPROTO_TCP,
+            libc::TCP_INFO,
+            &mut info as *mut libc::tcp_info as *mut libc::c_void,
+            &mut len,
+        )
+    };
+    if ret != 0 {
+        let error = std::io::Error::last_os_error();
+        return Err(match error.raw_os_error() {
+            Some(libc::ENOPROTOOPT | libc::EOPNOTSUPP) => {
+                TcpError::Unsupported(format!("TCP_INFO is not available: {}", error))
+            }
+            _ => TcpError::ConnectionFailed(format!("Failed to read TCP_INFO: {}", error)),
+        });
+    }
+
+    Ok(TcpInfoSample {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        rtt_us: info.tcpi_rtt,
+        rtt_var_us: info.tcpi_rttvar,
+        retransmits: info.tcpi_retransmits.into(),
+        total_retransmits: info.tcpi_total_retrans,
+        snd_cwnd: info.tcpi_snd_cwnd,
+        unacked: info.tcpi_unacked,
+        lost: info.tcpi_lost,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn read_tcp_info(_socket: &Socket) -> Result<TcpInfoSample, TcpError> {
+    Err(TcpError::Unsupported("TCP_INFO is only available on Linux".to_string()))
+}
+
+/// 接続またはサーバーのクライアントのTCP_INFOを取得
+#[tauri::command]
+pub async fn get_tcp_info(id: String) -> Result<TcpInfoSample, TcpError> {
+    let socket = registered_socket(&id)?;
+    read_tcp_info(&socket)
+}
+
+/// TCP_INFOの定期サンプリングを開始（実行中の場合は設定を変えて再開し、履歴は引き継ぐ）
+#[tauri::command]
+pub async fn start_tcp_info_sampling(id: String, options: Option<TcpInfoSamplingOptions>) -> Result<(), TcpError> {
+    let options = options.unwrap_or_default();
+    let interval_ms = options
+        .interval_ms
+        .unwrap_or(DEFAULT_SAMPLE_INTERVAL_MS)
+        .max(MIN_SAMPLE_INTERVAL_MS);
+    let max_samples = options.max_samples.unwrap_or(DEFAULT_MAX_SAMPLES).max(1);
+
+    // 対応していない環境ではここでエラーにする
+    let socket = registered_socket(&id)?;
+    read_tcp_info(&socket)?;
+
+    let mut sockets = sockets().lock().unwrap();
+    let entry = sockets
+        .get_mut(&id)
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("No TCP socket with ID {}", id)))?;
+    if let Some(sampler) = entry.sampler.take() {
+        sampler.abort();
+    }
+
+    let history = Arc::clone(&entry.history);
+    entry.sampler = Some(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_millis(interval_ms));
+        loop {
+            interval.tick().await;
+            // 接続が削除されたら終了
+            let sample = match registered_socket(&id).and_then(|socket| read_tcp_info(&socket)) {
+                Ok(sample) => sample,
+                Err(e) => {
+                    log::info!("Stopped TCP_INFO sampling for {}: {}", id, e);
+                    break;
+                }
+            };
+
+            {
+                let mut history = history.lock().unwrap();
+                while history.len() >= max_samples {
+                    history.pop_front();
+                }
+                history.push_back(sample.clone());
+            }
+            emit_event("tcp_info_sampled", &TcpInfoSampledEvent { id: id.clone(), sample });
+        }
+    }));
+    Ok(())
+}
+
+/// TCP_INFOの定期サンプリングを停止（履歴は残す）
+#[tauri::command]
+pub async fn stop_tcp_info_sampling(id: String) -> Result<(), TcpError> {
+    let mut sockets = sockets().lock().unwrap();
+    let entry = sockets
+        .get_mut(&id)
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("No TCP socket with ID {}", id)))?;
+    if let Some(sampler) = entry.sampler.take() {
+        sampler.abort();
+    }
+    Ok(())
+}
+
+/// サンプリングしたTCP_INFOの時系列（古い順）
+#[tauri::command]
+pub async fn get_tcp_info_history(id: String) -> Result<Vec<TcpInfoSample>, TcpError> {
+    let sockets = sockets().lock().unwrap();
+    let entry = sockets
+        .get(&id)
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("No TCP socket with ID {}", id)))?;
+    let history = entry.history.lock().unwrap().iter().cloned().collect();
+    Ok(history)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::tcp::{disconnect_server_client, disconnect_tcp, open_tcp_connection, stop_tcp_server, ConnectionState};
+    use crate::test_support::{connection_request, start_test_server, wait_for_clients, wait_for_state};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_tcp_info_for_connection() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let _peer = listener.accept().await.unwrap();
+
+        let info = get_tcp_info(connection.id.clone()).await.unwrap();
+        assert!(info.snd_cwnd > 0);
+        assert_eq!(info.unacked, 0);
+
+        start_tcp_info_sampling(
+            connection.id.clone(),
+            Some(TcpInfoSamplingOptions { interval_ms: Some(100), max_samples: Some(2) }),
+        )
+        .await
+        .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(350)).await;
+        stop_tcp_info_sampling(connection.id.clone()).await.unwrap();
+
+        // 上限を超えた古いサンプルは破棄される
+        let history = get_tcp_info_history(connection.id.clone()).await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert!(history[0].timestamp <= history[1].timestamp);
+
+        // 切断するとソケットも破棄される
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+        assert!(matches!(get_tcp_info(connection.id).await, Err(TcpError::ConnectionNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_socket_is_released_when_peer_closes() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let connection = open_tcp_connection(connection_request(port)).await.unwrap().connection.unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        assert!(get_tcp_info(connection.id.clone()).await.is_ok());
+
+        drop(peer);
+        wait_for_state(&connection.id, ConnectionState::RemoteClosed).await;
+        assert!(matches!(get_tcp_info(connection.id.clone()).await, Err(TcpError::ConnectionNotFound(_))));
+        disconnect_tcp(connection.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tcp_info_for_server_client() {
+        let port = start_test_server("tcp-info").await;
+
+        let _client = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let clients = wait_for_clients("tcp-info", 1).await;
+        let client_id = clients[0].id.clone();
+        assert!(get_tcp_info(client_id.clone()).await.unwrap().snd_cwnd > 0);
+
+        // キックしたクライアントのソケットは破棄される
+        disconnect_server_client(client_id.clone()).await.unwrap();
+        assert!(matches!(get_tcp_info(client_id).await, Err(TcpError::ConnectionNotFound(_))));
+        stop_tcp_server(Some("tcp-info".to_string()), None).await.unwrap();
+    }
+}
@@ -0,0 +1,110 @@
+use std::future::Future;
+use std::time::Duration;
+
+use crate::framing::Framing;
+use crate::message_store::RetentionPolicy;
+use crate::socket_options::SocketOptions;
+use crate::tcp::{
+    get_connection, get_received_messages, get_received_messages_from_connection, list_server_clients,
+    start_tcp_server, ConnectionState, ServerClientInfo, TcpConnection, TcpConnectionRequest, TcpReceivedMessage,
+    TcpServerConfig,
+};
+use crate::text_encoding::TextEncoding;
+
+/// 条件を満たすまで10ms間隔で最大1秒待ち、最後に取得した値を返す
+pub(crate) async fn poll_until<T, F, Fut>(mut fetch: F, done: impl Fn(&T) -> bool) -> T
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = T>,
+{
+    for _ in 0..100 {
+        let value = fetch().await;
+        if done(&value) {
+            return value;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    fetch().await
+}
+
+/// OSが割り当てた空きポート番号
+pub(crate) fn free_port() -> u16 {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    listener.local_addr().unwrap().port()
+}
+
+/// 127.0.0.1で行単位に待ち受けるサーバーの設定
+pub(crate) fn server_config(server_id: &str, port: u16) -> TcpServerConfig {
+    TcpServerConfig {
+        id: Some(server_id.to_string()),
+        host: "127.0.0.1".to_string(),
+        port,
+        framing: Framing::Line,
+        encoding: TextEncoding::Utf8,
+        tls: None,
+        socket_options: SocketOptions::default(),
+        retention: RetentionPolicy::default(),
+    }
+}
+
+/// 空いているポートでテスト用のサーバーを起動する
+pub(crate) async fn start_test_server(server_id: &str) -> u16 {
+    let port = free_port();
+    start_tcp_server(server_config(server_id, port)).await.unwrap();
+    port
+}
+
+/// 127.0.0.1への行単位の接続要求
+pub(crate) fn connection_request(port: u16) -> TcpConnectionRequest {
+    TcpConnectionRequest {
+        host: "127.0.0.1".to_string(),
+        port,
+        framing: Framing::Line,
+        encoding: TextEncoding::Utf8,
+        tls: None,
+        reconnect: None,
+        timeouts: Default::default(),
+        socket_options: SocketOptions::default(),
+        retention: RetentionPolicy::default(),
+    }
+}
+
+pub(crate) async fn wait_for_state(connection_id: &str, state: ConnectionState) -> TcpConnection {
+    poll_until(
+        || async { get_connection(connection_id.to_string()).await.unwrap() },
+        |connection| connection.state == state,
+    )
+    .await
+}
+
+/// 接続の受信メッセージが`count`件以上になるまで待つ
+pub(crate) async fn wait_for_messages(connection_id: &str, count: usize) -> Vec<TcpReceivedMessage> {
+    poll_until(
+        || async {
+            get_received_messages_from_connection(connection_id.to_string())
+                .await
+                .unwrap()
+                .messages
+        },
+        |messages| messages.len() >= count,
+    )
+    .await
+}
+
+/// サーバーの受信メッセージが`count`件以上になるまで待つ
+pub(crate) async fn wait_for_server_messages(server_id: &str, count: usize) -> Vec<TcpReceivedMessage> {
+    poll_until(
+        || async { get_received_messages(Some(server_id.to_string())).await.unwrap().messages },
+        |messages| messages.len() >= count,
+    )
+    .await
+}
+
+/// 接続中のクライアントがちょうど`count`件になるまで待つ
+pub(crate) async fn wait_for_clients(server_id: &str, count: usize) -> Vec<ServerClientInfo> {
+    poll_until(
+        || async { list_server_clients(Some(server_id.to_string())).await.unwrap() },
+        |clients| clients.len() == count,
+    )
+    .await
+}
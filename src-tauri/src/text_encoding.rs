@@ -0,0 +1,161 @@
+use encoding_rs::{EncoderResult, EUC_JP, SHIFT_JIS, UTF_16BE, UTF_16LE};
+use serde::{Deserialize, Serialize};
+
+use crate::payload::PayloadError;
+
+/// 送受信メッセージの文字エンコーディング
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextEncoding {
+    #[default]
+    Utf8,
+    ShiftJis,
+    EucJp,
+    #[serde(rename = "iso_8859_1")]
+    Iso8859_1,
+    Utf16Le,
+    Utf16Be,
+}
+
+impl TextEncoding {
+    /// バイト列を文字列にデコードする
+    ///
+    /// 戻り値の2番目は不正なバイト列を置換文字に置き換えたかどうか
+    pub fn decode(&self, bytes: &[u8]) -> (String, bool) {
+        match self {
+            TextEncoding::Utf8 => match String::from_utf8_lossy(bytes) {
+                std::borrow::Cow::Borrowed(text) => (text.to_string(), false),
+                std::borrow::Cow::Owned(text) => (text, true),
+            },
+            // ISO-8859-1は全バイトがそのままU+0000〜U+00FFに対応する
+            TextEncoding::Iso8859_1 => (bytes.iter().map(|&b| b as char).collect(), false),
+            _ => {
+                let (text, had_errors) = self.encoding_rs().decode_without_bom_handling(bytes);
+                (text.into_owned(), had_errors)
+            }
+        }
+    }
+
+    /// 文字列をバイト列にエンコードする
+    ///
+    /// 表現できない文字があった場合はその位置を含むエラーを返す
+    pub fn encode(&self, text: &str) -> Result<Vec<u8>, PayloadError> {
+        match self {
+            TextEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+            TextEncoding::Iso8859_1 => text
+                .chars()
+                .enumerate()
+                .map(|(i, c)| {
+                    u8::try_from(c as u32).map_err(|_| unmappable(i, c, self))
+                })
+                .collect(),
+            TextEncoding::Utf16Le => Ok(text.encode_utf16().flat_map(u16::to_le_bytes).collect()),
+            TextEncoding::Utf16Be => Ok(text.encode_utf16().flat_map(u16::to_be_bytes).collect()),
+            TextEncoding::ShiftJis | TextEncoding::EucJp => {
+                let mut encoder = self.encoding_rs().new_encoder();
+                let mut out = Vec::with_capacity(text.len() * 2 + 16);
+                let mut consumed = 0;
+                loop {
+                    let (result, read) = encoder.encode_from_utf8_to_vec_without_replacement(
+                        &text[consumed..],
+                        &mut out,
+                        true,
+                    );
+                    consumed += read;
+                    match result {
+                        EncoderResult::InputEmpty => return Ok(out),
+                        EncoderResult::OutputFull => out.reserve(text.len() - consumed + 16),
+                        EncoderResult::Unmappable(c) => {
+                            // readには表現できなかった文字自体も含まれる
+                            let position = text[..consumed].chars().count() - 1;
+                            return Err(unmappable(position, c, self));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// 1コード単位のバイト数（UTF-16は2、それ以外は1）
+    pub fn code_unit_len(&self) -> usize {
+        match self {
+            TextEncoding::Utf16Le | TextEncoding::Utf16Be => 2,
+            _ => 1,
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            TextEncoding::Utf8 => "UTF-8",
+            TextEncoding::ShiftJis => "Shift_JIS",
+            TextEncoding::EucJp => "EUC-JP",
+            TextEncoding::Iso8859_1 => "ISO-8859-1",
+            TextEncoding::Utf16Le => "UTF-16LE",
+            TextEncoding::Utf16Be => "UTF-16BE",
+        }
+    }
+
+    fn encoding_rs(&self) -> &'static encoding_rs::Encoding {
+        match self {
+            TextEncoding::ShiftJis => SHIFT_JIS,
+            TextEncoding::EucJp => EUC_JP,
+            TextEncoding::Utf16Le => UTF_16LE,
+            TextEncoding::Utf16Be => UTF_16BE,
+            TextEncoding::Utf8 | TextEncoding::Iso8859_1 => encoding_rs::UTF_8,
+        }
+    }
+}
+
+fn unmappable(position: usize, character: char, encoding: &TextEncoding) -> PayloadError {
+    PayloadError {
+        position,
+        character: Some(character),
+        reason: format!("Character cannot be encoded in {}", encoding.label()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shift_jis_roundtrip() {
+        let encoded = TextEncoding::ShiftJis.encode("テスト").unwrap();
+        assert_eq!(encoded, vec![0x83, 0x65, 0x83, 0x58, 0x83, 0x67]);
+        assert_eq!(TextEncoding::ShiftJis.decode(&encoded), ("テスト".to_string(), false));
+    }
+
+    #[test]
+    fn test_euc_jp_roundtrip() {
+        let encoded = TextEncoding::EucJp.encode("日本").unwrap();
+        assert_eq!(encoded, vec![0xC6, 0xFC, 0xCB, 0xDC]);
+        assert_eq!(TextEncoding::EucJp.decode(&encoded).0, "日本");
+    }
+
+    #[test]
+    fn test_decode_reports_lossy() {
+        let (text, lossy) = TextEncoding::Utf8.decode(&[b'A', 0xFF]);
+        assert_eq!(text, "A\u{FFFD}");
+        assert!(lossy);
+
+        let (text, lossy) = TextEncoding::Iso8859_1.decode(&[0xE9]);
+        assert_eq!(text, "é");
+        assert!(!lossy);
+    }
+
+    #[test]
+    fn test_utf16_encoding() {
+        assert_eq!(TextEncoding::Utf16Le.encode("Aあ").unwrap(), vec![0x41, 0x00, 0x42, 0x30]);
+        assert_eq!(TextEncoding::Utf16Be.decode(&[0x00, 0x41, 0x30, 0x42]).0, "Aあ");
+    }
+
+    #[test]
+    fn test_unmappable_character_position() {
+        let err = TextEncoding::ShiftJis.encode("ab😀").unwrap_err();
+        assert_eq!(err.position, 2);
+        assert_eq!(err.character, Some('😀'));
+
+        let err = TextEncoding::Iso8859_1.encode("né日").unwrap_err();
+        assert_eq!(err.position, 2);
+    }
+}
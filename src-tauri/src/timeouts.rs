@@ -0,0 +1,373 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::io;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncWrite, ReadBuf};
+use tokio::sync::Notify;
+use tokio::time::{Instant, Sleep};
+
+/// 接続のタイムアウト設定（省略した項目は無制限）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct TcpTimeouts {
+    /// 接続の確立（TLSの場合はハンドシェイクまで）
+    #[serde(default)]
+    pub connect_ms: Option<u64>,
+    /// 受信がない状態がこの時間続いたら切断
+    #[serde(default)]
+    pub read_ms: Option<u64>,
+    /// 送信がこの時間進まなかったら失敗とする
+    #[serde(default)]
+    pub write_ms: Option<u64>,
+    /// 送受信ともにない状態がこの時間続いたら切断
+    #[serde(default)]
+    pub idle_ms: Option<u64>,
+}
+
+impl TcpTimeouts {
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect_ms.map(Duration::from_millis)
+    }
+
+    pub fn read(&self) -> Option<Duration> {
+        self.read_ms.map(Duration::from_millis)
+    }
+
+    pub fn write(&self) -> Option<Duration> {
+        self.write_ms.map(Duration::from_millis)
+    }
+
+    pub fn idle(&self) -> Option<Duration> {
+        self.idle_ms.map(Duration::from_millis)
+    }
+}
+
+/// 単発送信（`send_tcp_message`）のタイムアウト設定（省略した項目は無制限）
+///
+/// 接続を維持しないため受信・アイドルのタイムアウトはない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct SendTimeouts {
+    /// 接続の確立
+    #[serde(default)]
+    pub connect_ms: Option<u64>,
+    /// 送信がこの時間進まなかったら失敗とする
+    #[serde(default)]
+    pub write_ms: Option<u64>,
+}
+
+impl SendTimeouts {
+    pub fn connect(&self) -> Option<Duration> {
+        self.connect_ms.map(Duration::from_millis)
+    }
+
+    pub fn write(&self) -> Option<Duration> {
+        self.write_ms.map(Duration::from_millis)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeoutKind {
+    Connect,
+    Read,
+    Write,
+    Idle,
+}
+
+/// 発生したタイムアウト
+///
+/// 受信処理の結果として返せるよう`io::Error`に包んで扱う
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConnectionTimeout {
+    pub kind: TimeoutKind,
+    pub after: Duration,
+}
+
+impl ConnectionTimeout {
+    pub fn new(kind: TimeoutKind, after: Duration) -> Self {
+        Self { kind, after }
+    }
+
+    /// `io::Error`に包まれたタイムアウトを取り出す
+    pub fn from_io(error: &io::Error) -> Option<Self> {
+        error.get_ref()?.downcast_ref::<ConnectionTimeout>().copied()
+    }
+}
+
+impl fmt::Display for ConnectionTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = self.after.as_millis();
+        match self.kind {
+            TimeoutKind::Connect => write!(f, "not connected within {} ms", ms),
+            TimeoutKind::Read => write!(f, "no data received for {} ms", ms),
+            TimeoutKind::Write => write!(f, "write did not progress for {} ms", ms),
+            TimeoutKind::Idle => write!(f, "no traffic for {} ms", ms),
+        }
+    }
+}
+
+impl std::error::Error for ConnectionTimeout {}
+
+impl From<ConnectionTimeout> for io::Error {
+    fn from(timeout: ConnectionTimeout) -> Self {
+        io::Error::new(io::ErrorKind::TimedOut, timeout)
+    }
+}
+
+/// 接続の最終送受信時刻
+pub struct ConnectionActivity {
+    last_read: Mutex<Instant>,
+    last_write: Mutex<Instant>,
+    write_timed_out: AtomicBool,
+    notify: Notify,
+}
+
+impl ConnectionActivity {
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            last_read: Mutex::new(now),
+            last_write: Mutex::new(now),
+            write_timed_out: AtomicBool::new(false),
+            notify: Notify::new(),
+        }
+    }
+
+    /// 再接続時に計測をやり直す
+    pub fn reset(&self) {
+        let now = Instant::now();
+        *self.last_read.lock().unwrap() = now;
+        *self.last_write.lock().unwrap() = now;
+        self.write_timed_out.store(false, Ordering::SeqCst);
+    }
+
+    fn touch_read(&self) {
+        *self.last_read.lock().unwrap() = Instant::now();
+    }
+
+    fn touch_write(&self) {
+        *self.last_write.lock().unwrap() = Instant::now();
+    }
+
+    fn mark_write_timed_out(&self) {
+        self.write_timed_out.store(true, Ordering::SeqCst);
+        self.notify.notify_one();
+    }
+}
+
+impl Default for ConnectionActivity {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 受信・アイドル・送信タイムアウトを監視し、発生したらそのエラーを返す
+///
+/// 監視対象のタイムアウトがない場合は終了しない
+pub async fn watch(activity: &ConnectionActivity, timeouts: &TcpTimeouts) -> io::Error {
+    loop {
+        if activity.write_timed_out.load(Ordering::SeqCst) {
+            let after = timeouts.write().unwrap_or_default();
+            return ConnectionTimeout::new(TimeoutKind::Write, after).into();
+        }
+
+        let now = Instant::now();
+        let last_read = *activity.last_read.lock().unwrap();
+        let last_write = *activity.last_write.lock().unwrap();
+        let deadlines = [
+            timeouts.read().map(|after| (TimeoutKind::Read, after, last_read + after)),
+            timeouts.idle().map(|after| (TimeoutKind::Idle, after, last_read.max(last_write) + after)),
+        ];
+
+        let mut next_deadline: Option<Instant> = None;
+        for (kind, after, deadline) in deadlines.into_iter().flatten() {
+            if deadline <= now {
+                return ConnectionTimeout::new(kind, after).into();
+            }
+            next_deadline = Some(next_deadline.map_or(deadline, |next| next.min(deadline)));
+        }
+
+        match next_deadline {
+            Some(deadline) => {
+                tokio::select! {
+                    _ = tokio::time::sleep_until(deadline) => {}
+                    _ = activity.notify.notified() => {}
+                }
+            }
+            None => activity.notify.notified().await,
+        }
+    }
+}
+
+/// 受信時刻を記録する読み取り側
+pub struct MonitoredReader<R> {
+    inner: R,
+    activity: Arc<ConnectionActivity>,
+}
+
+impl<R> MonitoredReader<R> {
+    pub fn new(inner: R, activity: Arc<ConnectionActivity>) -> Self {
+        Self { inner, activity }
+    }
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for MonitoredReader<R> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut this.inner).poll_read(cx, buf);
+        if matches!(result, Poll::Ready(Ok(()))) && buf.filled().len() > filled {
+            this.activity.touch_read();
+        }
+        result
+    }
+}
+
+/// 送信時刻を記録し、送信が進まない場合にタイムアウトさせる書き込み側
+pub struct MonitoredWriter<W> {
+    inner: W,
+    activity: Arc<ConnectionActivity>,
+    write_timeout: Option<Duration>,
+    deadline: Option<Pin<Box<Sleep>>>,
+}
+
+impl<W> MonitoredWriter<W> {
+    pub fn new(inner: W, activity: Arc<ConnectionActivity>, write_timeout: Option<Duration>) -> Self {
+        Self {
+            inner,
+            activity,
+            write_timeout,
+            deadline: None,
+        }
+    }
+
+    /// 書き込みが保留中の間だけ期限を計る
+    fn check_deadline<T>(&mut self, cx: &mut Context<'_>, result: Poll<io::Result<T>>) -> Poll<io::Result<T>> {
+        if result.is_ready() {
+            self.deadline = None;
+            return result;
+        }
+        let Some(timeout) = self.write_timeout else {
+            return Poll::Pending;
+        };
+        let deadline = self.deadline.get_or_insert_with(|| Box::pin(tokio::time::sleep(timeout)));
+        if deadline.as_mut().poll(cx).is_pending() {
+            return Poll::Pending;
+        }
+        self.deadline = None;
+        self.activity.mark_write_timed_out();
+        Poll::Ready(Err(ConnectionTimeout::new(TimeoutKind::Write, timeout).into()))
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for MonitoredWriter<W> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_write(cx, buf);
+        if matches!(result, Poll::Ready(Ok(written)) if written > 0) {
+            this.activity.touch_write();
+        }
+        this.check_deadline(cx, result)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_flush(cx);
+        this.check_deadline(cx, result)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        let result = Pin::new(&mut this.inner).poll_shutdown(cx);
+        this.check_deadline(cx, result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    #[test]
+    fn test_timeout_round_trips_through_io_error() {
+        let timeout = ConnectionTimeout::new(TimeoutKind::Idle, Duration::from_millis(250));
+        let error: io::Error = timeout.into();
+        assert_eq!(error.kind(), io::ErrorKind::TimedOut);
+        assert_eq!(ConnectionTimeout::from_io(&error), Some(timeout));
+        assert_eq!(error.to_string(), "no traffic for 250 ms");
+
+        let other = io::Error::new(io::ErrorKind::TimedOut, "os timeout");
+        assert_eq!(ConnectionTimeout::from_io(&other), None);
+    }
+
+    #[tokio::test]
+    async fn test_watch_distinguishes_read_and_idle() {
+        let activity = Arc::new(ConnectionActivity::new());
+        let (client, mut server) = tokio::io::duplex(64);
+
+        // 送信が続いていても受信がなければ受信タイムアウト
+        let timeouts = TcpTimeouts {
+            read_ms: Some(100),
+            idle_ms: Some(100),
+            ..Default::default()
+        };
+        let mut writer = MonitoredWriter::new(client, Arc::clone(&activity), None);
+        let sender = async {
+            loop {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                writer.write_all(b"x").await.unwrap();
+            }
+        };
+        let error = tokio::select! {
+            error = watch(&activity, &timeouts) => error,
+            _ = sender => unreachable!(),
+        };
+        assert_eq!(ConnectionTimeout::from_io(&error).unwrap().kind, TimeoutKind::Read);
+
+        // 受信が続いている間はアイドルにならず、止まってから切れる
+        activity.reset();
+        let timeouts = TcpTimeouts {
+            idle_ms: Some(100),
+            ..Default::default()
+        };
+        let mut reader = MonitoredReader::new(writer.inner, Arc::clone(&activity));
+        let receiver = async {
+            let mut buf = [0u8; 16];
+            loop {
+                let _ = reader.read(&mut buf).await;
+            }
+        };
+        let peer = async {
+            for _ in 0..10 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                server.write_all(b"y").await.unwrap();
+            }
+            std::future::pending::<()>().await;
+        };
+        let started = Instant::now();
+        let error = tokio::select! {
+            error = watch(&activity, &timeouts) => error,
+            _ = receiver => unreachable!(),
+            _ = peer => unreachable!(),
+        };
+        assert_eq!(ConnectionTimeout::from_io(&error).unwrap().kind, TimeoutKind::Idle);
+        assert!(started.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn test_write_timeout_when_peer_stops_reading() {
+        let activity = Arc::new(ConnectionActivity::new());
+        let (client, _server) = tokio::io::duplex(8);
+        let mut writer = MonitoredWriter::new(client, Arc::clone(&activity), Some(Duration::from_millis(50)));
+
+        let error = writer.write_all(&[0u8; 64]).await.unwrap_err();
+        assert_eq!(ConnectionTimeout::from_io(&error).unwrap().kind, TimeoutKind::Write);
+
+        // 監視側にも通知される
+        let error = watch(&activity, &TcpTimeouts::default()).await;
+        assert_eq!(ConnectionTimeout::from_io(&error).unwrap().kind, TimeoutKind::Write);
+    }
+}
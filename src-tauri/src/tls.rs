@@ -0,0 +1,534 @@
+use base64::prelude::{Engine as _, BASE64_STANDARD};
+use rustls::client::danger::{HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier};
+use rustls::crypto::{ring, verify_tls12_signature, verify_tls13_signature, CryptoProvider};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer, ServerName, UnixTime};
+use rustls::server::{ServerConnection, WebPkiClientVerifier};
+use rustls::{ClientConfig, CommonState, DigitallySignedStruct, RootCertStore, ServerConfig, SignatureScheme};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs::File;
+use std::io::BufReader;
+use std::sync::Arc;
+use tokio::net::TcpStream;
+use tokio_rustls::client::TlsStream;
+use tokio_rustls::{TlsAcceptor, TlsConnector};
+
+/// TLS接続のオプション
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsClientOptions {
+    /// SNIおよび証明書検証に使うサーバー名（省略時は接続先ホスト）
+    #[serde(default)]
+    pub server_name: Option<String>,
+    /// 追加で信頼するCA証明書（PEM）のパス
+    #[serde(default)]
+    pub ca_bundle_path: Option<String>,
+    /// 証明書の検証を行わない（検証用機器向け）
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+    #[serde(default)]
+    pub alpn_protocols: Vec<String>,
+}
+
+/// TLSサーバーとして待ち受ける場合のオプション
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TlsServerOptions {
+    /// サーバー証明書（PEM）のパス
+    #[serde(default)]
+    pub cert_path: Option<String>,
+    /// 秘密鍵（PEM）のパス
+    #[serde(default)]
+    pub key_path: Option<String>,
+    /// 証明書を指定しない場合に自己署名証明書を生成する
+    #[serde(default)]
+    pub self_signed: bool,
+    /// クライアント証明書の検証に使うCA証明書（PEM）のパス
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// クライアント証明書を必須にする（mTLS）
+    #[serde(default)]
+    pub require_client_cert: bool,
+    #[serde(default)]
+    pub alpn_protocols: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PeerCertificate {
+    pub subject: String,
+    pub issuer: String,
+    pub serial: String,
+    pub not_before: String,
+    pub not_after: String,
+    pub sha256_fingerprint: String,
+    /// DER形式の証明書をBase64エンコードしたもの
+    pub der_base64: String,
+}
+
+/// ハンドシェイクで確定したTLSセッションの情報
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsSessionInfo {
+    pub server_name: String,
+    pub protocol_version: String,
+    pub cipher_suite: String,
+    pub alpn_protocol: Option<String>,
+    /// 相手のエンドエンティティ証明書のサブジェクト（mTLSでクライアント証明書がない場合はNone）
+    pub peer_subject: Option<String>,
+    pub peer_certificates: Vec<PeerCertificate>,
+}
+
+pub(crate) fn crypto_provider() -> Arc<CryptoProvider> {
+    Arc::new(ring::default_provider())
+}
+
+/// PEMファイルから証明書を読み込む
+pub(crate) fn load_certificates(path: &str) -> Result<Vec<CertificateDer<'static>>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    let certs = rustls_pemfile::certs(&mut BufReader::new(file))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| format!("Failed to parse certificates in {}: {}", path, e))?;
+    if certs.is_empty() {
+        return Err(format!("No certificates found in {}", path));
+    }
+    Ok(certs)
+}
+
+/// PEMファイルから秘密鍵を読み込む
+fn load_private_key(path: &str) -> Result<PrivateKeyDer<'static>, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path, e))?;
+    rustls_pemfile::private_key(&mut BufReader::new(file))
+        .map_err(|e| format!("Failed to parse private key in {}: {}", path, e))?
+        .ok_or_else(|| format!("No private key found in {}", path))
+}
+
+/// 指定したホスト名向けの自己署名証明書を生成する
+fn generate_self_signed(host: &str) -> Result<(Vec<CertificateDer<'static>>, PrivateKeyDer<'static>), String> {
+    let mut names = vec!["localhost".to_string()];
+    if host != "localhost" {
+        names.push(host.to_string());
+    }
+    let certified = rcgen::generate_simple_self_signed(names)
+        .map_err(|e| format!("Failed to generate self-signed certificate: {}", e))?;
+    log::info!(
+        "Generated self-signed certificate (SHA-256 {})",
+        describe_certificate(certified.cert.der()).sha256_fingerprint
+    );
+
+    let key = PrivateKeyDer::Pkcs8(certified.key_pair.serialize_der().into());
+    Ok((vec![certified.cert.der().clone()], key))
+}
+
+/// サーバー用のTLSアクセプターを構築する
+pub fn build_acceptor(options: &TlsServerOptions, host: &str) -> Result<TlsAcceptor, String> {
+    let (certs, key) = match (&options.cert_path, &options.key_path) {
+        (Some(cert_path), Some(key_path)) => (load_certificates(cert_path)?, load_private_key(key_path)?),
+        (None, None) if options.self_signed => generate_self_signed(host)?,
+        (None, None) => {
+            return Err("Either a certificate/key pair or self_signed is required".to_string());
+        }
+        _ => return Err("Both cert_path and key_path must be specified".to_string()),
+    };
+
+    let builder = ServerConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+
+    let builder = match &options.client_ca_path {
+        Some(path) => {
+            let mut roots = RootCertStore::empty();
+            for cert in load_certificates(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("Invalid CA certificate in {}: {}", path, e))?;
+            }
+            let verifier = WebPkiClientVerifier::builder_with_provider(Arc::new(roots), crypto_provider());
+            // 必須でない場合は証明書なしのクライアントも受け入れる
+            let verifier = if options.require_client_cert {
+                verifier.build()
+            } else {
+                verifier.allow_unauthenticated().build()
+            }
+            .map_err(|e| format!("Failed to build client verifier: {}", e))?;
+            builder.with_client_cert_verifier(verifier)
+        }
+        None if options.require_client_cert => {
+            return Err("client_ca_path is required when require_client_cert is set".to_string());
+        }
+        None => builder.with_no_client_auth(),
+    };
+
+    let mut config = builder
+        .with_single_cert(certs, key)
+        .map_err(|e| format!("Invalid server certificate: {}", e))?;
+    config.alpn_protocols = options
+        .alpn_protocols
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+    Ok(TlsAcceptor::from(Arc::new(config)))
+}
+
+fn build_client_config(options: &TlsClientOptions) -> Result<ClientConfig, String> {
+    let builder = ClientConfig::builder_with_provider(crypto_provider())
+        .with_safe_default_protocol_versions()
+        .map_err(|e| e.to_string())?;
+
+    let mut config = if options.accept_invalid_certs {
+        builder
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyServerCert(crypto_provider())))
+            .with_no_client_auth()
+    } else {
+        let mut roots = RootCertStore::empty();
+        roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+        if let Some(path) = &options.ca_bundle_path {
+            for cert in load_certificates(path)? {
+                roots
+                    .add(cert)
+                    .map_err(|e| format!("Invalid CA certificate in {}: {}", path, e))?;
+            }
+        }
+        builder.with_root_certificates(roots).with_no_client_auth()
+    };
+
+    config.alpn_protocols = options
+        .alpn_protocols
+        .iter()
+        .map(|protocol| protocol.as_bytes().to_vec())
+        .collect();
+    Ok(config)
+}
+
+/// 確立済みのTCPストリーム上でTLSハンドシェイクを行う
+pub async fn connect(
+    stream: TcpStream,
+    host: &str,
+    options: &TlsClientOptions,
+) -> Result<(TlsStream<TcpStream>, TlsSessionInfo), String> {
+    let config = build_client_config(options)?;
+    let server_name = options.server_name.clone().unwrap_or_else(|| host.to_string());
+    let name = ServerName::try_from(server_name.clone())
+        .map_err(|e| format!("Invalid server name {}: {}", server_name, e))?;
+
+    let tls_stream = TlsConnector::from(Arc::new(config))
+        .connect(name, stream)
+        .await
+        .map_err(|e| format!("TLS handshake failed: {}", e))?;
+
+    let info = session_info(server_name, tls_stream.get_ref().1);
+    Ok((tls_stream, info))
+}
+
+pub(crate) fn session_info(server_name: String, state: &CommonState) -> TlsSessionInfo {
+    let peer_certificates: Vec<PeerCertificate> = state
+        .peer_certificates()
+        .unwrap_or_default()
+        .iter()
+        .map(|cert| describe_certificate(cert))
+        .collect();
+    TlsSessionInfo {
+        server_name,
+        protocol_version: state
+            .protocol_version()
+            .and_then(|v| v.as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        cipher_suite: state
+            .negotiated_cipher_suite()
+            .and_then(|suite| suite.suite().as_str())
+            .unwrap_or("unknown")
+            .to_string(),
+        alpn_protocol: state
+            .alpn_protocol()
+            .map(|protocol| String::from_utf8_lossy(protocol).into_owned()),
+        peer_subject: peer_certificates.first().map(|cert| cert.subject.clone()),
+        peer_certificates,
+    }
+}
+
+/// サーバー側で受け付けたTLSセッションの情報（`server_name`はクライアントが送ったSNI）
+pub(crate) fn accepted_session_info(connection: &ServerConnection) -> TlsSessionInfo {
+    session_info(connection.server_name().unwrap_or_default().to_string(), connection)
+}
+
+fn describe_certificate(der: &CertificateDer<'_>) -> PeerCertificate {
+    let fingerprint = Sha256::digest(der.as_ref())
+        .iter()
+        .map(|b| format!("{:02X}", b))
+        .collect::<Vec<_>>()
+        .join(":");
+
+    let mut certificate = PeerCertificate {
+        subject: String::new(),
+        issuer: String::new(),
+        serial: String::new(),
+        not_before: String::new(),
+        not_after: String::new(),
+        sha256_fingerprint: fingerprint,
+        der_base64: BASE64_STANDARD.encode(der.as_ref()),
+    };
+
+    // 解析できない証明書でもDERとフィンガープリントは返す
+    match x509_parser::parse_x509_certificate(der.as_ref()) {
+        Ok((_, parsed)) => {
+            certificate.subject = parsed.subject().to_string();
+            certificate.issuer = parsed.issuer().to_string();
+            certificate.serial = parsed.raw_serial_as_string();
+            certificate.not_before = parsed.validity().not_before.to_string();
+            certificate.not_after = parsed.validity().not_after.to_string();
+        }
+        Err(e) => log::warn!("Failed to parse peer certificate: {}", e),
+    }
+    certificate
+}
+
+/// 証明書チェーンを検証せずに受け入れる検証器
+///
+/// ハンドシェイクの署名検証だけは通常通り行う
+#[derive(Debug)]
+struct AcceptAnyServerCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyServerCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls12_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, rustls::Error> {
+        verify_tls13_signature(message, cert, dss, &self.0.signature_verification_algorithms)
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 自己署名証明書で待ち受けるTLSサーバーを起動し、受信データをエコーする
+    async fn start_self_signed_server() -> (u16, String) {
+        let certified = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let cert_pem = certified.cert.pem();
+        let key = PrivateKeyDer::Pkcs8(certified.key_pair.serialize_der().into());
+
+        let mut config = ServerConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .with_no_client_auth()
+            .with_single_cert(vec![certified.cert.der().clone()], key)
+            .unwrap();
+        config.alpn_protocols = vec![b"echo/1".to_vec()];
+
+        let acceptor = TlsAcceptor::from(Arc::new(config));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let acceptor = acceptor.clone();
+                tokio::spawn(async move {
+                    let Ok(mut tls) = acceptor.accept(stream).await else {
+                        return;
+                    };
+                    let mut buf = [0u8; 64];
+                    if let Ok(n) = tls.read(&mut buf).await {
+                        tls.write_all(&buf[..n]).await.ok();
+                    }
+                    tls.shutdown().await.ok();
+                });
+            }
+        });
+        (port, cert_pem)
+    }
+
+    #[tokio::test]
+    async fn test_accept_invalid_certs_with_alpn() {
+        let (port, _) = start_self_signed_server().await;
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let options = TlsClientOptions {
+            server_name: Some("localhost".to_string()),
+            accept_invalid_certs: true,
+            alpn_protocols: vec!["echo/1".to_string()],
+            ..Default::default()
+        };
+
+        let (mut tls, info) = connect(stream, "127.0.0.1", &options).await.unwrap();
+        assert_eq!(info.alpn_protocol.as_deref(), Some("echo/1"));
+        assert_eq!(info.protocol_version, "TLSv1_3");
+        assert_eq!(info.peer_certificates.len(), 1);
+        assert!(info.peer_certificates[0].subject.contains("rcgen"));
+
+        tls.write_all(b"ping").await.unwrap();
+        let mut buf = [0u8; 4];
+        tls.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"ping");
+    }
+
+    #[tokio::test]
+    async fn test_custom_ca_bundle() {
+        let (port, cert_pem) = start_self_signed_server().await;
+        let path = std::env::temp_dir().join(format!("tls-test-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&path, cert_pem).unwrap();
+
+        // 信頼していない自己署名証明書は拒否される
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let options = TlsClientOptions {
+            server_name: Some("localhost".to_string()),
+            ..Default::default()
+        };
+        assert!(connect(stream, "127.0.0.1", &options).await.is_err());
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let options = TlsClientOptions {
+            ca_bundle_path: Some(path.to_string_lossy().into_owned()),
+            ..options
+        };
+        let result = connect(stream, "127.0.0.1", &options).await;
+        std::fs::remove_file(&path).ok();
+        assert_eq!(result.unwrap().1.server_name, "localhost");
+    }
+
+    #[tokio::test]
+    async fn test_self_signed_acceptor() {
+        let options = TlsServerOptions {
+            self_signed: true,
+            ..Default::default()
+        };
+        let acceptor = build_acceptor(&options, "127.0.0.1").unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut tls = acceptor.accept(stream).await.unwrap();
+            tls.write_all(b"hello").await.unwrap();
+            tls.shutdown().await.ok();
+        });
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let options = TlsClientOptions {
+            server_name: Some("localhost".to_string()),
+            accept_invalid_certs: true,
+            ..Default::default()
+        };
+        let (mut tls, info) = connect(stream, "127.0.0.1", &options).await.unwrap();
+        let mut buf = [0u8; 5];
+        tls.read_exact(&mut buf).await.unwrap();
+        assert_eq!(&buf, b"hello");
+        assert_eq!(info.peer_certificates.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_require_client_cert_rejects_anonymous_client() {
+        let client = rcgen::generate_simple_self_signed(vec!["client".to_string()]).unwrap();
+        let ca_path = std::env::temp_dir().join(format!("tls-client-ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&ca_path, client.cert.pem()).unwrap();
+
+        let options = TlsServerOptions {
+            self_signed: true,
+            client_ca_path: Some(ca_path.to_string_lossy().into_owned()),
+            require_client_cert: true,
+            ..Default::default()
+        };
+        let acceptor = build_acceptor(&options, "localhost");
+        std::fs::remove_file(&ca_path).ok();
+        let acceptor = acceptor.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            acceptor.accept(stream).await.is_ok()
+        });
+
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let options = TlsClientOptions {
+            server_name: Some("localhost".to_string()),
+            accept_invalid_certs: true,
+            ..Default::default()
+        };
+        // TLS 1.3ではクライアント側のハンドシェイク完了後にサーバーが拒否する
+        let _ = connect(stream, "127.0.0.1", &options).await;
+        assert!(!server.await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_accepted_session_reports_client_subject() {
+        let client = rcgen::generate_simple_self_signed(vec!["client".to_string()]).unwrap();
+        let ca_path = std::env::temp_dir().join(format!("tls-client-ca-{}.pem", uuid::Uuid::new_v4()));
+        std::fs::write(&ca_path, client.cert.pem()).unwrap();
+
+        let options = TlsServerOptions {
+            self_signed: true,
+            client_ca_path: Some(ca_path.to_string_lossy().into_owned()),
+            require_client_cert: true,
+            ..Default::default()
+        };
+        let acceptor = build_acceptor(&options, "localhost");
+        std::fs::remove_file(&ca_path).ok();
+        let acceptor = acceptor.unwrap();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut tls = acceptor.accept(stream).await.unwrap();
+            let info = accepted_session_info(tls.get_ref().1);
+            tls.write_all(b"ok").await.unwrap();
+            info
+        });
+
+        let key = PrivateKeyDer::Pkcs8(client.key_pair.serialize_der().into());
+        let config = ClientConfig::builder_with_provider(crypto_provider())
+            .with_safe_default_protocol_versions()
+            .unwrap()
+            .dangerous()
+            .with_custom_certificate_verifier(Arc::new(AcceptAnyServerCert(crypto_provider())))
+            .with_client_auth_cert(vec![client.cert.der().clone()], key)
+            .unwrap();
+        let stream = TcpStream::connect(("127.0.0.1", port)).await.unwrap();
+        let mut tls = TlsConnector::from(Arc::new(config))
+            .connect(ServerName::try_from("localhost").unwrap(), stream)
+            .await
+            .unwrap();
+        let mut buf = [0u8; 2];
+        tls.read_exact(&mut buf).await.unwrap();
+
+        let info = server.await.unwrap();
+        assert_eq!(info.server_name, "localhost");
+        assert!(info.peer_subject.unwrap().contains("rcgen"));
+    }
+
+    #[test]
+    fn test_server_options_require_certificate_source() {
+        assert!(build_acceptor(&TlsServerOptions::default(), "localhost").is_err());
+
+        let options = TlsServerOptions {
+            self_signed: true,
+            require_client_cert: true,
+            ..Default::default()
+        };
+        assert!(build_acceptor(&options, "localhost").is_err());
+    }
+}
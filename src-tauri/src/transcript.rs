@@ -0,0 +1,389 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::net::SocketAddr;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::archive;
+use crate::message_store::RetentionPolicy;
+use crate::session_log;
+use crate::tcp::{get_connection, next_message_seq, running_server_address, server_id_or_default, TcpError};
+
+/// 記録したフレームの向き
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Direction {
+    /// 送信
+    Tx,
+    /// 受信
+    Rx,
+    /// 接続状態の変化などアプリ側の記録
+    System,
+}
+
+/// 送受信の記録（1フレームまたは1イベント）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// 記録順の通し番号（単調増加）
+    pub seq: u64,
+    pub direction: Direction,
+    pub timestamp: String,
+    /// フレームのバイト数（systemは0）
+    pub bytes: usize,
+    /// 送受信データを文字エンコーディングでデコードしたもの、またはsystemの説明
+    pub text: String,
+    /// フレームの生バイト列（JSONでは`base64`にBase64表記で出力する）
+    #[serde(rename = "base64", with = "base64_bytes")]
+    pub raw: Vec<u8>,
+    /// 区切り・長さ・エスケープを含む回線上のバイト列（生バイト列と同じ場合はNone）
+    #[serde(skip)]
+    pub wire: Option<Vec<u8>>,
+    /// サーバーの記録の場合の相手クライアント
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub client_id: Option<String>,
+    /// 相手のアドレス
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub peer: Option<String>,
+}
+
+impl TranscriptEntry {
+    /// 回線上で送受信したバイト列
+    pub(crate) fn wire_bytes(&self) -> &[u8] {
+        self.wire.as_deref().unwrap_or(&self.raw)
+    }
+
+    fn stored_len(&self) -> usize {
+        self.raw.len() + self.wire.as_ref().map_or(0, Vec::len)
+    }
+}
+
+/// バイト列をBase64文字列としてシリアライズする
+mod base64_bytes {
+    use base64::prelude::{Engine as _, BASE64_STANDARD};
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&BASE64_STANDARD.encode(bytes))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        BASE64_STANDARD.decode(text).map_err(serde::de::Error::custom)
+    }
+}
+
+/// 記録の単位（接続またはサーバー）
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub(crate) enum TranscriptSource {
+    Connection(String),
+    Server(String),
+}
+
+/// 送受信を記録する先
+#[derive(Debug, Clone)]
+pub(crate) struct TranscriptTarget {
+    pub(crate) source: TranscriptSource,
+    pub(crate) client_id: Option<String>,
+    pub(crate) peer: Option<String>,
+}
+
+impl TranscriptTarget {
+    pub(crate) fn connection(connection_id: &str, peer: Option<String>) -> Self {
+        Self {
+            source: TranscriptSource::Connection(connection_id.to_string()),
+            client_id: None,
+            peer,
+        }
+    }
+
+    pub(crate) fn server(server_id: &str) -> Self {
+        Self {
+            source: TranscriptSource::Server(server_id.to_string()),
+            client_id: None,
+            peer: None,
+        }
+    }
+
+    pub(crate) fn server_client(server_id: &str, client_id: &str, peer: &str) -> Self {
+        Self {
+            source: TranscriptSource::Server(server_id.to_string()),
+            client_id: Some(client_id.to_string()),
+            peer: Some(peer.to_string()),
+        }
+    }
+}
+
+/// TCP通信の両端のアドレス
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Endpoints {
+    pub(crate) local: SocketAddr,
+    pub(crate) remote: SocketAddr,
+    /// こちらから接続したかどうか（サーバーが受け付けた接続はfalse）
+    pub(crate) initiated_locally: bool,
+}
+
+/// 接続時に記録した両端のアドレス（after_seqより後の記録に適用する）
+#[derive(Debug, Clone)]
+pub(crate) struct EndpointSpan {
+    pub(crate) after_seq: u64,
+    pub(crate) client_id: Option<String>,
+    pub(crate) endpoints: Endpoints,
+}
+
+struct StoredEntry {
+    recorded_at: Instant,
+    entry: TranscriptEntry,
+}
+
+/// 接続・サーバーごとの記録（保持ポリシーを超えた古いものから破棄する）
+#[derive(Default)]
+struct Transcript {
+    policy: RetentionPolicy,
+    entries: VecDeque<StoredEntry>,
+    bytes: usize,
+    /// 再接続のたびに追加する
+    endpoints: Vec<EndpointSpan>,
+}
+
+impl Transcript {
+    fn push(&mut self, entry: TranscriptEntry) {
+        let now = Instant::now();
+        self.bytes += entry.stored_len();
+        self.entries.push_back(StoredEntry { recorded_at: now, entry });
+        self.evict(now);
+    }
+
+    fn set_policy(&mut self, policy: RetentionPolicy) {
+        self.policy = policy;
+        self.evict(Instant::now());
+    }
+
+    fn evict(&mut self, now: Instant) {
+        while let Some(oldest) = self.entries.front() {
+            let age = now.duration_since(oldest.recorded_at);
+            if !self.policy.exceeded(self.entries.len(), self.bytes, age) {
+                break;
+            }
+            if let Some(evicted) = self.entries.pop_front() {
+                self.bytes -= evicted.entry.stored_len();
+            }
+        }
+    }
+
+    fn entries(&mut self) -> Vec<TranscriptEntry> {
+        self.evict(Instant::now());
+        self.entries.iter().map(|stored| stored.entry.clone()).collect()
+    }
+}
+
+// 接続・サーバーごとの記録（イベント発行と同じ同期処理の中から記録できるようstdのMutexを使う）
+static TRANSCRIPTS: std::sync::OnceLock<Mutex<HashMap<TranscriptSource, Transcript>>> = std::sync::OnceLock::new();
+
+fn transcripts() -> &'static Mutex<HashMap<TranscriptSource, Transcript>> {
+    TRANSCRIPTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn push(source: &TranscriptSource, mut entry: TranscriptEntry) {
+    let mut transcripts = transcripts().lock().unwrap();
+    // 記録順と通し番号の順序を一致させるためロック中に採番する
+    entry.seq = next_message_seq();
+    // ログの行の順序も通し番号と一致させる
+    session_log::write(source, &entry);
+    archive::record(source, &entry);
+    transcripts.entry(source.clone()).or_default().push(entry);
+}
+
+/// 記録の保持ポリシーを設定する（受信メッセージと同じポリシーを使う）
+pub(crate) fn set_retention(source: &TranscriptSource, policy: RetentionPolicy) {
+    transcripts().lock().unwrap().entry(source.clone()).or_default().set_policy(policy);
+}
+
+/// TCP接続の両端のアドレスを記録（以降の送受信に適用する）
+pub(crate) fn record_endpoints(target: &TranscriptTarget, endpoints: Endpoints) {
+    let mut transcripts = transcripts().lock().unwrap();
+    let transcript = transcripts.entry(target.source.clone()).or_default();
+    // 通し番号はロック中に採番するため、以降の記録はすべてこれより大きい
+    let after_seq = transcript.entries.back().map_or(0, |stored| stored.entry.seq);
+    transcript.endpoints.push(EndpointSpan {
+        after_seq,
+        client_id: target.client_id.clone(),
+        endpoints,
+    });
+}
+
+/// 送受信したフレームを記録（`wire`は回線上のバイト列）
+pub(crate) fn record_frame(
+    target: &TranscriptTarget,
+    direction: Direction,
+    raw: &[u8],
+    wire: &[u8],
+    text: String,
+    timestamp: String,
+) {
+    push(
+        &target.source,
+        TranscriptEntry {
+            seq: 0,
+            direction,
+            timestamp,
+            bytes: raw.len(),
+            text,
+            raw: raw.to_vec(),
+            wire: (wire != raw).then(|| wire.to_vec()),
+            client_id: target.client_id.clone(),
+            peer: target.peer.clone(),
+        },
+    );
+}
+
+/// 接続状態の変化などを記録
+pub(crate) fn record_system(target: &TranscriptTarget, text: String) {
+    push(
+        &target.source,
+        TranscriptEntry {
+            seq: 0,
+            direction: Direction::System,
+            timestamp: Utc::now().to_rfc3339(),
+            bytes: 0,
+            text,
+            raw: Vec::new(),
+            wire: None,
+            client_id: target.client_id.clone(),
+            peer: target.peer.clone(),
+        },
+    );
+}
+
+/// 記録を破棄する（接続の確立に失敗したとき・サーバーの削除時）
+pub(crate) fn forget(source: &TranscriptSource) {
+    transcripts().lock().unwrap().remove(source);
+}
+
+fn remove(source: &TranscriptSource) -> Option<()> {
+    transcripts().lock().unwrap().remove(source).map(|_| ())
+}
+
+pub(crate) fn contains(source: &TranscriptSource) -> bool {
+    transcripts().lock().unwrap().contains_key(source)
+}
+
+pub(crate) fn entries(source: &TranscriptSource) -> Vec<TranscriptEntry> {
+    transcripts()
+        .lock()
+        .unwrap()
+        .get_mut(source)
+        .map(Transcript::entries)
+        .unwrap_or_default()
+}
+
+/// 記録と両端のアドレスを取得（記録がない場合はNone）
+pub(crate) fn snapshot(source: &TranscriptSource) -> Option<(Vec<TranscriptEntry>, Vec<EndpointSpan>)> {
+    transcripts()
+        .lock()
+        .unwrap()
+        .get_mut(source)
+        .map(|transcript| (transcript.entries(), transcript.endpoints.clone()))
+}
+
+/// 接続の送受信記録を取得
+#[tauri::command]
+pub async fn get_connection_transcript(connection_id: String) -> Result<Vec<TranscriptEntry>, TcpError> {
+    let source = TranscriptSource::Connection(connection_id.clone());
+    if !contains(&source) {
+        return Err(TcpError::ConnectionNotFound(format!(
+            "Connection with ID {} not found",
+            connection_id
+        )));
+    }
+    Ok(entries(&source))
+}
+
+/// サーバーの送受信記録を取得（IDを省略した場合は既定のサーバー）
+#[tauri::command]
+pub async fn get_server_transcript(server_id: Option<String>) -> Result<Vec<TranscriptEntry>, TcpError> {
+    Ok(entries(&TranscriptSource::Server(server_id_or_default(server_id))))
+}
+
+/// 切断した接続の送受信記録を削除する
+#[tauri::command]
+pub async fn remove_connection_transcript(connection_id: String) -> Result<(), TcpError> {
+    // 接続中の記録は両端のアドレスが必要なため削除しない
+    if get_connection(connection_id.clone()).await.is_ok() {
+        return Err(TcpError::InvalidQuery(format!(
+            "Connection {} is still open; disconnect it first",
+            connection_id
+        )));
+    }
+    remove(&TranscriptSource::Connection(connection_id.clone()))
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("Connection with ID {} not found", connection_id)))
+}
+
+/// 停止したサーバーの送受信記録を削除する（IDを省略した場合は既定のサーバー）
+#[tauri::command]
+pub async fn remove_server_transcript(server_id: Option<String>) -> Result<(), TcpError> {
+    let server_id = server_id_or_default(server_id);
+    if running_server_address(&server_id).await.is_some() {
+        return Err(TcpError::InvalidQuery(format!(
+            "Server {} is still running; stop it first",
+            server_id
+        )));
+    }
+    remove(&TranscriptSource::Server(server_id.clone()))
+        .ok_or_else(|| TcpError::ConnectionNotFound(format!("Server with ID {} not found", server_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_entries_are_ordered_and_serialized() {
+        let target = TranscriptTarget::server_client("transcript-test", "client-1", "127.0.0.1:5000");
+        record_system(&target, "client connected".to_string());
+        record_frame(&target, Direction::Rx, b"ping", b"ping\n", "ping".to_string(), Utc::now().to_rfc3339());
+        record_frame(&target, Direction::Tx, b"pong\r", b"pong\r", "pong\r".to_string(), Utc::now().to_rfc3339());
+
+        let entries = entries(&target.source);
+        let directions: Vec<Direction> = entries.iter().map(|e| e.direction).collect();
+        assert_eq!(directions, [Direction::System, Direction::Rx, Direction::Tx]);
+        assert!(entries.windows(2).all(|pair| pair[0].seq < pair[1].seq));
+        assert_eq!(entries[2].bytes, 5);
+        // 回線上のバイト列は生バイト列と異なる場合だけ持つ
+        assert_eq!(entries[1].wire_bytes(), b"ping\n");
+        assert_eq!(entries[2].wire, None);
+
+        let json = serde_json::to_value(&entries[1]).unwrap();
+        assert_eq!(json["direction"], "rx");
+        assert_eq!(json["base64"], "cGluZw==");
+        assert_eq!(json["client_id"], "client-1");
+        assert!(json.get("raw").is_none());
+
+        let decoded: TranscriptEntry = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.raw, b"ping");
+
+        forget(&target.source);
+        assert!(super::entries(&target.source).is_empty());
+    }
+
+    #[test]
+    fn test_retention_evicts_oldest_entries() {
+        let target = TranscriptTarget::connection("transcript-retention", None);
+        set_retention(
+            &target.source,
+            RetentionPolicy {
+                max_messages: Some(10),
+                max_bytes: Some(6),
+                max_age_ms: None,
+            },
+        );
+        for text in ["one", "two", "three"] {
+            record_frame(&target, Direction::Tx, text.as_bytes(), text.as_bytes(), text.to_string(), Utc::now().to_rfc3339());
+        }
+
+        // バイト数の上限を超えた古い記録から破棄される
+        let texts: Vec<String> = entries(&target.source).into_iter().map(|entry| entry.text).collect();
+        assert_eq!(texts, ["three"]);
+        forget(&target.source);
+    }
+}
@@ -0,0 +1,412 @@
+use serde::{Deserialize, Serialize};
+use socket2::{Domain, Protocol, Socket, Type};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::Arc;
+use tokio::net::{lookup_host, UdpSocket};
+use tokio::sync::Mutex;
+use uuid::Uuid;
+
+use crate::framing::{FrameCodec, Framing};
+use crate::message_store::{MessageStore, RetentionPolicy, SharedMessageStore};
+use crate::tcp::{
+    deliver_connection_message, register_connection, ConnectionData, ConnectionWriter, MulticastMembership,
+    TcpConnection, TcpConnectionResult, TcpError, TcpReceivedMessage, Transport,
+};
+use crate::text_encoding::TextEncoding;
+use crate::transcript::{self, TranscriptSource};
+
+/// UDPで1回に受信できる最大サイズ
+const MAX_DATAGRAM_LEN: usize = 65535;
+
+fn default_udp_framing() -> Framing {
+    Framing::Raw
+}
+
+/// UDPセッションの設定
+///
+/// コネクションがないため接続・アイドル等のタイムアウトは設定できない
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UdpConnectionRequest {
+    /// 受信用にバインドするローカルアドレス（省略時は全インターフェース）
+    #[serde(default)]
+    pub local_host: Option<String>,
+    /// 受信用にバインドするローカルポート（0の場合は自動割り当て）
+    #[serde(default)]
+    pub local_port: u16,
+    /// ユニキャストの送信先（省略時は最後に受信した相手へ返信）
+    #[serde(default)]
+    pub remote_host: Option<String>,
+    #[serde(default)]
+    pub remote_port: Option<u16>,
+    /// データグラム内の区切り方式（既定ではデータグラム全体を1メッセージとする）
+    #[serde(default = "default_udp_framing")]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// マルチキャストグループへの参加設定（タイムアウトはUDPセッションと同様に未対応）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MulticastJoinRequest {
+    /// 参加するマルチキャストグループ（IPv4またはIPv6）
+    pub group: String,
+    pub port: u16,
+    /// 使用するインターフェース（IPv4はアドレス、IPv6はインターフェース番号。省略時はOSに任せる）
+    #[serde(default)]
+    pub interface: Option<String>,
+    /// 送信時のTTL（IPv6ではホップ数）
+    #[serde(default)]
+    pub ttl: Option<u32>,
+    /// 自分が送信したデータグラムも受信する
+    #[serde(default = "default_multicast_loop")]
+    pub multicast_loop: bool,
+    #[serde(default = "default_udp_framing")]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+fn default_multicast_loop() -> bool {
+    true
+}
+
+async fn resolve(host: &str, port: u16) -> Result<SocketAddr, TcpError> {
+    lookup_host((host, port))
+        .await
+        .map_err(|e| TcpError::InvalidAddress(format!("Failed to resolve {}:{}: {}", host, port, e)))?
+        .next()
+        .ok_or_else(|| TcpError::InvalidAddress(format!("No address found for {}:{}", host, port)))
+}
+
+/// UDPの送受信セッションを開始し、TCP接続と同じレジストリに登録する
+#[tauri::command]
+pub async fn connect_udp(request: UdpConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    request.framing.validate(request.encoding).map_err(TcpError::InvalidFraming)?;
+
+    // 送信先の妥当性をチェック
+    let target = match (&request.remote_host, request.remote_port) {
+        (Some(host), Some(port)) if !host.is_empty() && port != 0 => Some(resolve(host, port).await?),
+        (None, None) => None,
+        _ => {
+            return Err(TcpError::InvalidAddress(
+                "Remote host and port must be specified together".to_string(),
+            ));
+        }
+    };
+
+    // 送信先のアドレスファミリーに合わせてバインド先を決める
+    let local_host = match &request.local_host {
+        Some(host) if !host.is_empty() => host.clone(),
+        _ if target.is_some_and(|addr| addr.is_ipv6()) => "::".to_string(),
+        _ => "0.0.0.0".to_string(),
+    };
+    let local_addr = resolve(&local_host, request.local_port).await?;
+    let socket = UdpSocket::bind(local_addr).await.map_err(|e| {
+        TcpError::ConnectionFailed(format!("Failed to bind UDP socket to {}: {}", local_addr, e))
+    })?;
+    let bound_addr = socket.local_addr().map_err(|e| TcpError::ConnectionFailed(e.to_string()))?;
+    let socket = Arc::new(socket);
+
+    let connection_id = Uuid::new_v4().to_string();
+    // 送信先がない場合はバインドしたローカルアドレスを表示に使う
+    let display_addr = target.unwrap_or(bound_addr);
+    let connection = TcpConnection::new(connection_id.clone(), display_addr.ip().to_string(), display_addr.port(), Transport::Udp);
+
+    let last_peer = Arc::new(std::sync::Mutex::new(None));
+    transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+    let messages = MessageStore::shared(request.retention.clone());
+
+    // 受信処理（登録時にタスクとして開始する）
+    let receiver = handle_udp_receiver(
+        Arc::clone(&socket),
+        connection_id.clone(),
+        request.framing.clone(),
+        request.encoding,
+        Arc::clone(&messages),
+        Arc::clone(&last_peer),
+    );
+
+    let connection_data = ConnectionData {
+        info: connection.clone(),
+        writer: Arc::new(Mutex::new(ConnectionWriter::Udp {
+            socket,
+            target,
+            last_peer,
+            membership: None,
+        })),
+        framing: request.framing,
+        encoding: request.encoding,
+        messages,
+        receiver_handle: None,
+        tls_info: None,
+    };
+    register_connection(connection_data, receiver).await;
+
+    log::info!("UDP session bound to {}", bound_addr);
+    Ok(TcpConnectionResult {
+        success: true,
+        connection: Some(connection),
+        error: None,
+    })
+}
+
+/// マルチキャストグループ用のソケットを作成して参加する
+fn open_multicast_socket(request: &MulticastJoinRequest) -> Result<(UdpSocket, MulticastMembership), TcpError> {
+    let group: IpAddr = request
+        .group
+        .parse()
+        .map_err(|_| TcpError::InvalidAddress(format!("Invalid multicast group: {}", request.group)))?;
+    if !group.is_multicast() {
+        return Err(TcpError::InvalidAddress(format!("{} is not a multicast address", group)));
+    }
+    let join_error = |e: std::io::Error| TcpError::ConnectionFailed(format!("Failed to join {}: {}", group, e));
+
+    let domain = if group.is_ipv4() { Domain::IPV4 } else { Domain::IPV6 };
+    let socket = Socket::new(domain, Type::DGRAM, Some(Protocol::UDP)).map_err(join_error)?;
+    // 同じグループを複数のセッションやアプリで受信できるようにする
+    socket.set_reuse_address(true).map_err(join_error)?;
+    #[cfg(unix)]
+    socket.set_reuse_port(true).map_err(join_error)?;
+
+    let membership = match group {
+        IpAddr::V4(group) => {
+            let interface = match &request.interface {
+                Some(interface) => interface.parse::<Ipv4Addr>().map_err(|_| {
+                    TcpError::InvalidAddress(format!("Invalid IPv4 interface address: {}", interface))
+                })?,
+                None => Ipv4Addr::UNSPECIFIED,
+            };
+            socket
+                .bind(&SocketAddr::from((Ipv4Addr::UNSPECIFIED, request.port)).into())
+                .map_err(join_error)?;
+            socket.join_multicast_v4(&group, &interface).map_err(join_error)?;
+            socket.set_multicast_if_v4(&interface).map_err(join_error)?;
+            socket.set_multicast_loop_v4(request.multicast_loop).map_err(join_error)?;
+            if let Some(ttl) = request.ttl {
+                socket.set_multicast_ttl_v4(ttl).map_err(join_error)?;
+            }
+            MulticastMembership::V4 { group, interface }
+        }
+        IpAddr::V6(group) => {
+            let interface = match &request.interface {
+                Some(interface) => interface.parse::<u32>().map_err(|_| {
+                    TcpError::InvalidAddress(format!("Invalid IPv6 interface index: {}", interface))
+                })?,
+                None => 0,
+            };
+            socket.set_only_v6(true).map_err(join_error)?;
+            socket
+                .bind(&SocketAddr::from((Ipv6Addr::UNSPECIFIED, request.port)).into())
+                .map_err(join_error)?;
+            socket.join_multicast_v6(&group, interface).map_err(join_error)?;
+            socket.set_multicast_if_v6(interface).map_err(join_error)?;
+            socket.set_multicast_loop_v6(request.multicast_loop).map_err(join_error)?;
+            if let Some(hops) = request.ttl {
+                socket.set_multicast_hops_v6(hops).map_err(join_error)?;
+            }
+            MulticastMembership::V6 { group, interface }
+        }
+    };
+
+    socket.set_nonblocking(true).map_err(join_error)?;
+    let socket = UdpSocket::from_std(socket.into()).map_err(join_error)?;
+    Ok((socket, membership))
+}
+
+/// マルチキャストグループに参加し、受信したデータグラムをイベントとして通知する
+///
+/// 送信は`send_tcp_message_on_connection`でグループ宛てに行い、`disconnect_tcp`でグループから離脱する
+#[tauri::command]
+pub async fn join_multicast(request: MulticastJoinRequest) -> Result<TcpConnectionResult, TcpError> {
+    request.framing.validate(request.encoding).map_err(TcpError::InvalidFraming)?;
+    if request.port == 0 {
+        return Err(TcpError::InvalidAddress("Port must be valid".to_string()));
+    }
+
+    let (socket, membership) = open_multicast_socket(&request)?;
+    let socket = Arc::new(socket);
+    let group_addr = match membership {
+        MulticastMembership::V4 { group, .. } => SocketAddr::from((group, request.port)),
+        MulticastMembership::V6 { group, interface } => {
+            std::net::SocketAddrV6::new(group, request.port, 0, interface).into()
+        }
+    };
+
+    let connection_id = Uuid::new_v4().to_string();
+    let connection = TcpConnection::new(connection_id.clone(), group_addr.ip().to_string(), request.port, Transport::Multicast);
+
+    let last_peer = Arc::new(std::sync::Mutex::new(None));
+    transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+    let messages = MessageStore::shared(request.retention.clone());
+    let receiver = handle_udp_receiver(
+        Arc::clone(&socket),
+        connection_id.clone(),
+        request.framing.clone(),
+        request.encoding,
+        Arc::clone(&messages),
+        Arc::clone(&last_peer),
+    );
+
+    let connection_data = ConnectionData {
+        info: connection.clone(),
+        writer: Arc::new(Mutex::new(ConnectionWriter::Udp {
+            socket,
+            target: Some(group_addr),
+            last_peer,
+            membership: Some(membership),
+        })),
+        framing: request.framing,
+        encoding: request.encoding,
+        messages,
+        receiver_handle: None,
+        tls_info: None,
+    };
+    register_connection(connection_data, receiver).await;
+
+    log::info!("Joined multicast group {}", group_addr);
+    Ok(TcpConnectionResult {
+        success: true,
+        connection: Some(connection),
+        error: None,
+    })
+}
+
+async fn handle_udp_receiver(
+    socket: Arc<UdpSocket>,
+    connection_id: String,
+    framing: Framing,
+    encoding: TextEncoding,
+    messages: SharedMessageStore,
+    last_peer: Arc<std::sync::Mutex<Option<SocketAddr>>>,
+) -> io::Result<()> {
+    let mut codec = FrameCodec::new(framing, encoding);
+    let mut buf = vec![0u8; MAX_DATAGRAM_LEN];
+
+    loop {
+        let (len, peer) = match socket.recv_from(&mut buf).await {
+            Ok(received) => received,
+            // ICMP Port Unreachableは受信エラーとして通知されるが、セッションは継続する
+            Err(e) if matches!(e.kind(), io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionRefused) => {
+                log::warn!("Error receiving on UDP connection {}: {}", connection_id, e);
+                continue;
+            }
+            // それ以外のエラーは繰り返し発生するため受信を終了する
+            Err(e) => return Err(e),
+        };
+        *last_peer.lock().unwrap() = Some(peer);
+
+        let frames = match codec.split_datagram(&buf[..len]) {
+            Ok(frames) => frames,
+            Err(e) => {
+                log::warn!("Invalid datagram from {} on connection {}: {}", peer, connection_id, e);
+                continue;
+            }
+        };
+        for frame in frames {
+            let received_msg = TcpReceivedMessage::from_frame(frame.payload, peer.to_string(), encoding);
+            deliver_connection_message(&connection_id, &messages, received_msg, Some(frame.wire)).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::PayloadFormat;
+    use crate::tcp::{disconnect_tcp, send_tcp_message_on_connection, TcpMessageOnConnection};
+    use crate::test_support::{free_port, wait_for_messages};
+
+    #[tokio::test]
+    async fn test_udp_session_send_and_receive() {
+        let peer = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let peer_addr = peer.local_addr().unwrap();
+
+        let request = UdpConnectionRequest {
+            local_host: Some("127.0.0.1".to_string()),
+            local_port: 0,
+            remote_host: Some("127.0.0.1".to_string()),
+            remote_port: Some(peer_addr.port()),
+            framing: Framing::Raw,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        };
+        let connection = connect_udp(request).await.unwrap().connection.unwrap();
+        assert_eq!(connection.transport, Transport::Udp);
+
+        let result = send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "ping".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert!(result.success);
+
+        let mut buf = [0u8; 16];
+        let (len, session_addr) = peer.recv_from(&mut buf).await.unwrap();
+        assert_eq!(&buf[..len], b"ping");
+
+        peer.send_to(b"pong", session_addr).await.unwrap();
+        let messages = wait_for_messages(&connection.id, 1).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "pong");
+        assert_eq!(messages[0].client_addr, peer_addr.to_string());
+
+        disconnect_tcp(connection.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multicast_loopback() {
+        let request = MulticastJoinRequest {
+            group: "239.255.42.99".to_string(),
+            port: free_port(),
+            interface: Some("127.0.0.1".to_string()),
+            ttl: Some(0),
+            multicast_loop: true,
+            framing: Framing::Raw,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        };
+        let connection = join_multicast(request).await.unwrap().connection.unwrap();
+        assert_eq!(connection.transport, Transport::Multicast);
+        assert_eq!(connection.host, "239.255.42.99");
+
+        // ループバックが有効なので自分の送信した内容を受信できる
+        let result = send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "status".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert!(result.success, "{:?}", result.error);
+
+        let messages = wait_for_messages(&connection.id, 1).await;
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "status");
+
+        disconnect_tcp(connection.id).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_multicast_rejects_unicast_group() {
+        let request = MulticastJoinRequest {
+            group: "127.0.0.1".to_string(),
+            port: free_port(),
+            interface: None,
+            ttl: None,
+            multicast_loop: true,
+            framing: Framing::Raw,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        };
+        assert!(matches!(join_multicast(request).await, Err(TcpError::InvalidAddress(_))));
+    }
+}
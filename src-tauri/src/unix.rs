@@ -0,0 +1,416 @@
+use serde::{Deserialize, Serialize};
+
+use crate::framing::Framing;
+use crate::message_store::RetentionPolicy;
+use crate::tcp::{TcpConnectionResult, TcpError};
+use crate::text_encoding::TextEncoding;
+
+/// Unixドメインソケットへの接続設定
+///
+/// TCP接続と異なり、受信・送信・アイドルのタイムアウトには未対応
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnixConnectionRequest {
+    /// 接続先のソケットファイルのパス
+    pub path: String,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// Unixドメインソケットのサーバー設定（TCPサーバーと同じくクライアントのタイムアウトはない）
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UnixServerConfig {
+    /// サーバーID（省略時は"default"。TCPサーバーと同じレジストリを使う）
+    #[serde(default)]
+    pub id: Option<String>,
+    /// 待ち受けるソケットファイルのパス
+    pub path: String,
+    #[serde(default)]
+    pub framing: Framing,
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// Unixドメインソケットに接続し、TCP接続と同じレジストリに登録する
+#[tauri::command]
+pub async fn connect_unix(request: UnixConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    #[cfg(unix)]
+    {
+        imp::connect_unix(request).await
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = request;
+        Err(unsupported())
+    }
+}
+
+/// Unixドメインソケットで待ち受けるサーバーを開始する
+///
+/// TCPサーバーと同じレジストリにIDごとに登録し、同じIDで起動中のサーバー（TCPを含む）だけを置き換える
+#[tauri::command]
+pub async fn start_unix_server(config: UnixServerConfig) -> Result<String, TcpError> {
+    #[cfg(unix)]
+    {
+        imp::start_unix_server(config).await
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = config;
+        Err(unsupported())
+    }
+}
+
+#[cfg(not(unix))]
+fn unsupported() -> TcpError {
+    TcpError::ConnectionFailed("Unix domain sockets are not supported on this platform".to_string())
+}
+
+#[cfg(unix)]
+mod imp {
+    use std::io;
+    use std::os::unix::fs::{FileTypeExt, MetadataExt};
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
+    use tokio::net::{UnixListener, UnixStream};
+    use tokio::sync::Mutex;
+    use uuid::Uuid;
+
+    use super::{UnixConnectionRequest, UnixServerConfig};
+    use crate::message_store::MessageStore;
+    use crate::tcp::{
+        accept_server_client, handle_connection_receiver, prepare_server, register_connection, register_server,
+        running_server_address, server_id_or_default, stop_server,
+        BoxedReader, BoxedWriter, ConnectionData, ServerClientInfo, ConnectionWriter, TcpConnection, TcpConnectionResult, TcpError,
+        TcpServerStopOptions, Transport,
+    };
+    use crate::transcript::{self, TranscriptSource};
+
+    /// 接続相手のプロセス情報を表示用の文字列にする
+    fn describe_peer(stream: &UnixStream) -> String {
+        match stream.peer_cred() {
+            Ok(cred) => match cred.pid() {
+                Some(pid) => format!("unix:uid={},gid={},pid={}", cred.uid(), cred.gid(), pid),
+                None => format!("unix:uid={},gid={}", cred.uid(), cred.gid()),
+            },
+            Err(e) => {
+                log::warn!("Failed to read peer credentials: {}", e);
+                "unix:unknown".to_string()
+            }
+        }
+    }
+
+    /// 受け付けたクライアントの情報に相手プロセスの資格情報を付ける
+    fn client_info(server_id: &str, stream: &UnixStream) -> ServerClientInfo {
+        let mut info = ServerClientInfo::new(server_id, &Uuid::new_v4().to_string(), describe_peer(stream));
+        if let Ok(cred) = stream.peer_cred() {
+            info.uid = Some(cred.uid());
+            info.gid = Some(cred.gid());
+            info.pid = cred.pid();
+        }
+        info
+    }
+
+    /// サーバーのタスクが終了したときに、作成したソケットファイルを削除する
+    ///
+    /// 同じパスで再作成されたソケットを消さないよう、作成時と同じファイルの場合だけ削除する
+    struct SocketFileGuard {
+        path: PathBuf,
+        dev: u64,
+        ino: u64,
+    }
+
+    impl SocketFileGuard {
+        fn new(path: &Path) -> Option<Self> {
+            let metadata = std::fs::symlink_metadata(path).ok()?;
+            Some(Self {
+                path: path.to_path_buf(),
+                dev: metadata.dev(),
+                ino: metadata.ino(),
+            })
+        }
+    }
+
+    impl Drop for SocketFileGuard {
+        fn drop(&mut self) {
+            match std::fs::symlink_metadata(&self.path) {
+                Ok(metadata) if metadata.dev() == self.dev && metadata.ino() == self.ino => {
+                    if let Err(e) = std::fs::remove_file(&self.path) {
+                        log::warn!("Failed to remove socket {}: {}", self.path.display(), e);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub(super) async fn connect_unix(request: UnixConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+        if request.path.is_empty() {
+            return Err(TcpError::InvalidAddress("Socket path must be valid".to_string()));
+        }
+        request.framing.validate(request.encoding).map_err(TcpError::InvalidFraming)?;
+
+        let stream = UnixStream::connect(&request.path).await.map_err(|e| {
+            TcpError::ConnectionFailed(format!("Failed to connect to {}: {}", request.path, e))
+        })?;
+        log::info!("Connected to {} ({})", request.path, describe_peer(&stream));
+
+        let connection_id = Uuid::new_v4().to_string();
+        let connection = TcpConnection::new(connection_id.clone(), request.path.clone(), 0, Transport::Unix);
+
+        let (reader, writer) = stream.into_split();
+        let reader: BoxedReader = Box::new(reader);
+        let writer: BoxedWriter = Box::new(writer);
+        transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+        let messages = MessageStore::shared(request.retention.clone());
+
+        // 受信処理（登録時にタスクとして開始する）
+        let receiver = handle_connection_receiver(
+            reader,
+            connection_id.clone(),
+            request.framing.clone(),
+            request.encoding,
+            Arc::clone(&messages),
+        );
+
+        let connection_data = ConnectionData {
+            info: connection.clone(),
+            writer: Arc::new(Mutex::new(ConnectionWriter::Stream(writer))),
+            framing: request.framing,
+            encoding: request.encoding,
+            messages,
+            receiver_handle: None,
+            tls_info: None,
+        };
+        register_connection(connection_data, receiver).await;
+
+        Ok(TcpConnectionResult {
+            success: true,
+            connection: Some(connection),
+            error: None,
+        })
+    }
+
+    /// 既存のソケットファイルに接続してみて、誰も待ち受けていない場合だけ削除する
+    ///
+    /// 他のプロセスや別のIDのサーバーが使用中の場合はエラーにする（同じIDのサーバーの再起動は止めてから削除する）
+    async fn remove_stale_socket(path: &Path, server_id: &str) -> Result<(), TcpError> {
+        let display = path.display();
+        match UnixStream::connect(path).await {
+            Err(e) if e.kind() == io::ErrorKind::ConnectionRefused => {}
+            Ok(_) if running_server_address(server_id).await.as_deref() == path.to_str() => {
+                // 中断モードではメッセージを送らないため失敗しない
+                stop_server(server_id, &TcpServerStopOptions::default()).await.ok();
+            }
+            Ok(_) => {
+                return Err(TcpError::ServerStartFailed(format!(
+                    "Failed to bind to {}: {}",
+                    display,
+                    io::Error::from(io::ErrorKind::AddrInUse)
+                )));
+            }
+            Err(e) => {
+                return Err(TcpError::ServerStartFailed(format!("Failed to check socket {}: {}", display, e)));
+            }
+        }
+        // 停止したサーバーのソケットファイルは既に削除されている場合がある
+        match std::fs::remove_file(path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(TcpError::ServerStartFailed(format!(
+                "Failed to remove stale socket {}: {}",
+                display, e
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    pub(super) async fn start_unix_server(config: UnixServerConfig) -> Result<String, TcpError> {
+        if config.path.is_empty() {
+            return Err(TcpError::InvalidAddress("Socket path must be valid".to_string()));
+        }
+        config.framing.validate(config.encoding).map_err(TcpError::InvalidFraming)?;
+
+        // 前回の起動で残ったソケットファイルを削除（通常のファイルや使用中のソケットは消さない）
+        let server_id = server_id_or_default(config.id);
+        let path = Path::new(&config.path);
+        if let Ok(metadata) = std::fs::symlink_metadata(path) {
+            if !metadata.file_type().is_socket() {
+                return Err(TcpError::ServerStartFailed(format!(
+                    "{} exists and is not a socket",
+                    config.path
+                )));
+            }
+            remove_stale_socket(path, &server_id).await?;
+        }
+
+        // 待ち受けに失敗しても同じIDの既存サーバーが止まらないよう、先にリスナーを開始する
+        let listener = UnixListener::bind(path).map_err(|e| {
+            TcpError::ServerStartFailed(format!("Failed to bind to {}: {}", config.path, e))
+        })?;
+
+        // 同じIDの既存サーバーを停止し、メッセージ履歴を新しくする
+        let context = prepare_server(&server_id, config.framing, config.encoding, config.retention).await;
+
+        let socket_file = SocketFileGuard::new(path);
+        let server_context = context.clone();
+        let server_task = tokio::spawn(async move {
+            // 停止（タスクの中断）時にソケットファイルを削除する
+            let _socket_file = socket_file;
+            loop {
+                match listener.accept().await {
+                    Ok((stream, _)) => {
+                        // Unixソケットではアドレスの代わりに相手プロセスの資格情報を使う
+                        let info = client_info(&server_context.id, &stream);
+                        log::info!("Accepted Unix socket client {}", info.client_addr);
+                        let (reader, writer) = stream.into_split();
+                        tokio::spawn(accept_server_client(
+                            server_context.clone(),
+                            info,
+                            Box::new(reader),
+                            Box::new(writer),
+                        ));
+                    }
+                    Err(e) => {
+                        log::error!("Failed to accept connection: {}", e);
+                        break;
+                    }
+                }
+            }
+        });
+        register_server(context, config.path.clone(), Transport::Unix, false, server_task).await;
+
+        Ok(format!("Unix socket server started on {}", config.path))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::payload::PayloadFormat;
+    use crate::tcp::{
+        disconnect_tcp, list_server_clients, send_tcp_message_on_connection, stop_tcp_server, TcpMessageOnConnection,
+    };
+    use crate::test_support::{wait_for_messages, wait_for_server_messages};
+    use std::path::Path;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{UnixListener, UnixStream};
+
+    fn socket_path(name: &str) -> String {
+        std::env::temp_dir()
+            .join(format!("{}-{}.sock", name, uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned()
+    }
+
+    #[tokio::test]
+    async fn test_unix_server_reports_peer_credentials() {
+        let path = socket_path("unix-server");
+        let server_id = Some(format!("unix-{}", uuid::Uuid::new_v4()));
+        start_unix_server(server_config(server_id.as_deref().unwrap(), &path)).await.unwrap();
+
+        let mut client = UnixStream::connect(&path).await.unwrap();
+        client.write_all(b"hello\n").await.unwrap();
+
+        let messages = wait_for_server_messages(server_id.as_deref().unwrap(), 1).await;
+        let clients = list_server_clients(server_id.clone()).await.unwrap();
+        stop_tcp_server(server_id, None).await.unwrap();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "hello");
+        assert!(messages[0].client_addr.contains(&format!("pid={}", std::process::id())));
+        assert_eq!(clients.len(), 1);
+        assert_eq!(clients[0].pid, Some(std::process::id() as i32));
+        assert!(clients[0].uid.is_some());
+        // 停止するとソケットファイルが削除される
+        assert!(!Path::new(&path).exists());
+    }
+
+    fn server_config(id: &str, path: &str) -> UnixServerConfig {
+        UnixServerConfig {
+            id: Some(id.to_string()),
+            path: path.to_string(),
+            framing: Framing::Line,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_live_socket_path_is_not_taken_over() {
+        let path = socket_path("unix-in-use");
+        let first = format!("unix-{}", uuid::Uuid::new_v4());
+        let second = format!("unix-{}", uuid::Uuid::new_v4());
+        start_unix_server(server_config(&first, &path)).await.unwrap();
+
+        // 別のIDのサーバーが使用中のパスでは起動できない
+        let error = start_unix_server(server_config(&second, &path)).await.unwrap_err();
+        assert!(matches!(error, TcpError::ServerStartFailed(_)));
+        let mut client = UnixStream::connect(&path).await.unwrap();
+        client.write_all(b"still here\n").await.unwrap();
+        let messages = wait_for_server_messages(&first, 1).await;
+        assert_eq!(messages[0].message, "still here");
+
+        // 同じIDの再起動は既存のサーバーを止めて待ち受け直す
+        start_unix_server(server_config(&first, &path)).await.unwrap();
+        assert!(UnixStream::connect(&path).await.is_ok());
+        stop_tcp_server(Some(first), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stale_socket_file_is_replaced() {
+        let path = socket_path("unix-stale");
+        // 待ち受けを終えてもファイルが残ったソケット
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        assert!(Path::new(&path).exists());
+
+        let server_id = format!("unix-{}", uuid::Uuid::new_v4());
+        start_unix_server(server_config(&server_id, &path)).await.unwrap();
+        assert!(UnixStream::connect(&path).await.is_ok());
+        stop_tcp_server(Some(server_id), None).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_connect_unix_roundtrip() {
+        let path = socket_path("unix-client");
+        let listener = UnixListener::bind(&path).unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0u8; 5];
+            stream.read_exact(&mut buf).await.unwrap();
+            stream.write_all(&buf).await.unwrap();
+        });
+
+        let connection = connect_unix(UnixConnectionRequest {
+            path: path.clone(),
+            framing: Framing::Line,
+            encoding: TextEncoding::Utf8,
+            retention: Default::default(),
+        })
+        .await
+        .unwrap()
+        .connection
+        .unwrap();
+
+        let result = send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "echo".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+        assert!(result.success);
+
+        let messages = wait_for_messages(&connection.id, 1).await;
+        disconnect_tcp(connection.id).await.unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].message, "echo");
+    }
+}
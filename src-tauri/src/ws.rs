@@ -0,0 +1,487 @@
+use bytes::Bytes;
+use chrono::Utc;
+use futures_util::stream::{SplitSink, SplitStream};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::io::{AsyncRead, AsyncWrite};
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::HeaderValue;
+use tokio_tungstenite::tungstenite::error::{Error as WsError, ProtocolError};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+use uuid::Uuid;
+
+use crate::framing::Framing;
+use crate::message_store::{MessageStore, RetentionPolicy, SharedMessageStore};
+use crate::tcp::{
+    deliver_connection_message, emit_event, register_connection, stream_endpoints, ConnectionData, ConnectionWriter,
+    FrameType, TcpConnection, TcpConnectionResult, TcpError, TcpReceivedMessage, Transport,
+};
+use crate::text_encoding::TextEncoding;
+use crate::tls::{self, TlsClientOptions};
+use crate::transcript::{self, TranscriptSource, TranscriptTarget};
+
+/// 平文とTLSのどちらの上でもWebSocketを扱うためのストリーム
+trait AsyncStream: AsyncRead + AsyncWrite + Send + Unpin {}
+impl<T: AsyncRead + AsyncWrite + Send + Unpin> AsyncStream for T {}
+
+type BoxedStream = Box<dyn AsyncStream>;
+
+/// WebSocket接続の設定
+///
+/// 接続・受信のタイムアウトはないため、相手の無応答はPing/Pongの計測で確認する
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebSocketConnectionRequest {
+    /// ws:// または wss:// のURL
+    pub url: String,
+    /// ハンドシェイク時に追加するヘッダー
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    /// Sec-WebSocket-Protocolで提示するサブプロトコル
+    #[serde(default)]
+    pub subprotocols: Vec<String>,
+    /// wss:// の場合のTLSオプション
+    #[serde(default)]
+    pub tls: Option<TlsClientOptions>,
+    /// 指定した間隔でPingを送信し、Pongまでの往復時間を計測する
+    #[serde(default)]
+    pub ping_interval_ms: Option<u64>,
+    /// 送信時のフレーム種別
+    #[serde(default)]
+    pub send_frame_type: FrameType,
+    /// バイナリフレームの送受信に使う文字エンコーディング（テキストフレームは常にUTF-8）
+    #[serde(default)]
+    pub encoding: TextEncoding,
+    /// 受信メッセージの保持ポリシー
+    #[serde(default)]
+    pub retention: RetentionPolicy,
+}
+
+/// Ping/Pongの計測状況
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WebSocketInfo {
+    pub url: String,
+    pub subprotocol: Option<String>,
+    pub pings_sent: u64,
+    pub pongs_received: u64,
+    pub pings_received: u64,
+    pub last_rtt_ms: Option<f64>,
+    pub last_pong_at: Option<String>,
+    pub last_ping_received_at: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PingPongKind {
+    PingSent,
+    PongReceived,
+    PingReceived,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebSocketPingPongEvent {
+    pub connection_id: String,
+    pub kind: PingPongKind,
+    pub rtt_ms: Option<f64>,
+    pub timestamp: String,
+}
+
+/// WebSocket接続の送信側
+pub(crate) struct WebSocketWriter {
+    sink: SplitSink<WebSocketStream<BoxedStream>, Message>,
+    frame_type: FrameType,
+}
+
+impl WebSocketWriter {
+    pub(crate) async fn send_frame(&mut self, frame: &[u8]) -> io::Result<()> {
+        let message = match self.frame_type {
+            FrameType::Text => {
+                let text = std::str::from_utf8(frame).map_err(|e| {
+                    io::Error::new(io::ErrorKind::InvalidData, format!("Text frames must be valid UTF-8: {}", e))
+                })?;
+                Message::text(text)
+            }
+            FrameType::Binary => Message::binary(frame.to_vec()),
+        };
+        self.send_message(message).await
+    }
+
+    async fn send_message(&mut self, message: Message) -> io::Result<()> {
+        self.sink.send(message).await.map_err(io::Error::other)
+    }
+
+    pub(crate) async fn flush(&mut self) -> io::Result<()> {
+        self.sink.flush().await.map_err(io::Error::other)
+    }
+
+    pub(crate) async fn close(&mut self) -> io::Result<()> {
+        self.sink.close().await.map_err(io::Error::other)
+    }
+}
+
+static WEBSOCKET_INFO: std::sync::OnceLock<Arc<Mutex<HashMap<String, WebSocketInfo>>>> = std::sync::OnceLock::new();
+
+fn websocket_info() -> &'static Arc<Mutex<HashMap<String, WebSocketInfo>>> {
+    WEBSOCKET_INFO.get_or_init(|| Arc::new(Mutex::new(HashMap::new())))
+}
+
+/// Pingのペイロードに埋め込む送信時刻（UNIXエポックからのマイクロ秒）
+fn now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_micros() as u64)
+        .unwrap_or_default()
+}
+
+fn emit_ping_pong(connection_id: &str, kind: PingPongKind, rtt_ms: Option<f64>) {
+    let event = WebSocketPingPongEvent {
+        connection_id: connection_id.to_string(),
+        kind,
+        rtt_ms,
+        timestamp: Utc::now().to_rfc3339(),
+    };
+    emit_event("websocket_ping_pong", &event);
+}
+
+/// WebSocketサーバーに接続し、TCP接続と同じレジストリに登録する
+#[tauri::command]
+pub async fn connect_websocket(request: WebSocketConnectionRequest) -> Result<TcpConnectionResult, TcpError> {
+    let mut handshake = request
+        .url
+        .as_str()
+        .into_client_request()
+        .map_err(|e| TcpError::InvalidAddress(format!("Invalid WebSocket URL {}: {}", request.url, e)))?;
+
+    let uri = handshake.uri().clone();
+    let secure = match uri.scheme_str() {
+        Some("ws") => false,
+        Some("wss") => true,
+        _ => {
+            return Err(TcpError::InvalidAddress(format!(
+                "WebSocket URL must start with ws:// or wss://: {}",
+                request.url
+            )));
+        }
+    };
+    let host = uri
+        .host()
+        .map(|host| host.trim_start_matches('[').trim_end_matches(']').to_string())
+        .ok_or_else(|| TcpError::InvalidAddress(format!("WebSocket URL has no host: {}", request.url)))?;
+    let port = uri.port_u16().unwrap_or(if secure { 443 } else { 80 });
+
+    // カスタムヘッダーとサブプロトコルを設定
+    for (name, value) in &request.headers {
+        let name = tokio_tungstenite::tungstenite::http::HeaderName::from_bytes(name.as_bytes())
+            .map_err(|e| TcpError::InvalidAddress(format!("Invalid header name {}: {}", name, e)))?;
+        let value = HeaderValue::from_str(value)
+            .map_err(|e| TcpError::InvalidAddress(format!("Invalid header value for {}: {}", name, e)))?;
+        handshake.headers_mut().insert(name, value);
+    }
+    if !request.subprotocols.is_empty() {
+        let value = HeaderValue::from_str(&request.subprotocols.join(", "))
+            .map_err(|e| TcpError::InvalidAddress(format!("Invalid subprotocol: {}", e)))?;
+        handshake.headers_mut().insert("Sec-WebSocket-Protocol", value);
+    }
+
+    let address = format!("{}:{}", host, port);
+    let stream = TcpStream::connect((host.as_str(), port)).await.map_err(|e| {
+        TcpError::ConnectionFailed(format!("Failed to connect to {}: {}", address, e))
+    })?;
+    // PCAPNGに書き出せるよう、ストリームをまとめる前に両端のアドレスを取得しておく
+    let endpoints = stream_endpoints(&stream, true);
+
+    let (stream, tls_info): (BoxedStream, _) = if secure {
+        let options = request.tls.clone().unwrap_or_default();
+        let (tls_stream, info) = tls::connect(stream, &host, &options)
+            .await
+            .map_err(TcpError::TlsFailed)?;
+        (Box::new(tls_stream), Some(info))
+    } else {
+        (Box::new(stream), None)
+    };
+
+    let (ws_stream, response) = tokio_tungstenite::client_async(handshake, stream)
+        .await
+        .map_err(|e| TcpError::ConnectionFailed(format!("WebSocket handshake with {} failed: {}", request.url, e)))?;
+    let subprotocol = response
+        .headers()
+        .get("Sec-WebSocket-Protocol")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+
+    let connection_id = Uuid::new_v4().to_string();
+    let connection = TcpConnection::new(connection_id.clone(), host, port, Transport::WebSocket);
+    if let Some(endpoints) = endpoints {
+        transcript::record_endpoints(&TranscriptTarget::connection(&connection_id, None), endpoints);
+    }
+
+    websocket_info().lock().await.insert(
+        connection_id.clone(),
+        WebSocketInfo {
+            url: request.url.clone(),
+            subprotocol,
+            ..Default::default()
+        },
+    );
+
+    let (sink, stream) = ws_stream.split();
+    let writer = Arc::new(Mutex::new(ConnectionWriter::WebSocket(WebSocketWriter {
+        sink,
+        frame_type: request.send_frame_type,
+    })));
+    transcript::set_retention(&TranscriptSource::Connection(connection_id.clone()), request.retention.clone());
+    let messages = MessageStore::shared(request.retention.clone());
+
+    // 受信処理（登録時にタスクとして開始する。Ping間隔が指定されていれば同じタスク内で定期送信する）
+    let ping_interval = request.ping_interval_ms.filter(|ms| *ms > 0).map(Duration::from_millis);
+    let receiver = {
+        let writer = Arc::clone(&writer);
+        let connection_id = connection_id.clone();
+        let messages = Arc::clone(&messages);
+        let encoding = request.encoding;
+        async move {
+            let pinger = async {
+                match ping_interval {
+                    Some(interval) => send_pings(writer, connection_id.clone(), interval).await,
+                    None => std::future::pending().await,
+                }
+            };
+            tokio::select! {
+                result = handle_websocket_receiver(stream, connection_id.clone(), encoding, messages) => result,
+                result = pinger => result,
+            }
+        }
+    };
+
+    let connection_data = ConnectionData {
+        info: connection.clone(),
+        writer,
+        // WebSocketはメッセージ単位で送受信するため区切りは付けない
+        framing: Framing::Raw,
+        // テキストフレームは仕様上UTF-8のため、送信時の変換もUTF-8で行う
+        encoding: match request.send_frame_type {
+            FrameType::Text => TextEncoding::Utf8,
+            FrameType::Binary => request.encoding,
+        },
+        messages,
+        receiver_handle: None,
+        tls_info,
+    };
+    register_connection(connection_data, receiver).await;
+
+    Ok(TcpConnectionResult {
+        success: true,
+        connection: Some(connection),
+        error: None,
+    })
+}
+
+/// WebSocket接続のサブプロトコルとPing/Pongの計測値を取得
+#[tauri::command]
+pub async fn get_websocket_info(connection_id: String) -> Result<WebSocketInfo, TcpError> {
+    websocket_info()
+        .lock()
+        .await
+        .get(&connection_id)
+        .cloned()
+        .ok_or_else(|| {
+            TcpError::ConnectionNotFound(format!("WebSocket connection with ID {} not found", connection_id))
+        })
+}
+
+/// 切断された接続の計測値を破棄する
+pub(crate) async fn forget_connection(connection_id: &str) {
+    websocket_info().lock().await.remove(connection_id);
+}
+
+/// Pingを定期送信する（送信に失敗した時点で終了）
+async fn send_pings(writer: Arc<Mutex<ConnectionWriter>>, connection_id: String, interval: Duration) -> io::Result<()> {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await;
+
+    loop {
+        ticker.tick().await;
+        let payload = Bytes::copy_from_slice(&now_micros().to_be_bytes());
+        let result = match &mut *writer.lock().await {
+            ConnectionWriter::WebSocket(ws) => ws.send_message(Message::Ping(payload)).await,
+            _ => return Ok(()),
+        };
+        if let Err(e) = result {
+            log::warn!("Failed to send ping on connection {}: {}", connection_id, e);
+            return Err(e);
+        }
+
+        if let Some(info) = websocket_info().lock().await.get_mut(&connection_id) {
+            info.pings_sent += 1;
+        }
+        emit_ping_pong(&connection_id, PingPongKind::PingSent, None);
+    }
+}
+
+async fn handle_websocket_receiver(
+    mut stream: SplitStream<WebSocketStream<BoxedStream>>,
+    connection_id: String,
+    encoding: TextEncoding,
+    messages: SharedMessageStore,
+) -> io::Result<()> {
+    let client_addr = format!("Connection {}", connection_id);
+
+    while let Some(result) = stream.next().await {
+        let message = match result {
+            Ok(message) => message,
+            // クローズハンドシェイクなしにTCPが閉じられた場合は相手からの切断として扱う
+            Err(WsError::ConnectionClosed | WsError::Protocol(ProtocolError::ResetWithoutClosingHandshake)) => {
+                return Ok(());
+            }
+            Err(WsError::Io(e)) => {
+                log::error!("Error reading from WebSocket connection {}: {}", connection_id, e);
+                return Err(e);
+            }
+            Err(e) => {
+                log::error!("Error reading from WebSocket connection {}: {}", connection_id, e);
+                return Err(io::Error::other(e));
+            }
+        };
+
+        match message {
+            Message::Text(text) => {
+                // テキストフレームは仕様上常にUTF-8
+                let mut received_msg =
+                    TcpReceivedMessage::from_frame(text.as_bytes().to_vec(), client_addr.clone(), TextEncoding::Utf8);
+                received_msg.frame_type = Some(FrameType::Text);
+                // WebSocketのフレームヘッダーとマスクは記録しない（回線上のバイト列はペイロードと同じ扱い）
+                deliver_connection_message(&connection_id, &messages, received_msg, None).await;
+            }
+            Message::Binary(data) => {
+                let mut received_msg = TcpReceivedMessage::from_frame(data.to_vec(), client_addr.clone(), encoding);
+                received_msg.frame_type = Some(FrameType::Binary);
+                deliver_connection_message(&connection_id, &messages, received_msg, None).await;
+            }
+            Message::Ping(_) => {
+                // Pongはtungsteniteが自動で返信する
+                if let Some(info) = websocket_info().lock().await.get_mut(&connection_id) {
+                    info.pings_received += 1;
+                    info.last_ping_received_at = Some(Utc::now().to_rfc3339());
+                }
+                emit_ping_pong(&connection_id, PingPongKind::PingReceived, None);
+            }
+            Message::Pong(payload) => {
+                let rtt_ms = <[u8; 8]>::try_from(payload.as_ref())
+                    .ok()
+                    .map(|sent| now_micros().saturating_sub(u64::from_be_bytes(sent)) as f64 / 1000.0);
+                if let Some(info) = websocket_info().lock().await.get_mut(&connection_id) {
+                    info.pongs_received += 1;
+                    info.last_pong_at = Some(Utc::now().to_rfc3339());
+                    if rtt_ms.is_some() {
+                        info.last_rtt_ms = rtt_ms;
+                    }
+                }
+                emit_ping_pong(&connection_id, PingPongKind::PongReceived, rtt_ms);
+            }
+            Message::Close(frame) => {
+                log::info!("WebSocket connection {} closed: {:?}", connection_id, frame);
+                break;
+            }
+            Message::Frame(_) => {}
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::PayloadFormat;
+    use crate::tcp::{disconnect_tcp, send_tcp_message_on_connection, TcpMessageOnConnection};
+    use crate::test_support::{poll_until, wait_for_messages};
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+
+    /// カスタムヘッダーを確認し、最初に提示されたサブプロトコルを選択する
+    #[allow(clippy::result_large_err)]
+    fn select_subprotocol(request: &Request, mut response: Response) -> Result<Response, ErrorResponse> {
+        assert_eq!(request.headers().get("x-device-token").unwrap(), "secret");
+        response
+            .headers_mut()
+            .insert("Sec-WebSocket-Protocol", HeaderValue::from_static("telemetry.v1"));
+        Ok(response)
+    }
+
+    /// 受信したテキストをそのまま、テキスト以外はバイナリで返すエコーサーバー
+    async fn start_echo_server() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (stream, _) = listener.accept().await.unwrap();
+            let mut ws = tokio_tungstenite::accept_hdr_async(stream, select_subprotocol).await.unwrap();
+            while let Some(Ok(message)) = ws.next().await {
+                match message {
+                    Message::Text(text) => {
+                        ws.send(Message::Text(text)).await.unwrap();
+                        ws.send(Message::binary(vec![0x00, 0xFF])).await.unwrap();
+                    }
+                    Message::Close(_) => break,
+                    _ => {}
+                }
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn test_websocket_text_binary_and_ping() {
+        let port = start_echo_server().await;
+        let request = WebSocketConnectionRequest {
+            url: format!("ws://127.0.0.1:{}/stream", port),
+            headers: HashMap::from([("X-Device-Token".to_string(), "secret".to_string())]),
+            subprotocols: vec!["telemetry.v1".to_string(), "telemetry.v0".to_string()],
+            tls: None,
+            ping_interval_ms: Some(20),
+            send_frame_type: FrameType::Text,
+            encoding: TextEncoding::ShiftJis,
+            retention: Default::default(),
+        };
+        let connection = connect_websocket(request).await.unwrap().connection.unwrap();
+        assert_eq!(connection.transport, Transport::WebSocket);
+
+        send_tcp_message_on_connection(TcpMessageOnConnection {
+            connection_id: connection.id.clone(),
+            message: "こんにちは".to_string(),
+            payload_format: PayloadFormat::Text,
+        })
+        .await
+        .unwrap();
+
+        let messages = wait_for_messages(&connection.id, 2).await;
+        let info = poll_until(
+            || async { get_websocket_info(connection.id.clone()).await.unwrap() },
+            |info| info.last_rtt_ms.is_some(),
+        )
+        .await;
+        assert_eq!(info.subprotocol.as_deref(), Some("telemetry.v1"));
+        assert!(info.pongs_received >= 1);
+        assert!(info.last_rtt_ms.is_some());
+
+        assert_eq!(messages.len(), 2);
+        // 接続のエンコーディングがShift_JISでもテキストフレームはUTF-8で送受信する
+        assert_eq!(messages[0].message, "こんにちは");
+        assert_eq!(messages[0].frame_type, Some(FrameType::Text));
+        assert_eq!(messages[1].raw, vec![0x00, 0xFF]);
+        assert_eq!(messages[1].frame_type, Some(FrameType::Binary));
+
+        // 両端のアドレスを記録しているためPCAPNGに書き出せる
+        disconnect_tcp(connection.id.clone()).await.unwrap();
+        let path = std::env::temp_dir().join(format!("ws-pcapng-test-{}.pcapng", std::process::id()));
+        let result = crate::pcapng::export_connection_pcapng(connection.id, path.to_string_lossy().into_owned())
+            .await
+            .unwrap();
+        assert_eq!((result.frames, result.skipped), (3, 0));
+        std::fs::remove_file(&path).ok();
+    }
+}
@@ -199,14 +199,35 @@ describe('TcpClient', () => {
 
 	describe('stopServer', () => {
 		it('サーバー停止が成功する', async () => {
-			const mockResponse = 'Server stopped successfully';
+			const mockResponse = {
+				success: true,
+				message: 'TCP server stopped',
+				was_running: true,
+				closed_clients: 0,
+				timed_out_clients: 0
+			};
 			mockInvoke.mockResolvedValue(mockResponse);
 
 			const result = await TcpClient.stopServer();
 
 			expect(result.success).toBe(true);
-			expect(result.message).toBe(mockResponse);
-			expect(mockInvoke).toHaveBeenCalledWith('stop_tcp_server');
+			expect(result.message).toBe('TCP server stopped');
+			expect(mockInvoke).toHaveBeenCalledWith('stop_tcp_server', { serverId: null, options: null });
+		});
+
+		it('停止オプションとサーバーIDを渡せる', async () => {
+			mockInvoke.mockResolvedValue({
+				success: true,
+				message: 'TCP server stopped',
+				was_running: true,
+				closed_clients: 2,
+				timed_out_clients: 0
+			});
+
+			const options = { mode: 'graceful' as const, goodbye_message: 'bye', timeout_ms: 1000 };
+			await TcpClient.stopServer(options, 'device-a');
+
+			expect(mockInvoke).toHaveBeenCalledWith('stop_tcp_server', { serverId: 'device-a', options });
 		});
 	});
 
@@ -6,9 +6,25 @@ import type {
 	TcpReceiveResult,
 	TcpConnectionRequest,
 	TcpConnectionResult,
-	TcpMessageOnConnection
+	TcpMessageOnConnection,
+	TcpServerStopOptions,
+	TcpServerStopResult,
+	TcpCommandError
 } from './types/tcp.js';
 
+/**
+ * コマンドのエラーから表示用のメッセージを取り出します
+ */
+function errorMessage(error: unknown): string {
+	if (error instanceof Error) {
+		return error.message;
+	}
+	if (typeof error === 'object' && error !== null && 'message' in error) {
+		return (error as TcpCommandError).message;
+	}
+	return '不明なエラーが発生しました';
+}
+
 export class TcpClient {
 	/**
 	 * TCPメッセージを送信します（CRデリミタ付き）
@@ -58,7 +74,7 @@ export class TcpClient {
 			return {
 				success: false,
 				message: '',
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -117,28 +133,31 @@ export class TcpClient {
 			return {
 				success: false,
 				message: '',
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
 
 	/**
-	 * TCPサーバーを停止します
+	 * TCPサーバーを停止します（IDを省略した場合は既定のサーバー）
 	 */
-	static async stopServer(): Promise<TcpSendResult> {
+	static async stopServer(options?: TcpServerStopOptions, serverId?: string): Promise<TcpSendResult> {
 		try {
-			const result = await invoke<string>('stop_tcp_server');
+			const result = await invoke<TcpServerStopResult>('stop_tcp_server', {
+				serverId: serverId ?? null,
+				options: options ?? null
+			});
 
 			return {
-				success: true,
-				message: result
+				success: result.success,
+				message: result.message
 			};
 		} catch (error) {
 			console.error('TCPサーバー停止エラー:', error);
 			return {
 				success: false,
 				message: '',
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -155,7 +174,7 @@ export class TcpClient {
 			return {
 				success: false,
 				messages: [],
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -188,7 +207,7 @@ export class TcpClient {
 			return {
 				success: false,
 				connection: undefined,
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -209,7 +228,7 @@ export class TcpClient {
 			return {
 				success: false,
 				message: '',
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -245,7 +264,7 @@ export class TcpClient {
 			return {
 				success: false,
 				message: '',
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
@@ -264,7 +283,7 @@ export class TcpClient {
 			return {
 				success: false,
 				messages: [],
-				error: error instanceof Error ? error.message : '不明なエラーが発生しました'
+				error: errorMessage(error)
 			};
 		}
 	}
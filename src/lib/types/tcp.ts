@@ -1,7 +1,79 @@
+export type Endianness = 'big' | 'little';
+
+export type Framing =
+	| { type: 'line' }
+	| { type: 'cr' }
+	| { type: 'lf' }
+	| { type: 'cr_lf' }
+	| { type: 'stx_etx' }
+	| { type: 'delimiter'; delimiter: number[] }
+	| { type: 'length_prefixed'; width: 1 | 2 | 4; endianness?: Endianness }
+	| { type: 'fixed_length'; length: number }
+	| { type: 'slip' }
+	| { type: 'cobs' }
+	| { type: 'raw' };
+
+export type TextEncoding = 'utf8' | 'shift_jis' | 'euc_jp' | 'iso_8859_1' | 'utf16_le' | 'utf16_be';
+
+export type PayloadFormat = 'text' | 'hex' | 'escaped' | 'base64';
+
+export interface PayloadError {
+	position: number; // 入力文字列中の文字位置
+	character?: string;
+	reason: string;
+}
+
+export type TcpErrorKind =
+	| 'connection_failed'
+	| 'send_failed'
+	| 'invalid_address'
+	| 'server_start_failed'
+	| 'connection_not_found'
+	| 'invalid_framing'
+	| 'invalid_payload'
+	| 'tls_failed'
+	| 'connect_timeout'
+	| 'read_timeout'
+	| 'write_timeout'
+	| 'idle_timeout'
+	| 'unsupported'
+	| 'storage_failed'
+	| 'invalid_query';
+
+// Tauriコマンドが返すエラー
+export interface TcpCommandError {
+	kind: TcpErrorKind;
+	message: string;
+	detail?: PayloadError; // kindがinvalid_payloadのときのみ
+}
+
+export interface PayloadPreview {
+	length: number;
+	hex: string;
+}
+
 export interface TcpMessage {
 	host: string;
 	port: number;
 	message: string;
+	payload_format?: PayloadFormat;
+	encoding?: TextEncoding;
+	framing?: Framing; // 省略時はCRデリミタ
+	timeouts?: TcpSendTimeouts;
+}
+
+// 単発送信のタイムアウト（省略した項目は無制限、ミリ秒）
+export interface TcpSendTimeouts {
+	connect_ms?: number | null;
+	write_ms?: number | null;
+}
+
+// 省略した項目は無制限（ミリ秒）
+export interface TcpTimeouts {
+	connect_ms?: number | null; // TLSの場合はハンドシェイクまで
+	read_ms?: number | null; // 受信がなければ切断
+	write_ms?: number | null; // 送信が進まなければ失敗
+	idle_ms?: number | null; // 送受信ともになければ切断
 }
 
 export interface TcpSendResult {
@@ -21,27 +93,101 @@ export interface TcpConnectionInfo {
 
 export interface TcpReceivedMessage {
 	message: string;
+	lossy: boolean; // デコード時に置換文字が発生したかどうか
 	timestamp: string;
 	client_addr: string;
+	hex: string; // 生バイト列の16進表記
+	base64: string;
+	frame_type?: FrameType; // WebSocketで受信した場合のみ
+	seq: number; // 全接続・サーバーで単調増加する通し番号
 }
 
 export interface TcpReceiveResult {
 	success: boolean;
 	messages: TcpReceivedMessage[];
 	error?: string;
+	dropped?: number; // 保持ポリシーにより破棄したメッセージの累計
 }
 
+export interface TlsServerOptions {
+	cert_path?: string;
+	key_path?: string;
+	self_signed?: boolean; // 証明書未指定時に自己署名証明書を生成
+	client_ca_path?: string;
+	require_client_cert?: boolean;
+	alpn_protocols?: string[];
+}
+
+// 受け付けたクライアントにはタイムアウトを適用しない
 export interface TcpServerConfig {
+	id?: string; // 省略時は"default"（同じIDのサーバーは再起動される）
 	host: string;
 	port: number;
+	framing?: Framing;
+	encoding?: TextEncoding;
+	tls?: TlsServerOptions;
+	socket_options?: SocketOptions; // local_address/local_portは無視
+	retention?: RetentionPolicy;
+}
+
+// 省略した項目はOSの既定値
+export interface SocketOptions {
+	nodelay?: boolean | null; // TCP_NODELAY
+	keepalive?: KeepaliveOptions | null;
+	recv_buffer_size?: number | null; // SO_RCVBUF
+	send_buffer_size?: number | null; // SO_SNDBUF
+	linger_secs?: number | null; // SO_LINGER（0でクローズ時にRST）
+	local_address?: string | null; // 接続前にバインド（クライアントのみ）
+	local_port?: number | null;
+}
+
+export interface KeepaliveOptions {
+	time_secs?: number | null;
+	interval_secs?: number | null;
+	retries?: number | null; // Windowsでは無視
 }
 
+export type Transport = 'tcp' | 'udp' | 'multicast' | 'unix' | 'websocket' | 'serial';
+
+export type FrameType = 'text' | 'binary';
+
+export type ConnectionState =
+	| 'connecting'
+	| 'connected'
+	| 'reconnecting'
+	| 'remote_closed'
+	| 'error'
+	| 'closed';
+
+// fin: 相手が正常に切断, reset: 相手がRSTで切断, local: こちらから切断
+// *_timeout: 各タイムアウトによる切断（idle_timeoutはこちらから切断）
+export type CloseReason =
+	| 'fin'
+	| 'reset'
+	| 'error'
+	| 'local'
+	| 'connect_timeout'
+	| 'read_timeout'
+	| 'write_timeout'
+	| 'idle_timeout';
+
 export interface TcpConnection {
 	id: string;
 	host: string;
 	port: number;
 	connected: boolean;
 	connectedAt: string;
+	transport?: Transport;
+	state?: ConnectionState;
+	close_reason?: CloseReason | null;
+	error?: string | null;
+	closed_at?: string | null;
+	reconnects?: number;
+}
+
+export interface ConnectionStateEvent {
+	connection: TcpConnection;
+	timestamp: string;
 }
 
 export interface TcpConnectionResult {
@@ -50,12 +196,377 @@ export interface TcpConnectionResult {
 	error?: string;
 }
 
+export interface TlsClientOptions {
+	server_name?: string; // SNI（省略時は接続先ホスト）
+	ca_bundle_path?: string;
+	accept_invalid_certs?: boolean;
+	alpn_protocols?: string[];
+}
+
+export interface PeerCertificate {
+	subject: string;
+	issuer: string;
+	serial: string;
+	not_before: string;
+	not_after: string;
+	sha256_fingerprint: string;
+	der_base64: string;
+}
+
+export interface TlsSessionInfo {
+	server_name: string;
+	protocol_version: string;
+	cipher_suite: string;
+	alpn_protocol?: string;
+	peer_subject?: string; // 相手のエンドエンティティ証明書のサブジェクト
+	peer_certificates: PeerCertificate[];
+}
+
 export interface TcpConnectionRequest {
 	host: string;
 	port: number;
+	framing?: Framing;
+	encoding?: TextEncoding;
+	tls?: TlsClientOptions;
+	reconnect?: ReconnectPolicy;
+	timeouts?: TcpTimeouts;
+	socket_options?: SocketOptions;
+	retention?: RetentionPolicy;
+}
+
+// 相手から切断されたときの自動再接続（指数バックオフ）
+export interface ReconnectPolicy {
+	initial_delay_ms?: number; // デフォルト500
+	max_delay_ms?: number; // デフォルト30000
+	multiplier?: number; // デフォルト2.0
+	jitter?: number; // 0.0〜1.0、デフォルト0.2
+	max_attempts?: number | null; // 省略時は無制限
+	init_script?: string[]; // 接続直後と再接続のたびに送信
+	init_payload_format?: PayloadFormat;
+}
+
+// tcp_reconnect_attemptイベント
+export interface ReconnectAttemptEvent {
+	connection_id: string;
+	attempt: number;
+	max_attempts: number | null;
+	delay_ms: number;
+	success: boolean;
+	error: string | null;
+	timestamp: string;
+}
+
+// コネクションがないためタイムアウトは設定できない
+export interface UdpConnectionRequest {
+	local_host?: string;
+	local_port?: number; // 0の場合は自動割り当て
+	remote_host?: string; // 省略時は最後に受信した相手へ返信
+	remote_port?: number;
+	framing?: Framing; // 既定はraw（データグラム単位）
+	encoding?: TextEncoding;
+	retention?: RetentionPolicy;
+}
+
+// タイムアウトはUDPと同様に未対応
+export interface MulticastJoinRequest {
+	group: string;
+	port: number;
+	interface?: string; // IPv4はアドレス、IPv6はインターフェース番号
+	ttl?: number;
+	multicast_loop?: boolean;
+	framing?: Framing;
+	encoding?: TextEncoding;
+	retention?: RetentionPolicy;
+}
+
+// 受信・送信・アイドルのタイムアウトは未対応
+export interface UnixConnectionRequest {
+	path: string; // ソケットファイルのパス
+	framing?: Framing;
+	encoding?: TextEncoding;
+	retention?: RetentionPolicy;
+}
+
+// TCPサーバーと同じくクライアントのタイムアウトはない
+export interface UnixServerConfig {
+	id?: string;
+	path: string;
+	framing?: Framing;
+	encoding?: TextEncoding;
+	retention?: RetentionPolicy;
+}
+
+export type SerialParity = 'none' | 'odd' | 'even';
+export type SerialStopBits = 'one' | 'two';
+export type SerialFlowControl = 'none' | 'software' | 'hardware';
+
+// 受信・送信・アイドルのタイムアウトは未対応
+export interface SerialConnectionRequest {
+	path: string; // 例: "/dev/ttyUSB0", "COM3"
+	baud_rate?: number; // 既定は9600
+	data_bits?: number; // 5〜8（既定は8）
+	parity?: SerialParity;
+	stop_bits?: SerialStopBits;
+	flow_control?: SerialFlowControl;
+	dtr?: boolean;
+	rts?: boolean;
+	framing?: Framing;
+	encoding?: TextEncoding;
+	retention?: RetentionPolicy;
+}
+
+export interface SerialLineStatus {
+	cts: boolean;
+	dsr: boolean;
+	ring: boolean;
+	carrier_detect: boolean;
+}
+
+export interface SerialPortEntry {
+	path: string;
+	port_type: 'usb' | 'pci' | 'bluetooth' | 'unknown';
+	vid: number | null;
+	pid: number | null;
+	serial_number: string | null;
+	manufacturer: string | null;
+	product: string | null;
+}
+
+// タイムアウトはないため、無応答はPing/Pongで確認する
+export interface WebSocketConnectionRequest {
+	url: string; // ws:// または wss://
+	headers?: Record<string, string>;
+	subprotocols?: string[];
+	tls?: TlsClientOptions;
+	ping_interval_ms?: number;
+	send_frame_type?: FrameType;
+	encoding?: TextEncoding; // バイナリフレームのみに適用（テキストフレームは常にUTF-8）
+	retention?: RetentionPolicy;
+}
+
+export interface WebSocketInfo {
+	url: string;
+	subprotocol: string | null;
+	pings_sent: number;
+	pongs_received: number;
+	pings_received: number;
+	last_rtt_ms: number | null;
+	last_pong_at: string | null;
+	last_ping_received_at: string | null;
+}
+
+export interface WebSocketPingPongEvent {
+	connection_id: string;
+	kind: 'ping_sent' | 'pong_received' | 'ping_received';
+	rtt_ms: number | null;
+	timestamp: string;
+}
+
+export interface TcpServerInfo {
+	id: string;
+	address: string; // Unixソケットの場合はパス
+	transport: Transport;
+	tls: boolean;
+	started_at: string;
+	client_count: number;
+	message_count: number;
+}
+
+export type ServerStopMode = 'abort' | 'graceful';
+
+export interface TcpServerStopOptions {
+	mode?: ServerStopMode; // 既定はabort（即座に切断）
+	goodbye_message?: string; // gracefulモードのみ
+	payload_format?: PayloadFormat;
+	timeout_ms?: number; // gracefulモードでクライアントの切断を待つ時間（既定3000）
+}
+
+export interface TcpServerStopResult {
+	success: boolean;
+	message: string;
+	was_running: boolean;
+	closed_clients: number;
+	timed_out_clients: number; // タイムアウトにより強制的に中断したクライアント数
+}
+
+export interface ServerClientInfo {
+	id: string;
+	server_id: string;
+	client_addr: string;
+	connected_at: string;
+	tls?: TlsSessionInfo; // TLSサーバーの場合のみ（mTLSのクライアント証明書を含む）
+	// Unixソケットサーバーの場合の相手プロセスの資格情報
+	uid?: number;
+	gid?: number;
+	pid?: number;
+}
+
+export interface ServerMessageReceivedEvent {
+	server_id: string;
+	client_id: string;
+	message: TcpReceivedMessage;
+}
+
+export interface ServerClientDisconnectedEvent {
+	client: ServerClientInfo;
+	reason: 'closed' | 'error' | 'kicked' | 'server_stopped';
+	error: string | null;
+}
+
+export interface ServerClientMessage {
+	client_id: string;
+	message: string;
+	payload_format?: PayloadFormat;
+}
+
+export interface ServerBroadcastMessage {
+	server_id?: string; // 省略時は"default"
+	message: string;
+	payload_format?: PayloadFormat;
+}
+
+export interface ServerBroadcastFailure {
+	client_id: string;
+	error: string;
+}
+
+export interface ServerBroadcastResult {
+	success: boolean;
+	sent: number; // 送信できたクライアント数
+	failures: ServerBroadcastFailure[];
+	timestamp: string;
 }
 
 export interface TcpMessageOnConnection {
 	connection_id: string;
 	message: string;
+	payload_format?: PayloadFormat;
+}
+
+// get_tcp_info / tcp_info_sampledイベント（Linuxのみ）
+export interface TcpInfoSample {
+	timestamp: string;
+	rtt_us: number; // 平滑化RTT
+	rtt_var_us: number;
+	retransmits: number; // 現在のタイムアウトでの連続再送回数
+	total_retransmits: number;
+	snd_cwnd: number; // 輻輳ウィンドウ（セグメント数）
+	unacked: number;
+	lost: number;
+}
+
+export interface TcpInfoSamplingOptions {
+	interval_ms?: number | null; // デフォルト1000、最小100
+	max_samples?: number | null; // デフォルト600
+}
+
+export interface TcpInfoSampledEvent {
+	id: string; // 接続IDまたはサーバーのクライアントID
+	sample: TcpInfoSample;
+}
+
+export type TranscriptDirection = 'tx' | 'rx' | 'system';
+
+// 送受信記録（切断後もremove_connection_transcriptで削除するか保持ポリシーで破棄されるまで残る）
+export interface TranscriptEntry {
+	seq: number; // 記録順の通し番号
+	direction: TranscriptDirection;
+	timestamp: string;
+	bytes: number; // systemは0
+	text: string; // デコードしたデータ、またはsystemの説明
+	base64: string;
+	client_id?: string; // サーバーの記録の場合の相手クライアント
+	peer?: string;
+}
+
+// 受信メッセージと送受信記録の保持ポリシー（いずれかを超えたら古いものから破棄、nullで無制限）
+export interface RetentionPolicy {
+	max_messages?: number | null; // デフォルト10000
+	max_bytes?: number | null; // デフォルト64MiB
+	max_age_ms?: number | null;
+}
+
+export interface MessageQuery {
+	after_seq?: number | null; // 前ページのnext_cursor
+	until_seq?: number | null;
+	limit?: number | null; // デフォルト500、最大5000
+}
+
+export interface MessagePage {
+	messages: TcpReceivedMessage[];
+	next_cursor: number | null;
+	has_more: boolean;
+	dropped: number;
+	retained: number;
+	retained_bytes: number;
+}
+
+export type SessionLogFormat = 'json_lines' | 'text';
+
+// アプリのデータディレクトリ配下に送受信を記録する
+export interface SessionLogOptions {
+	format?: SessionLogFormat; // デフォルトjson_lines（.jsonl）、textは.log
+	max_file_bytes?: number | null; // デフォルト10MiB、nullで無制限
+	rotate_interval_secs?: number | null;
+	retention_days?: number | null; // デフォルト30日、nullで無期限（同じ接続・サーバーのログのみ削除）
+}
+
+export interface SessionLogFile {
+	name: string;
+	size: number;
+	modified: string;
+	active: boolean; // 書き込み中
+}
+
+export interface SessionLogContent {
+	name: string;
+	lines: string[];
+	next_offset: number | null;
+}
+
+// SQLiteアーカイブの検索条件（省略した条件では絞り込まない）
+export interface ArchiveQuery {
+	text?: string | null; // 大文字・小文字を区別しない部分一致
+	regex?: string | null;
+	connection_id?: string | null;
+	server_id?: string | null;
+	direction?: TranscriptDirection | null;
+	peer?: string | null;
+	from?: string | null; // RFC3339（含む）
+	to?: string | null; // RFC3339（含まない）
+	cursor?: number | null; // 前ページのnext_cursor
+	limit?: number | null; // デフォルト100、最大1000
+}
+
+export interface ArchivedFrame {
+	id: number;
+	connection_id?: string;
+	server_id?: string;
+	client_id?: string;
+	peer?: string;
+	direction: TranscriptDirection;
+	seq: number;
+	timestamp: string;
+	bytes: number;
+	text: string;
+	base64: string;
+}
+
+export interface ArchiveSearchResult {
+	frames: ArchivedFrame[];
+	next_cursor: number | null;
+}
+
+export interface ArchiveStatus {
+	enabled: boolean; // デフォルトtrue（アプリの起動ごとに戻る）
+	retention_days: number | null;
+	dropped: number; // 書き込みが追いつかず破棄したフレーム数
+}
+
+// 送受信記録のPCAPNG書き出し結果（区切りなどを含む回線上のバイト列を書き出し、TCP/IPヘッダは合成。WebSocketはペイロードのみ）
+export interface PcapExportResult {
+	path: string;
+	packets: number; // 合成したハンドシェイクを含む
+	frames: number;
+	skipped: number; // 両端のアドレスが分からないフレーム
 }